@@ -1,5 +1,14 @@
-use clap::Parser;
-use uuid::Uuid;
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::parser::ValueSource;
+use clap::{ArgGroup, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng, TryRngCore};
+use serde::{Deserialize, Serialize};
+use uuid::{ClockSequence, Uuid};
 
 /// Language setting for internationalization
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -14,31 +23,86 @@ impl Language {
     fn detect() -> Self {
         // Check LANG, LC_ALL, LC_MESSAGES environment variables
         for var in ["LANG", "LC_ALL", "LC_MESSAGES"] {
-            if let Ok(lang) = std::env::var(var) {
-                if lang.to_lowercase().starts_with("zh") {
-                    return Language::Chinese;
-                }
+            if let Ok(lang) = std::env::var(var)
+                && lang.to_lowercase().starts_with("zh")
+            {
+                return Language::Chinese;
             }
         }
         // Default to English if no Chinese locale detected or on error
         Language::English
     }
+
+    /// Parse a --lang or ZUUID_LANG value: en or zh, also as a locale such as zh_CN.UTF-8
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.to_lowercase();
+        if s.starts_with("zh") || s == "chinese" {
+            Some(Language::Chinese)
+        } else if s.starts_with("en") {
+            Some(Language::English)
+        } else {
+            None
+        }
+    }
+
+    /// The language for this run: --lang, then ZUUID_LANG, then the locale variables
+    /// The arguments are scanned before clap runs, so that its own errors are localized too
+    fn resolve(args: &[String]) -> Self {
+        let from_args = args.iter().enumerate().rev().find_map(|(i, arg)| match arg.strip_prefix("--lang") {
+            Some(value) if value.starts_with('=') => Some(value[1..].to_string()),
+            Some("") => args.get(i + 1).cloned(),
+            _ => None,
+        });
+        from_args
+            .or_else(|| std::env::var("ZUUID_LANG").ok())
+            .and_then(|value| Language::parse(&value))
+            .unwrap_or_else(Language::detect)
+    }
 }
 
+/// Parse --lang for clap, which validates it and lists it in --help; `Language::resolve` applies it
+fn parse_language(s: &str) -> Result<Language, String> {
+    Language::parse(s).ok_or_else(|| Messages::global().invalid_language(s))
+}
+
+/// The process-wide messages, set once by `main`
+static MESSAGES: std::sync::OnceLock<Messages> = std::sync::OnceLock::new();
+
 /// Get localized messages based on language
+#[derive(Clone, Copy, Debug)]
 struct Messages {
     lang: Language,
 }
 
 impl Messages {
-    fn new(lang: Language) -> Self {
+    const fn new(lang: Language) -> Self {
         Self { lang }
     }
 
+    /// Fix the language for the rest of the process; only the first call has an effect
+    fn init(lang: Language) {
+        let _ = MESSAGES.set(Messages::new(lang));
+    }
+
+    /// Messages in the language chosen by `init`, or English before that (as in unit tests,
+    /// which construct `Messages::new` for the language they check instead)
+    /// Only for clap value parsers and `FromStr`/`Display` impls, whose signatures cannot take one;
+    /// everything else is handed `&Messages` by `main`
+    fn global() -> Messages {
+        *MESSAGES.get_or_init(|| Messages::new(Language::English))
+    }
+
+    fn invalid_language(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid language: {}. Valid values: en, zh", value),
+            Language::Chinese => format!("无效的语言：{}。有效值：en、zh", value),
+        }
+    }
+
     fn conflict_warning(&self) -> &'static str {
         match self.lang {
-            Language::English => "Warning: Both -f (full) and -s (simple) format flags specified.",
-            Language::Chinese => "警告：同时指定了 -f（完整）和 -s（简单）格式标志。",
+            Language::English => "Both -f (full) and -s (simple) format flags specified.",
+            Language::Chinese => "同时指定了 -f（完整）和 -s（简单）格式标志。",
         }
     }
 
@@ -58,539 +122,10868 @@ impl Messages {
 
     fn invalid_version(&self, version: &str) -> String {
         match self.lang {
-            Language::English => format!("Invalid UUID version: {}. Valid values: 4, 7", version),
-            Language::Chinese => format!("无效的 UUID 版本：{}。有效值：4、7", version),
+            Language::English => format!(
+                "Invalid UUID version: {}. Valid values: 2, 3, 4, 5, 7, also written v7, uuid7 or uuidv7, or dce (2), random (4) and time (7)",
+                version
+            ),
+            Language::Chinese => {
+                format!("无效的 UUID 版本：{}。有效值：2、3、4、5、7，也可写作 v7、uuid7、uuidv7，或 dce（2）、random（4）、time（7）", version)
+            }
         }
     }
-}
 
-/// UUID version to generate
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
-enum UuidVersion {
-    /// Version 4: Random UUID (default)
-    #[default]
-    V4,
-    /// Version 7: Time-ordered UUID
-    V7,
-}
+    fn version_reason(&self) -> &'static str {
+        match self.lang {
+            Language::English => "use 2, 3, 4, 5 or 7, also written v7, uuid7 or uuidv7, or dce (2), random (4) and time (7)",
+            Language::Chinese => "请使用 2、3、4、5 或 7，也可写作 v7、uuid7、uuidv7，或 dce（2）、random（4）、time（7）",
+        }
+    }
 
-impl std::str::FromStr for UuidVersion {
-    type Err = String;
+    fn namespace_reason(&self) -> &'static str {
+        match self.lang {
+            Language::English => "use dns, url, oid, x500, nil, max, bluetooth or a UUID",
+            Language::Chinese => "请使用 dns、url、oid、x500、nil、max、bluetooth 或一个 UUID",
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lang = Language::detect();
-        let msgs = Messages::new(lang);
+    fn expected_one_of(&self, values: &str) -> String {
+        match self.lang {
+            Language::English => format!("expected one of {}", values),
+            Language::Chinese => format!("应为以下之一：{}", values),
+        }
+    }
 
-        match s.to_lowercase().as_str() {
-            "4" | "v4" => Ok(UuidVersion::V4),
-            "7" | "v7" => Ok(UuidVersion::V7),
-            _ => Err(msgs.invalid_version(s)),
+    fn input_error(&self, err: &InputError) -> String {
+        match self.lang {
+            Language::English => format!("Invalid value #{} for {}: \"{}\": {}", err.index, err.arg_name, err.value, err.reason),
+            Language::Chinese => format!("{} 的第 {} 个值无效：\"{}\"：{}", err.arg_name, err.index, err.value, err.reason),
         }
     }
-}
 
-impl std::fmt::Display for UuidVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            UuidVersion::V4 => write!(f, "v4"),
-            UuidVersion::V7 => write!(f, "v7"),
+    fn missing_namespace(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("UUID {} requires --namespace.", version),
+            Language::Chinese => format!("UUID {} 需要指定 --namespace。", version),
         }
     }
-}
 
-/// UUID generator tool
-#[derive(Parser)]
-#[command(name = "zuuid")]
-#[command(about = "Generate UUID v4/v7", long_about = None)]
-struct Cli {
-    /// UUID version to generate (4 or 7, default: 4)
-    #[arg(short = 'V', long = "uuid-version", visible_short_alias = 'v', default_value = "4")]
-    version: UuidVersion,
+    fn missing_name(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("UUID {} requires at least one --name.", version),
+            Language::Chinese => format!("UUID {} 至少需要一个 --name。", version),
+        }
+    }
 
-    /// Output UUID in uppercase
-    #[arg(short = 'U', long = "upper", visible_short_alias = 'u')]
-    uppercase: bool,
+    fn invalid_v7_precision(&self, precision: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid v7 precision: {}. Valid values: ms, sub-ms", precision),
+            Language::Chinese => format!("无效的 v7 精度：{}。可选值：ms、sub-ms", precision),
+        }
+    }
 
-    /// Output UUID without hyphens (32 chars)
-    #[arg(short = 's', long = "simple", visible_short_alias = 'S')]
-    simple: bool,
+    fn v7_precision_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--v7-precision only applies to UUID v7, not {}.", version),
+            Language::Chinese => format!("--v7-precision 仅适用于 UUID v7，不适用于 {}。", version),
+        }
+    }
 
-    /// Output UUID with hyphens in full format (36 chars)
-    #[arg(short = 'f', long = "full", visible_short_alias = 'F')]
-    full: bool,
+    fn timestamp_ms_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--timestamp-ms only applies to UUID v7, not {}.", version),
+            Language::Chinese => format!("--timestamp-ms 仅适用于 UUID v7，不适用于 {}。", version),
+        }
+    }
 
-    /// Number of UUIDs to generate (default: 1)
-    #[arg(short = 'n', long = "count", default_value = "1")]
-    count: usize,
-}
+    fn counter_start_needs_sub_ms(&self) -> &'static str {
+        match self.lang {
+            Language::English => "--counter-start seeds the sub-millisecond counter and needs --v7-precision sub-ms.",
+            Language::Chinese => "--counter-start 设置亚毫秒计数器的初始值，需要同时使用 --v7-precision sub-ms。",
+        }
+    }
 
-/// Determine format precedence based on argument order
-/// Returns (prefer_full, conflict_detected)
-fn determine_format_precedence() -> (bool, bool) {
-    let args: Vec<String> = std::env::args().collect();
+    fn name_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--name is only used by UUID v3 and v5, not {}.", version),
+            Language::Chinese => format!("--name 仅用于 UUID v3 和 v5，不适用于 {}。", version),
+        }
+    }
 
-    // Find positions of format-related flags
-    let mut full_pos = None;
-    let mut simple_pos = None;
+    fn dce_option_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--domain and --local-id only apply to UUID v2, not {}.", version),
+            Language::Chinese => format!("--domain 和 --local-id 仅适用于 UUID v2，不适用于 {}。", version),
+        }
+    }
 
-    for (i, arg) in args.iter().enumerate() {
-        // Check for combined flags like -fs, -sf, -fS, -Sf, etc.
-        if arg.starts_with('-') && arg.len() > 1 {
-            let flags = &arg[1..]; // Remove leading '-'
-            for (j, ch) in flags.chars().enumerate() {
-                match ch {
-                    'f' | 'F' => {
-                        if full_pos.is_none() {
-                            full_pos = Some(i * 1000 + j); // Use composite position
-                        }
-                    }
-                    's' | 'S' => {
-                        if simple_pos.is_none() {
-                            simple_pos = Some(i * 1000 + j);
-                        }
-                    }
-                    _ => {}
-                }
-            }
+    fn missing_local_id(&self, domain: DceDomain) -> String {
+        match self.lang {
+            Language::English => format!("UUID v2 in the {} domain requires --local-id.", domain),
+            Language::Chinese => format!("{} 域的 UUID v2 需要指定 --local-id。", domain),
         }
     }
 
-    match (full_pos, simple_pos) {
-        (Some(f), Some(s)) => {
-            // Both flags present, check order
-            let prefer_full = f < s;
-            (prefer_full, true)
+    fn per_line_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--per-line cannot be used with name-based UUID {}.", version),
+            Language::Chinese => format!("--per-line 不能用于基于名称的 UUID {}。", version),
         }
-        _ => (true, false), // Default to full, no conflict
     }
-}
 
-/// Print warning message in yellow
-fn print_conflict_warning(prefer_full: bool) {
-    let lang = Language::detect();
-    let msgs = Messages::new(lang);
+    fn duplicate_name(&self, name: &str) -> String {
+        match self.lang {
+            Language::English => format!("Name \"{}\" is listed more than once and yields the same UUID.", name),
+            Language::Chinese => format!("名称“{}”重复出现，会生成相同的 UUID。", name),
+        }
+    }
 
-    eprintln!("\x1b[33m{}\x1b[0m", msgs.conflict_warning());
-    if prefer_full {
-        eprintln!("\x1b[33m{}\x1b[0m", msgs.using_full());
-    } else {
-        eprintln!("\x1b[33m{}\x1b[0m", msgs.using_simple());
+    fn invalid_vanity_prefix(&self, prefix: &str) -> String {
+        match self.lang {
+            Language::English => format!(
+                "Invalid vanity prefix: {}. Use 1 to {} hex digits (0-9, a-f)",
+                prefix, MAX_VANITY_PREFIX
+            ),
+            Language::Chinese => format!("无效的靓号前缀：{}。请使用 1 到 {} 位十六进制数字（0-9、a-f）", prefix, MAX_VANITY_PREFIX),
+        }
     }
-}
 
-/// Generate a formatted UUID string based on the given options
-fn generate_uuid(version: UuidVersion, uppercase: bool, simple: bool, full: bool, prefer_full: bool) -> String {
-    let id = match version {
-        UuidVersion::V4 => Uuid::new_v4(),
-        UuidVersion::V7 => Uuid::now_v7(),
-    };
+    fn slow_vanity_search(&self, prefix: &str) -> String {
+        let attempts = 16u64.pow(prefix.len() as u32);
+        match self.lang {
+            Language::English => format!(
+                "Prefix \"{}\" needs about {} attempts on average; the search may take a long time.",
+                prefix, attempts
+            ),
+            Language::Chinese => format!("前缀“{}”平均需要约 {} 次尝试，搜索可能耗时很长。", prefix, attempts),
+        }
+    }
 
-    // Determine format based on flags and precedence
-    let output = if full && simple {
-        // Both flags set, use precedence
-        if prefer_full {
-            id.to_string()
-        } else {
-            id.as_simple().to_string()
+    fn vanity_progress(&self, attempts: u64, per_second: u64) -> String {
+        match self.lang {
+            Language::English => format!("Searched {} UUIDs ({}/s)", attempts, per_second),
+            Language::Chinese => format!("已搜索 {} 个 UUID（{}/秒）", attempts, per_second),
         }
-    } else if full {
-        id.to_string()
-    } else if simple {
-        id.as_simple().to_string()
-    } else {
-        id.to_string()
-    };
+    }
 
-    if uppercase {
-        output.to_uppercase()
-    } else {
-        output
+    fn phrase_requires_v4(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--from-phrase only derives UUID v4, not {}.", version),
+            Language::Chinese => format!("--from-phrase 仅生成 UUID v4，不支持 {}。", version),
+        }
     }
-}
 
-fn main() {
-    let (prefer_full, conflict) = determine_format_precedence();
-    let cli = Cli::parse();
+    fn phrase_not_secure(&self) -> &'static str {
+        match self.lang {
+            Language::English => {
+                "--from-phrase UUIDs are derived from the phrase and anyone who knows it can reproduce them; \
+                 do not use them as security-sensitive identifiers."
+            }
+            Language::Chinese => "--from-phrase 生成的 UUID 由短语推导，知道短语的人都能重现，不要用作安全敏感的标识符。",
+        }
+    }
 
-    if conflict {
-        print_conflict_warning(prefer_full);
+    fn target_partition_out_of_range(&self, target: u32, partitions: u32) -> String {
+        match self.lang {
+            Language::English => format!("--target-partition {} is out of range for {} partitions (0-{}).", target, partitions, partitions - 1),
+            Language::Chinese => format!("--target-partition {} 超出 {} 个分区的范围（0-{}）。", target, partitions, partitions - 1),
+        }
     }
 
-    for _ in 0..cli.count {
-        println!("{}", generate_uuid(cli.version, cli.uppercase, cli.simple, cli.full, prefer_full));
+    fn partition_not_found(&self, target: u32, attempts: u32) -> String {
+        match self.lang {
+            Language::English => format!("No UUID hashed to partition {} in {} attempts.", target, attempts),
+            Language::Chinese => format!("尝试 {} 次后仍未找到落在分区 {} 的 UUID。", attempts, target),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn vanity_requires_v4(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("--vanity only searches UUID v4, not {}.", version),
+            Language::Chinese => format!("--vanity 仅搜索 UUID v4，不支持 {}。", version),
+        }
+    }
 
-    #[test]
-    fn test_generate_default_format() {
-        let uuid = generate_uuid(UuidVersion::V4, false, false, false, false);
-        // Default format: lowercase with hyphens (8-4-4-4-12)
-        assert!(uuid.len() == 36);
-        assert!(uuid.contains('-'));
-        assert!(uuid.chars().filter(|&c| c == '-').count() == 4);
-        // Check that there are no uppercase letters
-        assert!(!uuid.chars().any(|c| c.is_ascii_uppercase()));
+    fn qr_single_only(&self) -> &'static str {
+        match self.lang {
+            Language::English => "--qr renders a single UUID; it cannot be combined with a count above 1 or several names.",
+            Language::Chinese => "--qr 只能渲染一个 UUID，不能与大于 1 的数量或多个名称一起使用。",
+        }
     }
 
-    #[test]
-    fn test_generate_uppercase_format() {
-        let uuid = generate_uuid(UuidVersion::V4, true, false, false, false);
-        // Uppercase format with hyphens
-        assert!(uuid.len() == 36);
-        assert!(uuid.contains('-'));
-        assert!(uuid.chars().filter(|&c| c == '-').count() == 4);
-        // Check that there are no lowercase letters
-        assert!(!uuid.chars().any(|c| c.is_ascii_lowercase()));
+    fn qr_error(&self, err: &str) -> String {
+        match self.lang {
+            Language::English => format!("Failed to build QR code: {}", err),
+            Language::Chinese => format!("生成二维码失败：{}", err),
+        }
     }
 
-    #[test]
-    fn test_generate_simple_format() {
-        let uuid = generate_uuid(UuidVersion::V4, false, true, false, false);
-        // Simple format: lowercase without hyphens
-        assert!(uuid.len() == 32);
-        assert!(!uuid.contains('-'));
-        // Check that there are no uppercase letters
-        assert!(!uuid.chars().any(|c| c.is_ascii_uppercase()));
+    fn invalid_version_number(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid UUID version: {}. Use a number from 1 to 8", value),
+            Language::Chinese => format!("无效的 UUID 版本：{}。请使用 1 到 8 之间的数字", value),
+        }
     }
 
-    #[test]
-    fn test_generate_uppercase_simple_format() {
-        let uuid = generate_uuid(UuidVersion::V4, true, true, false, false);
-        // Uppercase simple format
-        assert!(uuid.len() == 32);
-        assert!(!uuid.contains('-'));
-        // Check that there are no lowercase letters
-        assert!(!uuid.chars().any(|c| c.is_ascii_lowercase()));
+    fn version_mismatch(&self, input: &str, found: usize, expected: u8) -> String {
+        match self.lang {
+            Language::English => format!("{} is a version {} UUID, expected version {}", input, found, expected),
+            Language::Chinese => format!("{} 是版本 {} 的 UUID，要求版本 {}", input, found, expected),
+        }
     }
 
-    #[test]
-    fn test_uuid_v4_uniqueness() {
-        let uuid1 = generate_uuid(UuidVersion::V4, false, false, false, false);
-        let uuid2 = generate_uuid(UuidVersion::V4, false, false, false, false);
-        // Two UUIDs should be different (extremely unlikely to be the same)
-        assert_ne!(uuid1, uuid2);
+    fn invalid_uuid(&self, input: &str) -> String {
+        match self.lang {
+            Language::English => format!("Not a valid UUID: {}", input),
+            Language::Chinese => format!("不是有效的 UUID：{}", input),
+        }
     }
 
-    #[test]
-    fn test_uuid_v7_uniqueness() {
-        let uuid1 = generate_uuid(UuidVersion::V7, false, false, false, false);
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        let uuid2 = generate_uuid(UuidVersion::V7, false, false, false, false);
-        // Two V7 UUIDs should be different
-        assert_ne!(uuid1, uuid2);
+    fn not_a_uuid(&self) -> &'static str {
+        match self.lang {
+            Language::English => "not a UUID",
+            Language::Chinese => "不是有效的 UUID",
+        }
     }
 
-    #[test]
-    fn test_uuid_v7_ordered() {
-        let uuid1 = generate_uuid(UuidVersion::V7, false, false, false, false);
-        std::thread::sleep(std::time::Duration::from_millis(10));
-        let uuid2 = generate_uuid(UuidVersion::V7, false, false, false, false);
-        // V7 UUIDs should be time-ordered (uuid2 > uuid1)
-        assert!(uuid2 > uuid1);
+    fn invalid_inputs(&self, invalid: usize, total: usize) -> String {
+        match self.lang {
+            Language::English => format!("{} of {} inputs are not valid UUIDs", invalid, total),
+            Language::Chinese => format!("{} 个输入中有 {} 个不是有效的 UUID", total, invalid),
+        }
     }
 
-    #[test]
-    fn test_uuid_valid_format() {
-        let uuid = generate_uuid(UuidVersion::V4, false, false, false, false);
-        // Check standard UUID format: xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx
-        let parts: Vec<&str> = uuid.split('-').collect();
-        assert_eq!(parts.len(), 5);
-        assert_eq!(parts[0].len(), 8);
-        assert_eq!(parts[1].len(), 4);
-        assert_eq!(parts[2].len(), 4);
-        assert_eq!(parts[3].len(), 4);
-        assert_eq!(parts[4].len(), 12);
+    fn version_description(&self, id: &Uuid) -> String {
+        use uuid::Version;
+        let name = match (self.lang, id.get_version()) {
+            (Language::English, Some(Version::Nil)) => return "nil".to_string(),
+            (Language::English, Some(Version::Max)) => return "max".to_string(),
+            (Language::English, Some(Version::Mac)) => "time-based",
+            (Language::English, Some(Version::Dce)) => "DCE security",
+            (Language::English, Some(Version::Md5)) => "name-based, MD5",
+            (Language::English, Some(Version::Random)) => "random",
+            (Language::English, Some(Version::Sha1)) => "name-based, SHA-1",
+            (Language::English, Some(Version::SortMac)) => "reordered time",
+            (Language::English, Some(Version::SortRand)) => "Unix time-ordered",
+            (Language::English, Some(Version::Custom)) => "custom",
+            (Language::English, _) => "unknown",
+            (Language::Chinese, Some(Version::Nil)) => return "空 UUID".to_string(),
+            (Language::Chinese, Some(Version::Max)) => return "最大 UUID".to_string(),
+            (Language::Chinese, Some(Version::Mac)) => "基于时间",
+            (Language::Chinese, Some(Version::Dce)) => "DCE 安全",
+            (Language::Chinese, Some(Version::Md5)) => "基于名称，MD5",
+            (Language::Chinese, Some(Version::Random)) => "随机",
+            (Language::Chinese, Some(Version::Sha1)) => "基于名称，SHA-1",
+            (Language::Chinese, Some(Version::SortMac)) => "重排时间",
+            (Language::Chinese, Some(Version::SortRand)) => "Unix 时间有序",
+            (Language::Chinese, Some(Version::Custom)) => "自定义",
+            (Language::Chinese, _) => "未知",
+        };
+        format!("{} ({})", id.get_version_num(), name)
     }
 
-    #[test]
-    fn test_uuid_hex_chars() {
-        let uuid = generate_uuid(UuidVersion::V4, false, true, false, false);
-        // All characters should be valid hex digits
-        assert!(uuid.chars().all(|c| c.is_ascii_hexdigit()));
+    fn variant_description(&self, variant: uuid::Variant) -> &'static str {
+        match (self.lang, variant) {
+            (_, uuid::Variant::RFC4122) => "RFC 9562",
+            (Language::English, uuid::Variant::NCS) => "NCS (reserved)",
+            (Language::English, uuid::Variant::Microsoft) => "Microsoft (reserved)",
+            (Language::English, _) => "future (reserved)",
+            (Language::Chinese, uuid::Variant::NCS) => "NCS（保留）",
+            (Language::Chinese, uuid::Variant::Microsoft) => "Microsoft（保留）",
+            (Language::Chinese, _) => "未来（保留）",
+        }
     }
 
-    #[test]
-    fn test_cli_parse_uppercase_short() {
-        let cli = Cli::try_parse_from(["zuuid", "-U"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(!cli.simple);
+    fn stats_line(&self, label: StatsLabel, count: u64) -> String {
+        match (self.lang, label) {
+            (Language::English, StatsLabel::Total) => format!("total: {}", count),
+            (Language::English, StatsLabel::Invalid) => format!("invalid: {}", count),
+            (Language::English, StatsLabel::Version(version)) => format!("version {}: {}", version, count),
+            (Language::English, StatsLabel::Variant(variant)) => {
+                format!("variant {}: {}", self.variant_description(variant), count)
+            }
+            (Language::Chinese, StatsLabel::Total) => format!("总数：{}", count),
+            (Language::Chinese, StatsLabel::Invalid) => format!("无效：{}", count),
+            (Language::Chinese, StatsLabel::Version(version)) => format!("版本 {}：{}", version, count),
+            (Language::Chinese, StatsLabel::Variant(variant)) => {
+                format!("变体 {}：{}", self.variant_description(variant), count)
+            }
+        }
     }
 
-    #[test]
-    fn test_cli_parse_uppercase_short_lower() {
-        let cli = Cli::try_parse_from(["zuuid", "-u"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(!cli.simple);
+    fn inspect_header(&self) -> [&'static str; 4] {
+        match self.lang {
+            Language::English => ["VERSION", "VARIANT", "TIMESTAMP", "UUID"],
+            Language::Chinese => ["版本", "变体", "时间戳", "UUID"],
+        }
     }
 
-    #[test]
-    fn test_cli_parse_uppercase_long() {
-        let cli = Cli::try_parse_from(["zuuid", "--upper"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(!cli.simple);
+    fn dump_legend(&self, id: &Uuid, variant_bits: &str, color: bool) -> Vec<String> {
+        let mark = |code: &str, plain: &str, text: String| {
+            if color { format!("\x1b[{}m{}\x1b[0m", code, text) } else { format!("{} {}", plain, text) }
+        };
+        let version = id.get_version_num();
+        let variant = self.variant_description(id.get_variant());
+        let (version, variant, timestamp) = match self.lang {
+            Language::English => (
+                format!("version nibble: {}", version),
+                format!("variant bits: {} ({})", variant_bits, variant),
+                "timestamp: 48-bit Unix milliseconds".to_string(),
+            ),
+            Language::Chinese => (
+                format!("版本半字节：{}", version),
+                format!("变体位：{}（{}）", variant_bits, variant),
+                "时间戳：48 位 Unix 毫秒".to_string(),
+            ),
+        };
+        let mut legend = vec![mark(DUMP_VERSION_COLOR, "[x]", version), mark(DUMP_VARIANT_COLOR, "{x}", variant)];
+        if version_has_dump_timestamp(id) {
+            legend.push(mark(DUMP_TIMESTAMP_COLOR, "<..>", timestamp));
+        }
+        legend
     }
 
-    #[test]
-    fn test_cli_parse_simple_short() {
-        let cli = Cli::try_parse_from(["zuuid", "-s"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
+    fn invalid_label(&self) -> &'static str {
+        match self.lang {
+            Language::English => "invalid",
+            Language::Chinese => "无效",
+        }
+    }
+
+    /// Canonical form, version and variant, plus clock sequence and node for the time-MAC versions 1 and 6
+    /// and domain and local ID for DCE Security (v2)
+    fn decoded(&self, id: &Uuid) -> Vec<String> {
+        let (canonical, version, variant, clock_seq, node, domain, local_id, colon) = match self.lang {
+            Language::English => ("canonical", "version", "variant", "clock_seq", "node", "domain", "local_id", ": "),
+            Language::Chinese => ("规范形式", "版本", "变体", "时钟序列", "节点", "域", "本地 ID", "："),
+        };
+        let mut lines = vec![
+            format!("{}{}{}", canonical, colon, id.hyphenated()),
+            format!("{}{}{}", version, colon, self.version_description(id)),
+            format!("{}{}{}", variant, colon, self.variant_description(id.get_variant())),
+        ];
+        if let Some((seq, mac)) = time_mac_fields(id) {
+            lines.push(format!("{}{}{}", clock_seq, colon, seq));
+            lines.push(format!("{}{}{}", node, colon, mac));
+        }
+        if let Some((dce_domain, dce_id)) = dce_fields(id) {
+            lines.push(format!("{}{}{}", domain, colon, dce_domain_label(dce_domain)));
+            lines.push(format!("{}{}{}", local_id, colon, dce_id));
+        }
+        lines
+    }
+
+    fn invalid_duration(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid duration: {}. Use a number with ms or s, e.g. 500ms or 3s", value),
+            Language::Chinese => format!("无效的时长：{}。请使用带 ms 或 s 的数字，例如 500ms 或 3s", value),
+        }
+    }
+
+    fn invalid_text_format(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid format: {}. Valid values: full, simple, base64", value),
+            Language::Chinese => format!("无效的格式：{}。可选值：full、simple、base64", value),
+        }
+    }
+
+    fn bench_version_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("bench measures generated UUIDs (v4, v7), not {}.", version),
+            Language::Chinese => format!("bench 只测量随机生成的 UUID（v4、v7），不支持 {}。", version),
+        }
+    }
+
+    fn serve_version_not_supported(&self, version: &str) -> String {
+        match self.lang {
+            Language::English => format!("Unsupported version: {}. The server generates v4 and v7", version),
+            Language::Chinese => format!("不支持的版本：{}。服务只生成 v4 和 v7", version),
+        }
+    }
+
+    fn count_over_limit(&self, count: usize, max: usize) -> String {
+        match self.lang {
+            Language::English => format!("Count {} exceeds the per-request limit of {}", count, max),
+            Language::Chinese => format!("数量 {} 超过单次请求上限 {}", count, max),
+        }
+    }
+
+    fn unknown_query_parameter(&self, key: &str) -> String {
+        match self.lang {
+            Language::English => format!("Unknown query parameter: {}. Use version, count or format", key),
+            Language::Chinese => format!("未知的查询参数：{}。可用参数：version、count、format", key),
+        }
+    }
+
+    fn not_found(&self, path: &str) -> String {
+        match self.lang {
+            Language::English => format!("Not found: {}. Endpoints: /uuid, /healthz", path),
+            Language::Chinese => format!("未找到：{}。可用端点：/uuid、/healthz", path),
+        }
+    }
+
+    fn method_not_allowed(&self) -> &'static str {
+        match self.lang {
+            Language::English => "Only GET is supported",
+            Language::Chinese => "仅支持 GET 请求",
+        }
+    }
+
+    fn invalid_mint_request(&self, line: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid request: {:?}. Send VERSION [COUNT [FORMAT]], e.g. \"7 100 simple\"", line),
+            Language::Chinese => format!("无效的请求：{:?}。请发送 版本 [数量 [格式]]，例如 \"7 100 simple\"", line),
+        }
+    }
+
+    fn socket_exists(&self, path: &std::path::Path) -> String {
+        match self.lang {
+            Language::English => format!("{} already exists; pass --force to replace it", path.display()),
+            Language::Chinese => format!("{} 已存在；如需替换请加上 --force", path.display()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn unix_socket_unsupported(&self) -> &'static str {
+        match self.lang {
+            Language::English => "--unix is only available on Unix systems",
+            Language::Chinese => "--unix 仅在 Unix 系统上可用",
+        }
+    }
+
+    fn socket_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to listen on {}: {}", path.display(), err),
+            Language::Chinese => format!("无法监听 {}：{}", path.display(), err),
+        }
+    }
+
+    fn serve_listening_unix(&self, path: &std::path::Path) -> String {
+        match self.lang {
+            Language::English => format!("Serving UUIDs on unix socket {}", path.display()),
+            Language::Chinese => format!("正在 Unix 套接字 {} 上提供 UUID 服务", path.display()),
+        }
+    }
+
+    fn serve_bind_error(&self, addr: &std::net::SocketAddr, err: &str) -> String {
+        match self.lang {
+            Language::English => format!("Failed to listen on {}: {}", addr, err),
+            Language::Chinese => format!("无法监听 {}：{}", addr, err),
+        }
+    }
+
+    fn serve_listening(&self, addr: &std::net::SocketAddr) -> String {
+        match self.lang {
+            Language::English => format!("Serving UUIDs on http://{} (GET /uuid, GET /healthz)", addr),
+            Language::Chinese => format!("正在 http://{} 提供 UUID 服务（GET /uuid、GET /healthz）", addr),
+        }
+    }
+
+    fn batch_summary(&self, summary: &BatchSummary, version: &str, elapsed: std::time::Duration) -> String {
+        let seconds = elapsed.as_secs_f64();
+        let rate = human_rate(summary.count as f64 / seconds.max(f64::EPSILON));
+        let mut line = match self.lang {
+            Language::English => format!("generated {} {} UUIDs in {:.2}s ({})", summary.count, version, seconds, rate),
+            Language::Chinese => format!("已生成 {} 个 {} UUID，用时 {:.2}s（{}）", summary.count, version, seconds, rate),
+        };
+        if summary.versions.len() > 1 {
+            let counts: Vec<String> = summary.versions.iter().map(|(version, count)| format!("v{} {}", version, count)).collect();
+            line += &match self.lang {
+                Language::English => format!(", versions {}", counts.join(", ")),
+                Language::Chinese => format!("，版本 {}", counts.join("、")),
+            };
+        }
+        if let Some((min, max)) = summary.range() {
+            line += &match self.lang {
+                Language::English => format!(", range {} .. {}", min, max),
+                Language::Chinese => format!("，范围 {} .. {}", min, max),
+            };
+        }
+        if let Some((min, max)) = summary.timestamps() {
+            line += &match self.lang {
+                Language::English => format!(", timestamps {} .. {}", min, max),
+                Language::Chinese => format!("，时间戳 {} .. {}", min, max),
+            };
+        }
+        line
+    }
+
+    fn namespace_description(&self, name: &str) -> &'static str {
+        match (self.lang, name) {
+            (Language::English, "dns") => "fully qualified domain names",
+            (Language::English, "url") => "URLs",
+            (Language::English, "oid") => "ISO object identifiers",
+            (Language::English, "nil") => "nil UUID, all bits zero",
+            (Language::English, "max") => "max UUID, all bits one",
+            (Language::English, "bluetooth") => "Bluetooth base UUID for 16- and 32-bit service UUIDs",
+            (Language::English, _) => "X.500 distinguished names (DER or text)",
+            (Language::Chinese, "dns") => "完全限定域名",
+            (Language::Chinese, "url") => "URL",
+            (Language::Chinese, "oid") => "ISO 对象标识符",
+            (Language::Chinese, "nil") => "nil UUID，全部位为 0",
+            (Language::Chinese, "max") => "max UUID，全部位为 1",
+            (Language::Chinese, "bluetooth") => "蓝牙基础 UUID，用于 16 位和 32 位服务 UUID",
+            (Language::Chinese, _) => "X.500 可分辨名称（DER 或文本）",
+        }
+    }
+
+    fn scan_summary(&self, report: &ScanReport) -> String {
+        match self.lang {
+            Language::English => format!(
+                "{} UUIDs ({} unique) in {} of {} files scanned, {} binary files skipped",
+                report.total,
+                report.unique,
+                report.files.len(),
+                report.files_scanned,
+                report.skipped_binary
+            ),
+            Language::Chinese => format!(
+                "扫描了 {} 个文件，其中 {} 个包含 UUID，共 {} 个（{} 个不同），跳过 {} 个二进制文件",
+                report.files_scanned,
+                report.files.len(),
+                report.total,
+                report.unique,
+                report.skipped_binary
+            ),
+        }
+    }
+
+    fn invalid_json_input(&self, err: &serde_json::Error) -> String {
+        match self.lang {
+            Language::English => format!("Input is not valid JSON: {}", err),
+            Language::Chinese => format!("输入不是有效的 JSON：{}", err),
+        }
+    }
+
+    fn not_encoded(&self, encoding: Encoding) -> String {
+        match self.lang {
+            Language::English => format!("not a valid {} UUID", encoding),
+            Language::Chinese => format!("不是有效的 {} UUID", encoding),
+        }
+    }
+
+    fn raw_needs_binary(&self) -> &'static str {
+        match self.lang {
+            Language::English => "The raw encoding is binary: use it as convert --from raw --binary-in or --to raw --binary-out",
+            Language::Chinese => "raw 编码是二进制数据：只能用于 convert --from raw --binary-in 或 --to raw --binary-out",
+        }
+    }
+
+    fn partial_record(&self, len: usize, offset: usize) -> String {
+        match self.lang {
+            Language::English => format!("Trailing partial record: {} of 16 bytes at offset {}", len, offset),
+            Language::Chinese => format!("末尾记录不完整：偏移 {} 处只有 {} 个字节（应为 16）", offset, len),
+        }
+    }
+
+    fn check_digit_missing(&self, input: &str) -> String {
+        match self.lang {
+            Language::English => format!("Missing check digit: {}", input),
+            Language::Chinese => format!("缺少校验字符：{}", input),
+        }
+    }
+
+    fn check_digit_mismatch(&self, input: &str, expected: char) -> String {
+        match self.lang {
+            Language::English => format!("Check digit does not match: {} (expected {})", input, expected),
+            Language::Chinese => format!("校验字符不匹配：{}（应为 {}）", input, expected),
+        }
+    }
+
+    fn scan_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Cannot scan {}: {}", path.display(), err),
+            Language::Chinese => format!("无法扫描 {}：{}", path.display(), err),
+        }
+    }
+
+    fn bench_header(&self) -> [&'static str; 5] {
+        match self.lang {
+            Language::English => ["version", "format", "warmup", "measured", "UUIDs/s"],
+            Language::Chinese => ["版本", "格式", "预热", "计时", "每秒 UUID 数"],
+        }
+    }
+
+    fn repl_help(&self) -> [&'static str; 7] {
+        match self.lang {
+            Language::English => [
+                "gen [n]               generate n UUIDs (default 1)",
+                "v4 | v7               switch the version",
+                "decode <uuid>         show canonical form, version and variant (also: inspect)",
+                "simple | full         switch the text format (also: format simple|full)",
+                "upper on|off          switch uppercase output",
+                "help                  show this list",
+                "quit                  leave the session",
+            ],
+            Language::Chinese => [
+                "gen [n]               生成 n 个 UUID（默认 1）",
+                "v4 | v7               切换版本",
+                "decode <uuid>         显示规范形式、版本和变体（也可用 inspect）",
+                "simple | full         切换输出格式（也可用 format simple|full）",
+                "upper on|off          切换大写输出",
+                "help                  显示本列表",
+                "quit                  退出会话",
+            ],
+        }
+    }
+
+    fn repl_prompt(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("zuuid ({})> ", version),
+            Language::Chinese => format!("zuuid（{}）> ", version),
+        }
+    }
+
+    fn unknown_repl_command(&self, command: &str) -> String {
+        match self.lang {
+            Language::English => format!("Unknown command: {}. Type help for the command list", command),
+            Language::Chinese => format!("未知命令：{}。输入 help 查看命令列表", command),
+        }
+    }
+
+    fn repl_usage(&self, usage: &str) -> String {
+        match self.lang {
+            Language::English => format!("Usage: {}", usage),
+            Language::Chinese => format!("用法：{}", usage),
+        }
+    }
+
+    fn invalid_rate(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid rate: {}. Use a positive number of UUIDs per second", value),
+            Language::Chinese => format!("无效的速率：{}。请使用正数（每秒 UUID 数）", value),
+        }
+    }
+
+    fn invalid_split(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid split size: {}. Use a positive number of lines per file", value),
+            Language::Chinese => format!("无效的分割大小：{}。请使用正整数（每个文件的行数）", value),
+        }
+    }
+
+    fn invalid_count(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => {
+                format!("Invalid count: {}. Use a number, a power of two such as 2^20, an expression such as 10*24, or auto", value)
+            }
+            Language::Chinese => format!("无效的数量：{}。请使用数字、2 的幂（例如 2^20）、表达式（例如 10*24）或 auto", value),
+        }
+    }
+
+    fn count_too_large(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Count too large: {} does not fit in {} bits", value, usize::BITS),
+            Language::Chinese => format!("数量过大：{} 超出 {} 位整数范围", value, usize::BITS),
+        }
+    }
+
+    fn count_zero(&self) -> &'static str {
+        match self.lang {
+            Language::English => "Count is 0, so nothing would be generated; pass --allow-empty if that is intended",
+            Language::Chinese => "数量为 0，不会生成任何内容；如确有此意，请加上 --allow-empty",
+        }
+    }
+
+    fn count_over_max(&self, count: usize, max: usize) -> String {
+        match self.lang {
+            Language::English => format!("Count {} exceeds the limit of {}; raise --max-count (or ZUUID_MAX_COUNT) to allow it", count, max),
+            Language::Chinese => format!("数量 {} 超过上限 {}；如需更多，请调高 --max-count（或 ZUUID_MAX_COUNT）", count, max),
+        }
+    }
+
+    fn short_too_long(&self, len: usize, encoding: Encoding, max: usize) -> String {
+        match self.lang {
+            Language::English => format!("--short {} is longer than {} text, which has {} characters", len, encoding, max),
+            Language::Chinese => format!("--short {} 超过了 {} 文本的长度（{} 个字符）", len, encoding, max),
+        }
+    }
+
+    fn wrap_not_supported(&self, encoding: Encoding) -> String {
+        match self.lang {
+            Language::English => format!("--wrap only applies to base32, base64 and base64url, not {}", encoding),
+            Language::Chinese => format!("--wrap 仅适用于 base32、base64 和 base64url，不适用于 {}", encoding),
+        }
+    }
+
+    fn short_not_supported(&self, encoding: Encoding) -> String {
+        match self.lang {
+            Language::English => format!("--short only applies to hex, uuid25, base32, base64 and base64url, not {}", encoding),
+            Language::Chinese => format!("--short 仅适用于 hex、uuid25、base32、base64 和 base64url，不适用于 {}", encoding),
+        }
+    }
+
+    fn mix_reason(&self) -> &'static str {
+        match self.lang {
+            Language::English => "use VERSION:WEIGHT pairs such as v4:30,v7:70, with v4 and v7 each at most once and weights above 0",
+            Language::Chinese => "请使用 版本:权重 的组合，如 v4:30,v7:70；v4 和 v7 各最多出现一次，权重大于 0",
+        }
+    }
+
+    fn mix_normalized(&self, total: u64) -> String {
+        match self.lang {
+            Language::English => format!("--mix weights add up to {}, not 100; using them as proportions", total),
+            Language::Chinese => format!("--mix 的权重之和为 {}，不是 100；按比例使用", total),
+        }
+    }
+
+    fn mixed_versions(&self) -> &'static str {
+        match self.lang {
+            Language::English => "mixed",
+            Language::Chinese => "混合",
+        }
+    }
+
+    fn invalid_short_length(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid --short length: {}. Valid values: 1 to 32", value),
+            Language::Chinese => format!("无效的 --short 长度：{}。有效值：1 到 32", value),
+        }
+    }
+
+    fn output_file_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to open output file {}: {}", path.display(), err),
+            Language::Chinese => format!("无法打开输出文件 {}：{}", path.display(), err),
+        }
+    }
+
+    fn checksum_read_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to read {} for checksum verification: {}", path.display(), err),
+            Language::Chinese => format!("无法读取 {} 以校验：{}", path.display(), err),
+        }
+    }
+
+    fn checksum_missing(&self, path: &std::path::Path) -> String {
+        match self.lang {
+            Language::English => format!("{} does not end with a \"{}<hex>\" line; generate it with --checksum", path.display(), CHECKSUM_PREFIX),
+            Language::Chinese => format!("{} 末尾没有 \"{}<hex>\" 行；请用 --checksum 生成", path.display(), CHECKSUM_PREFIX),
+        }
+    }
+
+    fn checksum_mismatch(&self, path: &std::path::Path, expected: &str, actual: &str) -> String {
+        match self.lang {
+            Language::English => {
+                format!("Checksum mismatch in {}: recorded {}, content hashes to {}; the file is truncated or corrupted", path.display(), expected, actual)
+            }
+            Language::Chinese => format!("{} 校验和不匹配：记录为 {}，内容实际为 {}；文件已截断或损坏", path.display(), expected, actual),
+        }
+    }
+
+    fn checksum_ok(&self, path: &std::path::Path) -> String {
+        match self.lang {
+            Language::English => format!("{}: checksum OK", path.display()),
+            Language::Chinese => format!("{}：校验和一致", path.display()),
+        }
+    }
+
+    fn invalid_snippet_language(&self, value: &str) -> String {
+        let languages: Vec<&str> = SNIPPETS.iter().map(|&(language, _)| language).collect();
+        match self.lang {
+            Language::English => format!("Unknown snippet language: {}. Use one of: {}", value, languages.join(", ")),
+            Language::Chinese => format!("未知的代码片段语言：{}。可选：{}", value, languages.join("、")),
+        }
+    }
+
+    fn invalid_xml_name(&self, name: &str) -> String {
+        match self.lang {
+            Language::English => format!(
+                "Invalid XML name: {}. Use letters, digits, '-', '_' and '.', starting with a letter or '_'",
+                name
+            ),
+            Language::Chinese => format!("无效的 XML 名称：{}。只能包含字母、数字、'-'、'_' 和 '.'，并以字母或 '_' 开头", name),
+        }
+    }
+
+    fn invalid_fd(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid file descriptor: {}. Use a non-negative number", value),
+            Language::Chinese => format!("无效的文件描述符：{}。请使用非负整数", value),
+        }
+    }
+
+    fn fd_error(&self, fd: i32, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Cannot write to file descriptor {}: {}", fd, err),
+            Language::Chinese => format!("无法写入文件描述符 {}：{}", fd, err),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn fd_unsupported(&self) -> &'static str {
+        match self.lang {
+            Language::English => "--fd is only available on Unix systems",
+            Language::Chinese => "--fd 仅在 Unix 系统上可用",
+        }
+    }
+
+    fn duplicate_key(&self, key: &str) -> String {
+        match self.lang {
+            Language::English => format!("Duplicate key in --keys: {}", key),
+            Language::Chinese => format!("--keys 中存在重复的键：{}", key),
+        }
+    }
+
+    fn env_key_exists(&self, key: &str, path: &std::path::Path) -> String {
+        match self.lang {
+            Language::English => format!("{} already sets {}; the appended value will override it", path.display(), key),
+            Language::Chinese => format!("{} 中已设置 {}；追加的值将覆盖它", path.display(), key),
+        }
+    }
+
+    fn map_file_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to access map file {}: {}", path.display(), err),
+            Language::Chinese => format!("无法访问映射文件 {}：{}", path.display(), err),
+        }
+    }
+
+    fn registry_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to access registry {}: {}", path.display(), err),
+            Language::Chinese => format!("无法访问登记文件 {}：{}", path.display(), err),
+        }
+    }
+
+    fn registry_corrupt(&self, path: &std::path::Path, number: usize, line: &str) -> String {
+        match self.lang {
+            Language::English => format!("Registry {} is corrupted at line {}: {}. Expected one UUID per line", path.display(), number, line),
+            Language::Chinese => format!("登记文件 {} 第 {} 行已损坏：{}。应为每行一个 UUID", path.display(), number, line),
+        }
+    }
+
+    fn registry_truncated(&self, path: &std::path::Path, len: u64) -> String {
+        match self.lang {
+            Language::English => format!(
+                "Binary registry {} is {} bytes long, which is not a whole number of 16-byte records",
+                path.display(),
+                len
+            ),
+            Language::Chinese => format!("二进制登记文件 {} 长 {} 字节，不是 16 字节记录的整数倍", path.display(), len),
+        }
+    }
+
+    fn registry_not_supported(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!(
+                "--registry cannot be used with UUID {}: the same name always gives the same UUID.",
+                version
+            ),
+            Language::Chinese => format!("--registry 不能用于 UUID {}：相同的名称总是得到相同的 UUID。", version),
+        }
+    }
+
+    fn map_file_corrupt(&self, path: &std::path::Path, number: usize, line: &str) -> String {
+        match self.lang {
+            Language::English => format!(
+                "Map file {} is corrupted at line {}: {}. Expected key,uuid with no key repeated",
+                path.display(),
+                number,
+                line
+            ),
+            Language::Chinese => format!("映射文件 {} 第 {} 行已损坏：{}。应为 key,uuid，且键不重复", path.display(), number, line),
+        }
+    }
+
+    fn audit_log_error(&self, path: &std::path::Path, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to write audit log {}: {}", path.display(), err),
+            Language::Chinese => format!("写入审计日志 {} 失败：{}", path.display(), err),
+        }
+    }
+
+    fn audit_log_skipped(&self, error: &str) -> String {
+        match self.lang {
+            Language::English => format!("{}. Continuing without audit log (--audit-best-effort).", error),
+            Language::Chinese => format!("{}。继续运行但不记录审计日志（--audit-best-effort）。", error),
+        }
+    }
+
+    fn config_error(&self, path: &std::path::Path, err: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid config file {}: {}", path.display(), err),
+            Language::Chinese => format!("配置文件 {} 无效：{}", path.display(), err),
+        }
+    }
+
+    fn invalid_format(&self, format: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid format: {}. Valid values: full, simple, braced, urn", format),
+            Language::Chinese => format!("无效的格式：{}。有效值：full、simple、braced、urn", format),
+        }
+    }
+
+    fn unknown_profile(&self, name: &str, available: &[&str]) -> String {
+        match (self.lang, available.is_empty()) {
+            (Language::English, true) => format!("Unknown profile: {}. No profiles are defined in the config file.", name),
+            (Language::English, false) => format!("Unknown profile: {}. Available profiles: {}", name, available.join(", ")),
+            (Language::Chinese, true) => format!("未知的配置档：{}。配置文件中没有定义任何配置档。", name),
+            (Language::Chinese, false) => format!("未知的配置档：{}。可用的配置档：{}", name, available.join("、")),
+        }
+    }
+
+    fn rng_unavailable(&self, attempts: u32, err: &str) -> String {
+        match self.lang {
+            Language::English => format!("System random number generator unavailable after {} attempts: {}", attempts, err),
+            Language::Chinese => format!("系统随机数生成器在尝试 {} 次后仍不可用：{}", attempts, err),
+        }
+    }
+
+    fn entropy_info(&self, seed: Option<u64>) -> String {
+        match (self.lang, seed) {
+            (Language::English, None) => {
+                "Entropy source: operating system CSPRNG (getrandom via rand's OsRng)".to_string()
+            }
+            (Language::English, Some(seed)) => format!(
+                "Entropy source: seeded mode, StdRng (ChaCha12) with seed {}; output is reproducible and not secret",
+                seed
+            ),
+            (Language::Chinese, None) => "熵源：操作系统密码学安全随机数生成器（通过 rand 的 OsRng 调用 getrandom）".to_string(),
+            (Language::Chinese, Some(seed)) => {
+                format!("熵源：种子模式，StdRng（ChaCha12），种子为 {}；输出可复现，不具备保密性", seed)
+            }
+        }
+    }
+
+    fn verbose_version(&self, version: UuidVersion) -> String {
+        match self.lang {
+            Language::English => format!("Resolved version: {}", version),
+            Language::Chinese => format!("解析后的版本：{}", version),
+        }
+    }
+
+    /// `winner` names the flag that decided the style when both -f and -s were given
+    fn verbose_format(&self, options: &FormatOptions, winner: Option<&str>) -> String {
+        let upper = options.case == Case::Upper;
+        let mut line = match self.lang {
+            Language::English => format!("Resolved format: {}, {}", options.style, if upper { "uppercase" } else { "lowercase" }),
+            Language::Chinese => format!("解析后的格式：{}，{}", options.style, if upper { "大写" } else { "小写" }),
+        };
+        if let Some(flag) = winner {
+            line.push_str(&match self.lang {
+                Language::English => format!(" (both -f and -s given, {} came first)", flag),
+                Language::Chinese => format!("（同时指定了 -f 和 -s，{} 在前）", flag),
+            });
+        }
+        line
+    }
+
+    fn verbose_count(&self, count: usize) -> String {
+        match self.lang {
+            Language::English => format!("Count: {}", count),
+            Language::Chinese => format!("数量：{}", count),
+        }
+    }
+
+    fn invalid_variable_name(&self, name: &str) -> String {
+        match self.lang {
+            Language::English => {
+                format!("Invalid variable name: {}. Use letters, digits and underscores, not starting with a digit", name)
+            }
+            Language::Chinese => format!("无效的变量名：{}。只能包含字母、数字和下划线，不能以数字开头", name),
+        }
+    }
+
+    fn invalid_shell(&self, value: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid shell: {}. Use sh, fish or powershell", value),
+            Language::Chinese => format!("无效的 shell：{}。请使用 sh、fish 或 powershell", value),
+        }
+    }
+
+    fn invalid_c_identifier(&self, name: &str) -> String {
+        match self.lang {
+            Language::English => format!(
+                "Invalid C identifier: {}. Use letters, digits and underscores, not starting with a digit or a keyword",
+                name
+            ),
+            Language::Chinese => format!("无效的 C 标识符：{}。只能包含字母、数字和下划线，不能以数字开头，也不能是关键字", name),
+        }
+    }
+
+    fn read_error(&self, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to read input: {}", err),
+            Language::Chinese => format!("读取输入失败：{}", err),
+        }
+    }
+
+    fn write_error(&self, err: &std::io::Error) -> String {
+        match self.lang {
+            Language::English => format!("Failed to write output: {}", err),
+            Language::Chinese => format!("写入输出失败：{}", err),
+        }
+    }
+
+    fn missing_input(&self) -> &'static str {
+        match self.lang {
+            Language::English => "No input source given. Use --stdin to read from standard input.",
+            Language::Chinese => "未指定输入来源。请使用 --stdin 从标准输入读取。",
+        }
+    }
+
+    fn unknown_warning_code(&self, code: &str) -> String {
+        match self.lang {
+            Language::English => format!("Unknown warning code in --no-warn: {}", code),
+            Language::Chinese => format!("--no-warn 中有未知的警告代码：{}", code),
+        }
+    }
+
+    fn invalid_line(&self, number: usize, line: &str) -> String {
+        match self.lang {
+            Language::English => format!("Line {} is not a UUID: {}", number, line),
+            Language::Chinese => format!("第 {} 行不是 UUID：{}", number, line),
+        }
+    }
+
+    fn invalid_version_line(&self, number: usize, line: &str) -> String {
+        match self.lang {
+            Language::English => format!("Line {} does not name a version to generate: {}. Use v4 or v7", number, line),
+            Language::Chinese => format!("第 {} 行不是可生成的版本：{}。请使用 v4 或 v7", number, line),
+        }
+    }
+
+    fn age_ago(&self, duration: &str) -> String {
+        match self.lang {
+            Language::English => format!("{} ago", duration),
+            Language::Chinese => format!("{}前", duration),
+        }
+    }
+
+    fn age_ahead(&self, duration: &str) -> String {
+        match self.lang {
+            Language::English => format!("in {} (in the future)", duration),
+            Language::Chinese => format!("{}后（位于未来）", duration),
+        }
+    }
+
+    fn age_now(&self) -> &'static str {
+        match self.lang {
+            Language::English => "now",
+            Language::Chinese => "现在",
+        }
+    }
+
+    fn no_timestamp(&self, version: usize) -> String {
+        match self.lang {
+            Language::English => format!("version {} UUIDs carry no timestamp; only v1, v6 and v7 do", version),
+            Language::Chinese => format!("版本 {} 的 UUID 不含时间戳，只有 v1、v6 和 v7 含有", version),
+        }
+    }
+
+    fn rotate_version_not_supported(&self, number: usize, version: usize) -> String {
+        match self.lang {
+            Language::English => format!("Line {} is a version {} UUID; rotate only regenerates v4 and v7", number, version),
+            Language::Chinese => format!("第 {} 行是版本 {} 的 UUID；rotate 只能重新生成 v4 和 v7", number, version),
+        }
+    }
+
+    fn distribution_check_not_supported(&self, version: &str) -> String {
+        match self.lang {
+            Language::English => format!("--distribution-check only applies to v4 UUIDs, not {}.", version),
+            Language::Chinese => format!("--distribution-check 只适用于 v4 UUID，不能用于 {}。", version),
+        }
+    }
+
+    fn distribution_summary(&self, histogram: &ByteHistogram, chi_square: Option<f64>) -> String {
+        match (self.lang, chi_square) {
+            (Language::English, Some(chi_square)) => format!(
+                "first byte of {} UUIDs: chi-square {:.1} over 256 values (skewed above {:.1})",
+                histogram.total(), chi_square, FIRST_BYTE_CHI_SQUARE_LIMIT
+            ),
+            (Language::English, None) => format!(
+                "first byte of {} UUIDs: too few to judge, the check needs at least {}",
+                histogram.total(), FIRST_BYTE_MIN_SAMPLE
+            ),
+            (Language::Chinese, Some(chi_square)) => format!(
+                "{} 个 UUID 的首字节：256 个取值的卡方值为 {:.1}（超过 {:.1} 视为偏斜）",
+                histogram.total(), chi_square, FIRST_BYTE_CHI_SQUARE_LIMIT
+            ),
+            (Language::Chinese, None) => format!(
+                "{} 个 UUID 的首字节：数量太少，无法判断，至少需要 {} 个",
+                histogram.total(), FIRST_BYTE_MIN_SAMPLE
+            ),
+        }
+    }
+
+    fn distribution_skewed(&self) -> &'static str {
+        match self.lang {
+            Language::English => "the first byte is far from uniform; the random number generator may be broken",
+            Language::Chinese => "首字节分布明显不均匀，随机数生成器可能有问题",
+        }
+    }
+
+    fn doctor_status(&self, ok: bool) -> &'static str {
+        match (self.lang, ok) {
+            (Language::English, true) => "ok",
+            (Language::English, false) => "FAIL",
+            (Language::Chinese, true) => "正常",
+            (Language::Chinese, false) => "失败",
+        }
+    }
+
+    fn doctor_rng_ok(&self) -> &'static str {
+        match self.lang {
+            Language::English => "operating system RNG reachable",
+            Language::Chinese => "可以访问操作系统随机数生成器",
+        }
+    }
+
+    fn doctor_needs_rng(&self) -> &'static str {
+        match self.lang {
+            Language::English => "not run: the RNG is unreachable",
+            Language::Chinese => "未运行：无法访问随机数生成器",
+        }
+    }
+
+    fn doctor_duplicates(&self, sample: usize, duplicates: usize) -> String {
+        match self.lang {
+            Language::English => format!("{} duplicates among {} v4 UUIDs", duplicates, sample),
+            Language::Chinese => format!("{} 个 v4 UUID 中有 {} 个重复", sample, duplicates),
+        }
+    }
+
+    fn doctor_distribution(&self, chi_square: f64, limit: f64) -> String {
+        match self.lang {
+            Language::English => format!("chi-square {:.1} over the random hex digits (limit {:.1})", chi_square, limit),
+            Language::Chinese => format!("随机十六进制位的卡方值为 {:.1}（上限 {:.1}）", chi_square, limit),
+        }
+    }
+
+    fn doctor_clock(&self, now: &str, ok: bool) -> String {
+        match (self.lang, ok) {
+            (Language::English, true) => now.to_string(),
+            (Language::English, false) => format!("{} is outside 2020 to 2100; v7 timestamps would be wrong", now),
+            (Language::Chinese, true) => now.to_string(),
+            (Language::Chinese, false) => format!("{} 不在 2020 到 2100 年之间，v7 时间戳会出错", now),
+        }
+    }
+
+    fn doctor_no_config_location(&self) -> &'static str {
+        match self.lang {
+            Language::English => "no config location: set ZUUID_CONFIG, XDG_CONFIG_HOME or HOME",
+            Language::Chinese => "没有配置文件位置：请设置 ZUUID_CONFIG、XDG_CONFIG_HOME 或 HOME",
+        }
+    }
+
+    fn doctor_config_absent(&self, path: &std::path::Path) -> String {
+        match self.lang {
+            Language::English => format!("{} (not present, using defaults)", path.display()),
+            Language::Chinese => format!("{}（不存在，使用默认值）", path.display()),
+        }
+    }
+
+    fn doctor_failed(&self, failed: usize, total: usize) -> String {
+        match self.lang {
+            Language::English => format!("{} of {} checks failed", failed, total),
+            Language::Chinese => format!("{} 项检查中有 {} 项失败", total, failed),
+        }
+    }
+
+    fn time_reason(&self) -> &'static str {
+        match self.lang {
+            Language::English => "use YYYY-MM-DD, a UTC timestamp such as 2024-01-01T12:00:00Z, or now",
+            Language::Chinese => "请使用 YYYY-MM-DD、UTC 时间戳（如 2024-01-01T12:00:00Z）或 now",
+        }
+    }
+
+    fn invalid_hex_prefix(&self, prefix: &str) -> String {
+        match self.lang {
+            Language::English => format!("Invalid prefix: {}. Use 1 to 32 hex digits (0-9, a-f)", prefix),
+            Language::Chinese => format!("无效的前缀：{}。请使用 1 到 32 位十六进制数字（0-9、a-f）", prefix),
+        }
+    }
+
+    fn too_many_lines(&self, max_lines: usize) -> String {
+        match self.lang {
+            Language::English => format!("Input exceeds {} lines. Raise the limit with --max-lines.", max_lines),
+            Language::Chinese => format!("输入超过 {} 行。可通过 --max-lines 提高上限。", max_lines),
+        }
+    }
+}
+
+/// UUID version to generate
+/// Each version is named by its number; v-, uuid- and uuidv-prefixed aliases and the
+/// descriptive random/time/timestamp aliases are accepted too but kept out of --help
+/// Serialized as "v7" and deserialized from any command-line spelling
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+enum UuidVersion {
+    /// Version 2: DCE Security UUID embedding a POSIX UID or GID
+    #[value(name = "2", aliases = ["v2", "uuid2", "uuidv2", "dce"])]
+    V2,
+    /// Version 3: Name-based UUID (MD5)
+    #[value(name = "3", aliases = ["v3", "uuid3", "uuidv3"])]
+    V3,
+    /// Version 4: Random UUID (default)
+    #[default]
+    #[value(name = "4", aliases = ["v4", "uuid4", "uuidv4", "random"])]
+    V4,
+    /// Version 5: Name-based UUID (SHA-1)
+    #[value(name = "5", aliases = ["v5", "uuid5", "uuidv5"])]
+    V5,
+    /// Version 7: Time-ordered UUID
+    #[value(name = "7", aliases = ["v7", "uuid7", "uuidv7", "time", "timestamp"])]
+    V7,
+}
+
+impl UuidVersion {
+    /// Whether this version is derived from a namespace and name rather than generated fresh
+    fn is_name_based(self) -> bool {
+        matches!(self, UuidVersion::V3 | UuidVersion::V5)
+    }
+
+    /// Whether this version needs nothing beyond random bits and the clock, as bench and serve require
+    fn is_standalone(self) -> bool {
+        matches!(self, UuidVersion::V4 | UuidVersion::V7)
+    }
+}
+
+/// A version string that names no supported version; localized only when displayed
+#[derive(Debug, PartialEq, Eq)]
+struct InvalidVersion(String);
+
+impl std::fmt::Display for InvalidVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Messages::global().invalid_version(&self.0))
+    }
+}
+
+/// Lower layers report plain localized strings
+impl From<InvalidVersion> for String {
+    fn from(err: InvalidVersion) -> Self {
+        err.to_string()
+    }
+}
+
+/// A rejected value, located by the argument it was given to and its 1-based position among that argument's values
+#[derive(Debug, PartialEq, Eq)]
+struct InputError {
+    arg_name: String,
+    index: usize,
+    value: String,
+    reason: String,
+}
+
+impl InputError {
+    fn new(arg_name: &str, index: usize, value: &str, reason: impl Into<String>) -> Self {
+        InputError { arg_name: arg_name.to_string(), index, value: value.to_string(), reason: reason.into() }
+    }
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Messages::global().input_error(self))
+    }
+}
+
+impl From<InputError> for AppError {
+    fn from(err: InputError) -> Self {
+        AppError::Invalid(err.to_string())
+    }
+}
+
+impl std::str::FromStr for UuidVersion {
+    type Err = InvalidVersion;
+
+    /// The same spellings as on the command line, for the config file, repl and serve
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true).map_err(|_| InvalidVersion(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for UuidVersion {
+    type Error = InvalidVersion;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<UuidVersion> for String {
+    fn from(version: UuidVersion) -> Self {
+        version.to_string()
+    }
+}
+
+impl std::fmt::Display for UuidVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UuidVersion::V2 => write!(f, "v2"),
+            UuidVersion::V3 => write!(f, "v3"),
+            UuidVersion::V4 => write!(f, "v4"),
+            UuidVersion::V5 => write!(f, "v5"),
+            UuidVersion::V7 => write!(f, "v7"),
+        }
+    }
+}
+
+/// Whose local ID a v2 UUID carries; the discriminant is the domain octet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum)]
+enum DceDomain {
+    /// POSIX UID (default)
+    #[default]
+    Person = 0,
+    /// POSIX GID
+    Group = 1,
+    /// Site-defined organization ID
+    Org = 2,
+}
+
+impl std::fmt::Display for DceDomain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DceDomain::Person => write!(f, "person"),
+            DceDomain::Group => write!(f, "group"),
+            DceDomain::Org => write!(f, "org"),
+        }
+    }
+}
+
+/// How much of a v7 UUID's random field is given to time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum V7Precision {
+    /// Millisecond timestamp; a counter only steps in when the millisecond repeats (default)
+    #[default]
+    Millis,
+    /// Top 12 bits of the counter hold the sub-millisecond time (RFC 9562 method 3)
+    SubMillis,
+}
+
+impl std::str::FromStr for V7Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ms" => Ok(V7Precision::Millis),
+            "sub-ms" => Ok(V7Precision::SubMillis),
+            _ => Err(Messages::global().invalid_v7_precision(s)),
+        }
+    }
+}
+
+/// Bluetooth base UUID (Core Specification Vol 3 Part B 2.5.1), 16- and 32-bit service UUIDs are offsets into it
+const BLUETOOTH_BASE_UUID: Uuid = Uuid::from_u128(0x00000000_0000_1000_8000_00805f9b34fb);
+
+/// Well-known UUIDs by the names --namespace accepts and `namespaces` lists:
+/// the RFC 9562 namespaces first, then the nil, max and Bluetooth base UUIDs
+const WELL_KNOWN_UUIDS: [(&str, Uuid); 7] = [
+    ("dns", Uuid::NAMESPACE_DNS),
+    ("url", Uuid::NAMESPACE_URL),
+    ("oid", Uuid::NAMESPACE_OID),
+    ("x500", Uuid::NAMESPACE_X500),
+    ("nil", Uuid::nil()),
+    ("max", Uuid::max()),
+    ("bluetooth", BLUETOOTH_BASE_UUID),
+];
+
+/// Look up a well-known UUID by name, ignoring ASCII case
+fn lookup(name: &str) -> Option<Uuid> {
+    WELL_KNOWN_UUIDS.iter().find(|(known, _)| name.eq_ignore_ascii_case(known)).map(|&(_, id)| id)
+}
+
+/// Parse a namespace for name-based UUIDs: a well-known name or any UUID
+fn parse_namespace(s: &str) -> Result<Uuid, String> {
+    match lookup(s) {
+        Some(id) => Ok(id),
+        None => Uuid::parse_str(s).map_err(|_| Messages::global().namespace_reason().to_string()),
+    }
+}
+
+/// Parse a filter prefix: hex digits, hyphens allowed anywhere, normalized to lowercase simple form
+fn parse_hex_prefix(s: &str) -> Result<String, String> {
+    let prefix: String = s.chars().filter(|&c| c != '-').collect();
+    if !prefix.is_empty() && prefix.len() <= 32 && prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(prefix.to_ascii_lowercase())
+    } else {
+        Err(Messages::global().invalid_hex_prefix(s))
+    }
+}
+
+/// Longest accepted --vanity prefix, in hex digits
+const MAX_VANITY_PREFIX: usize = 8;
+
+/// Prefixes longer than this trigger a warning about search time
+const VANITY_WARN_LENGTH: usize = 6;
+
+/// Parse a vanity prefix: 1 to 8 hex digits, normalized to lowercase for matching
+fn parse_vanity_prefix(s: &str) -> Result<String, String> {
+    if !s.is_empty() && s.len() <= MAX_VANITY_PREFIX && s.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(s.to_ascii_lowercase())
+    } else {
+        Err(Messages::global().invalid_vanity_prefix(s))
+    }
+}
+
+/// Parse --count: a plain number, a power of two written as 2^k, sums and products of those such as 10*24,
+/// or auto for one per CPU core
+fn parse_count(s: &str) -> Result<usize, String> {
+    parse_count_with(s, available_cores)
+}
+
+/// CPU cores available to this process, falling back to one when unknown
+fn available_cores() -> usize {
+    std::thread::available_parallelism().map_or(1, std::num::NonZero::get)
+}
+
+/// `parse_count` with the core count for auto supplied by `cores`
+fn parse_count_with(s: &str, cores: impl FnOnce() -> usize) -> Result<usize, String> {
+    let msgs = Messages::global();
+    if s.eq_ignore_ascii_case("auto") {
+        return Ok(cores());
+    }
+    // Evaluated strictly left to right, without precedence: 2+3*4 is 20
+    let operators = s.chars().filter(|c| matches!(c, '+' | '*'));
+    let mut terms = s.split(['+', '*']).map(|term| parse_count_term(term.trim(), s));
+    let mut total = terms.next().unwrap_or_else(|| Err(msgs.invalid_count(s)))?;
+    for (operator, term) in operators.zip(terms) {
+        let term = term?;
+        let result = if operator == '+' { total.checked_add(term) } else { total.checked_mul(term) };
+        total = result.ok_or_else(|| msgs.count_too_large(s))?;
+    }
+    Ok(total)
+}
+
+/// One operand of a --count expression: a plain number or 2^k; errors quote the whole expression `s`
+fn parse_count_term(term: &str, s: &str) -> Result<usize, String> {
+    let msgs = Messages::global();
+    let Some((base, exponent)) = term.split_once('^') else {
+        return term.parse().map_err(|_| msgs.invalid_count(s));
+    };
+    let exponent: u32 = match (base.trim(), exponent.trim().parse()) {
+        ("2", Ok(exponent)) => exponent,
+        _ => return Err(msgs.invalid_count(s)),
+    };
+    1usize.checked_shl(exponent).ok_or_else(|| msgs.count_too_large(s))
+}
+
+/// Parse --rate: a positive, finite number of UUIDs per second whose interval fits a `Duration`
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate.is_finite() && rate > 0.0 && std::time::Duration::try_from_secs_f64(1.0 / rate).is_ok() => {
+            Ok(rate)
+        }
+        _ => Err(Messages::global().invalid_rate(s)),
+    }
+}
+
+/// Parse --split: a positive number of data lines per file
+fn parse_split(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(lines) if lines > 0 => Ok(lines),
+        _ => Err(Messages::global().invalid_split(s)),
+    }
+}
+
+/// Parse an XML element name for --root and --element
+/// Names starting with "xml" are reserved by the XML specification
+fn parse_xml_name(s: &str) -> Result<String, String> {
+    let mut chars = s.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !s.to_ascii_lowercase().starts_with("xml");
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(Messages::global().invalid_xml_name(s))
+    }
+}
+
+/// Parse --fd: a non-negative file descriptor number
+fn parse_fd(s: &str) -> Result<i32, String> {
+    match s.parse::<i32>() {
+        Ok(fd) if fd >= 0 => Ok(fd),
+        _ => Err(Messages::global().invalid_fd(s)),
+    }
+}
+
+/// The --mix weights: which standalone versions make up a batch, and in what proportion
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct VersionMix(Vec<(UuidVersion, u64)>);
+
+impl VersionMix {
+    fn total(&self) -> u64 {
+        self.0.iter().map(|&(_, weight)| weight).sum()
+    }
+
+    /// How many of `count` UUIDs each version gets: its share rounded down, with the UUIDs left over
+    /// going to the largest remainders (the earlier entry on a tie), so the counts always add up to `count`
+    fn counts(&self, count: usize) -> Vec<(UuidVersion, usize)> {
+        let total = self.total() as u128;
+        let shares: Vec<(u128, u128)> =
+            self.0.iter().map(|&(_, weight)| ((count as u128 * weight as u128) / total, (count as u128 * weight as u128) % total)).collect();
+        let mut counts: Vec<usize> = shares.iter().map(|&(share, _)| share as usize).collect();
+        let mut order: Vec<usize> = (0..shares.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(shares[i].1));
+        for &i in order.iter().take(count - counts.iter().sum::<usize>()) {
+            counts[i] += 1;
+        }
+        self.0.iter().zip(counts).map(|(&(version, _), count)| (version, count)).collect()
+    }
+
+    /// The version of each UUID in a batch of `count`, interleaved by `rng`
+    fn plan(&self, count: usize, rng: &mut StdRng) -> Vec<UuidVersion> {
+        let mut plan: Vec<UuidVersion> =
+            self.counts(count).into_iter().flat_map(|(version, count)| std::iter::repeat_n(version, count)).collect();
+        plan.shuffle(rng);
+        plan
+    }
+}
+
+/// Parse --mix: VERSION:WEIGHT pairs such as v4:30,v7:70, each version at most once
+/// Weights that do not add up to 100 are accepted here and normalized with a warning when generating
+fn parse_mix(s: &str) -> Result<VersionMix, String> {
+    let reason = || Messages::global().mix_reason().to_string();
+    let mut entries = Vec::new();
+    for entry in s.split(',') {
+        let (version, weight) = entry.trim().split_once(':').ok_or_else(reason)?;
+        let version: UuidVersion = version.trim().parse().map_err(|_| reason())?;
+        let weight: u64 = weight.trim().parse().map_err(|_| reason())?;
+        if !version.is_standalone() || weight == 0 || entries.iter().any(|&(seen, _)| seen == version) {
+            return Err(reason());
+        }
+        entries.push((version, weight));
+    }
+    Ok(VersionMix(entries))
+}
+
+/// Parse the --short length: how many trailing hex digits to keep (1 to 32)
+fn parse_short_length(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(len) if (1..=32).contains(&len) => Ok(len),
+        _ => Err(Messages::global().invalid_short_length(s)),
+    }
+}
+
+/// Languages for --snippet and their UUID construction expressions; {} stands for the UUID
+/// Adding a language is one entry here plus its case in `test_snippets_for_nil`
+const SNIPPETS: [(&str, &str); 7] = [
+    ("python", "uuid.UUID(\"{}\")"),
+    ("go", "uuid.MustParse(\"{}\")"),
+    ("java", "UUID.fromString(\"{}\")"),
+    ("csharp", "new Guid(\"{}\")"),
+    ("kotlin", "UUID.fromString(\"{}\")"),
+    ("swift", "UUID(uuidString: \"{}\")!"),
+    ("typescript", "\"{}\" as UUID"),
+];
+
+/// Parse the --snippet language, case-insensitively
+fn parse_snippet_language(s: &str) -> Result<String, String> {
+    let language = s.to_lowercase();
+    if SNIPPETS.iter().any(|&(name, _)| name == language) {
+        Ok(language)
+    } else {
+        Err(Messages::global().invalid_snippet_language(s))
+    }
+}
+
+/// The --snippet expression constructing `text` in `language`
+fn snippet(language: &str, text: &str) -> String {
+    let (_, template) = SNIPPETS.iter().find(|&&(name, _)| name == language).expect("validated by parse_snippet_language");
+    template.replace("{}", text)
+}
+
+/// Reserved words that cannot name the --c-array-name variable (C11 plus common C23 additions)
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum", "extern", "float",
+    "for", "goto", "if", "inline", "int", "long", "register", "restrict", "return", "short", "signed", "sizeof",
+    "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while", "bool", "true", "false",
+    "nullptr", "alignas", "alignof", "constexpr", "static_assert", "thread_local", "typeof",
+];
+
+/// Parse a C identifier for --c-array-name
+fn parse_c_identifier(s: &str) -> Result<String, String> {
+    let mut chars = s.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !C_KEYWORDS.contains(&s);
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(Messages::global().invalid_c_identifier(s))
+    }
+}
+
+/// Parse a shell variable name for --export
+fn parse_variable_name(s: &str) -> Result<String, String> {
+    let mut chars = s.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(Messages::global().invalid_variable_name(s))
+    }
+}
+
+/// Shell dialect of the --export statement
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ShellDialect {
+    /// POSIX sh, bash and zsh: export NAME='value'
+    #[default]
+    Sh,
+    /// fish: set NAME 'value'
+    Fish,
+    /// PowerShell: $env:NAME = 'value'
+    PowerShell,
+}
+
+impl std::str::FromStr for ShellDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sh" | "bash" | "zsh" => Ok(ShellDialect::Sh),
+            "fish" => Ok(ShellDialect::Fish),
+            "powershell" | "pwsh" => Ok(ShellDialect::PowerShell),
+            _ => Err(Messages::global().invalid_shell(s)),
+        }
+    }
+}
+
+/// Parse a UUID version number for decode --require-version
+fn parse_version_number(s: &str) -> Result<u8, String> {
+    match s.trim_start_matches(['v', 'V']).parse::<u8>() {
+        Ok(version) if (1..=8).contains(&version) => Ok(version),
+        _ => Err(Messages::global().invalid_version_number(s)),
+    }
+}
+
+/// UUID generator tool
+#[derive(Parser)]
+#[command(name = "zuuid")]
+#[command(about = "Generate UUID v2/v3/v4/v5/v7", long_about = None)]
+// Negative numbers reach the value parsers, which reject them with a localized message instead of clap's "unexpected argument"
+#[command(allow_negative_numbers = true)]
+// Output styles that each replace the whole rendering are mutually exclusive; only -f/-s stay order-based
+#[command(group(ArgGroup::new("style").multiple(false).args(["braced", "urn", "short", "c_array"])))]
+#[command(group(ArgGroup::new("output_file").args(["output", "split"])))]
+struct Cli {
+    /// UUID version to generate
+    #[arg(short = 'V', long = "uuid-version", visible_short_alias = 'v', value_enum, ignore_case = true, default_value = "4")]
+    version: UuidVersion,
+
+    /// Generate a batch of several versions by weight, e.g. v4:30,v7:70, interleaved randomly (reproducibly with --seed)
+    #[arg(long = "mix", value_name = "VERSION:WEIGHT,...", value_parser = parse_mix, conflicts_with_all = ["version", "v7_precision", "timestamp_ms", "counter_start", "names", "domain", "local_id", "vanity", "from_phrase", "all_versions", "per_line", "target_partition", "registry", "audit_log"])]
+    mix: Option<VersionMix>,
+
+    /// Timestamp precision for v7: ms (default) or sub-ms for strict ordering at high rates
+    #[arg(long = "v7-precision", value_name = "ms|sub-ms")]
+    v7_precision: Option<V7Precision>,
+
+    /// Use this Unix time in milliseconds for every v7 UUID instead of the clock
+    #[arg(long = "timestamp-ms", value_name = "MS", value_parser = clap::value_parser!(u64).range(..=V7_MAX_MILLIS))]
+    timestamp_ms: Option<u64>,
+
+    /// Start the sub-ms v7 counter at N (below its 12 time bits) and bump it for each UUID, for testing rollover
+    #[arg(long = "counter-start", value_name = "N", value_parser = clap::value_parser!(u32).range(..=V7_COUNTER_MAX))]
+    counter_start: Option<u32>,
+
+    /// Output UUID in uppercase
+    #[arg(short = 'U', long = "upper", visible_short_alias = 'u')]
+    uppercase: bool,
+
+    /// Output UUID without hyphens (32 chars)
+    #[arg(short = 's', long = "simple", visible_short_alias = 'S')]
+    simple: bool,
+
+    /// Output UUID with hyphens in full format (36 chars)
+    #[arg(short = 'f', long = "full", visible_short_alias = 'F')]
+    full: bool,
+
+    /// Output UUID in braces, {8-4-4-4-12} (38 chars)
+    #[arg(long = "braced", conflicts_with_all = ["simple", "full"])]
+    braced: bool,
+
+    /// Output UUID as a URN, urn:uuid:8-4-4-4-12 (45 chars); -U leaves the prefix lowercase
+    #[arg(long = "urn", conflicts_with_all = ["simple", "full"])]
+    urn: bool,
+
+    /// Number of UUIDs to generate, a power of two such as 2^20, a left-to-right expression such as 10*24 or 5+5,
+    /// or auto for one per CPU core (default: 1)
+    #[arg(short = 'n', long = "count", default_value = "1", value_parser = parse_count)]
+    count: usize,
+
+    /// Accept -n 0, which prints nothing instead of failing
+    #[arg(long = "allow-empty")]
+    allow_empty: bool,
+
+    /// Largest count a run may generate, also settable as max_count in the config (default: 100000000)
+    #[arg(long = "max-count", env = "ZUUID_MAX_COUNT", default_value_t = DEFAULT_MAX_COUNT)]
+    max_count: usize,
+
+    /// Namespace for v3/v5: dns, url, oid, x500, nil, max, bluetooth or a UUID
+    #[arg(long = "namespace", value_parser = parse_namespace)]
+    namespace: Option<Uuid>,
+
+    /// Name to hash for v3/v5, one UUID per name (repeatable)
+    #[arg(long = "name")]
+    names: Vec<String>,
+
+    /// Warn about names listed more than once (they still produce output)
+    #[arg(long = "warn-dup-names", conflicts_with = "unique_names")]
+    warn_dup_names: bool,
+
+    /// Skip names already listed, so each distinct name produces one UUID
+    #[arg(long = "unique-names")]
+    unique_names: bool,
+
+    /// Domain of the local ID in a v2 UUID: person (default), group or org
+    #[arg(long = "domain")]
+    domain: Option<DceDomain>,
+
+    /// Local ID embedded in a v2 UUID (default: the current UID or GID on Unix)
+    #[arg(long = "local-id")]
+    local_id: Option<u32>,
+
+    /// Print only the last N characters as a lossy display ID: 1-32 hex digits, or 1-25 with --encoding uuid25
+    #[arg(long = "short", value_parser = parse_short_length)]
+    short: Option<usize>,
+
+    /// Also draw the UUID as a QR code on stderr (single UUID only)
+    #[arg(long = "qr", conflicts_with_all = ["short", "c_array", "per_line"])]
+    qr: bool,
+
+    /// Encode the URN form (urn:uuid:...) in the QR code instead of the printed text
+    #[arg(long = "qr-urn", requires = "qr")]
+    qr_urn: bool,
+
+    /// Print each UUID's 16 bytes as a C array literal: {0x12, 0x34, ...}
+    #[arg(long = "c-array")]
+    c_array: bool,
+
+    /// Wrap the C array in `const uint8_t NAME[16] = {...};` (numbered NAME_1.. for several UUIDs)
+    #[arg(long = "c-array-name", value_parser = parse_c_identifier, requires = "c_array")]
+    c_array_name: Option<String>,
+
+    /// Derive v4-shaped UUIDs from SHA-256 of a phrase, for reproducible local fixtures (not secret)
+    /// With -n above 1, UUID i (from 1) hashes "PHRASE#i"
+    #[arg(long = "from-phrase", conflicts_with_all = ["vanity", "per_line", "seed", "names"])]
+    from_phrase: Option<String>,
+
+    /// Search for a v4 UUID whose simple form starts with this hex prefix (up to 8 chars)
+    #[arg(long = "vanity", value_parser = parse_vanity_prefix, conflicts_with_all = ["count", "sort", "per_line", "seed", "names"])]
+    vanity: Option<String>,
+
+    /// Emit at most this many UUIDs per second, flushing each one as it is written
+    #[arg(long = "rate", value_parser = parse_rate, conflicts_with_all = ["per_line", "vanity"])]
+    rate: Option<f64>,
+
+    /// Print a shell statement setting NAME to the UUID, for eval "$(zuuid --export NAME)"
+    #[arg(long = "export", value_name = "NAME", value_parser = parse_variable_name, conflicts_with_all = ["c_array", "qr", "timestamped"])]
+    export: Option<String>,
+
+    /// Shell dialect for --export: sh (default), fish or powershell
+    #[arg(long = "shell", value_name = "SHELL", requires = "export")]
+    shell: Option<ShellDialect>,
+
+    /// Print KEY=uuid lines for a .env file, with keys from --keys or UUID_1, UUID_2, ...
+    #[arg(long = "env-file", conflicts_with_all = ["c_array", "qr", "export", "timestamped", "per_line"])]
+    env_file: bool,
+
+    /// Comma-separated keys for --env-file; generates one UUID per key
+    #[arg(long = "keys", value_name = "KEY,...", value_delimiter = ',', value_parser = parse_variable_name, requires = "env_file", conflicts_with = "count")]
+    keys: Vec<String>,
+
+    /// Write output to PATH instead of stdout (replacing its contents unless --append)
+    #[arg(short = 'o', long = "output", value_name = "PATH", conflicts_with = "split")]
+    output: Option<std::path::PathBuf>,
+
+    /// Write output to the already open file descriptor N instead of stdout (Unix only), e.g. --fd 3
+    #[arg(long = "fd", value_name = "N", value_parser = parse_fd, conflicts_with_all = ["output", "split"])]
+    fd: Option<i32>,
+
+    /// Append to the --output file instead of replacing it
+    #[arg(long = "append", requires = "output", conflicts_with = "bom")]
+    append: bool,
+
+    /// Print a construction expression in LANG: python, go, java, csharp, kotlin, swift or typescript
+    #[arg(long = "snippet", value_name = "LANG", value_parser = parse_snippet_language, conflicts_with_all = ["simple", "short", "c_array", "qr", "export", "env_file", "xml", "markdown", "pg_copy", "pg_copy_binary", "with_hash", "show_variant"])]
+    snippet: Option<String>,
+
+    /// Append the SHA-256 of the UUID's 16 bytes to each line, in hex
+    #[arg(long = "with-hash", conflicts_with_all = ["c_array", "qr", "export", "env_file", "xml", "pg_copy", "pg_copy_binary"])]
+    with_hash: bool,
+
+    /// Append a tab and the Kafka partition (murmur2 of the UUID text) each UUID would get among N partitions
+    #[arg(long = "with-partition", value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["short", "c_array", "encoding", "check_digit", "snippet", "qr", "export", "env_file", "xml", "pg_copy", "pg_copy_binary"])]
+    with_partition: Option<u32>,
+
+    /// Keep only UUIDs that --with-partition puts in partition K, generating until each one is found
+    #[arg(long = "target-partition", value_name = "K", requires = "with_partition", conflicts_with_all = ["names", "vanity", "from_phrase"])]
+    target_partition: Option<u32>,
+
+    /// Append the variant read from the UUID's bits to each line: RFC4122, Microsoft, NCS or Future
+    #[arg(long = "show-variant", conflicts_with_all = ["c_array", "qr", "export", "env_file", "xml", "pg_copy", "pg_copy_binary"])]
+    show_variant: bool,
+
+    /// Leave out the line ending after the last line only, e.g. for a file holding exactly one UUID
+    #[arg(long = "no-final-newline", conflicts_with_all = ["checksum", "split", "append"])]
+    no_final_newline: bool,
+
+    /// Follow each UUID with a comment giving its version and, for time-based versions, its timestamp
+    #[arg(long = "comment", conflicts_with_all = ["c_array", "qr", "export", "env_file", "snippet", "xml", "markdown", "pg_copy", "pg_copy_binary", "all_versions"])]
+    comment: bool,
+
+    /// Comment marker for --comment
+    #[arg(long = "comment-prefix", value_name = "PREFIX", default_value = "#", requires = "comment")]
+    comment_prefix: String,
+
+    /// Write UUIDs as hex (default), uuid25 (25 base-36 digits), an EFI_GUID initializer, an OID,
+    /// or unpadded RFC 4648 base32, base64 or base64url
+    #[arg(long = "encoding", value_enum, default_value_t, conflicts_with_all = ["braced", "urn", "c_array", "snippet", "check_digit"])]
+    encoding: Encoding,
+
+    /// Join the base32/base64/base64url UUIDs into one stream and break it every N characters (0 = one line)
+    #[arg(long = "wrap", value_name = "N", conflicts_with_all = ["per_line", "rate", "json", "ndjson", "xml", "markdown", "pg_copy", "pg_copy_binary", "columns", "split", "all_versions", "repl", "c_array", "qr", "export", "env_file", "snippet", "comment", "timestamped", "with_hash", "with_partition", "show_variant", "check_digit"])]
+    wrap: Option<usize>,
+
+    /// Append a Crockford base32 check symbol (the 128 bits mod 37), after "-" or, with --simple, after "*"
+    #[arg(long = "check-digit", conflicts_with_all = ["short", "c_array", "snippet", "qr", "pg_copy", "pg_copy_binary"])]
+    check_digit: bool,
+
+    /// Print a GitHub-flavored Markdown table with #, UUID and (for v7) Timestamp columns
+    #[arg(long = "markdown", conflicts_with_all = ["c_array", "qr", "export", "env_file", "timestamped", "per_line"])]
+    markdown: bool,
+
+    /// Print an XML document: <uuids><uuid version="4">...</uuid>...</uuids>
+    #[arg(long = "xml", conflicts_with_all = ["c_array", "qr", "export", "env_file", "markdown", "timestamped", "per_line"])]
+    xml: bool,
+
+    /// Name of the --xml root element (default: uuids)
+    #[arg(long = "root", value_name = "NAME", default_value = "uuids", value_parser = parse_xml_name, requires = "xml")]
+    xml_root: String,
+
+    /// Name of each --xml UUID element (default: uuid)
+    #[arg(long = "element", value_name = "NAME", default_value = "uuid", value_parser = parse_xml_name, requires = "xml")]
+    xml_element: String,
+
+    /// Start the --xml output with an <?xml version="1.0"?> declaration
+    #[arg(long = "declaration", requires = "xml")]
+    xml_declaration: bool,
+
+    /// Print one UUID per line in the text format of PostgreSQL's COPY ... FROM STDIN
+    #[arg(long = "pg-copy", conflicts_with_all = ["c_array", "qr", "short", "export", "env_file", "markdown", "xml", "timestamped"])]
+    pg_copy: bool,
+
+    /// End --pg-copy output with the \. end-of-data marker
+    #[arg(long = "with-terminator", requires = "pg_copy")]
+    with_terminator: bool,
+
+    /// Write PostgreSQL's binary COPY format (COPY ... FROM STDIN WITH (FORMAT binary)) for a uuid column
+    #[arg(long = "pg-copy-binary", conflicts_with_all = ["pg_copy", "c_array", "qr", "short", "export", "env_file", "markdown", "xml", "timestamped", "per_line", "rate", "bom"])]
+    pg_copy_binary: bool,
+
+    /// Lay UUIDs out in columns filling the terminal width (one per line when stdout is not a terminal)
+    #[arg(long = "columns", conflicts_with_all = ["per_line", "rate", "qr", "c_array", "export", "split", "output", "fd", "markdown", "xml", "pg_copy", "pg_copy_binary"])]
+    columns: bool,
+
+    /// Write output to numbered files of N lines each (PREFIX1.txt, PREFIX2.txt, ...) instead of stdout
+    #[arg(long = "split", value_name = "N", value_parser = parse_split, conflicts_with_all = ["qr", "c_array"])]
+    split: Option<usize>,
+
+    /// File name prefix for --split (default: uuids-)
+    #[arg(long = "split-prefix", value_name = "PREFIX", default_value = "uuids-", requires = "split")]
+    split_prefix: String,
+
+    /// Header line for each --split file; {index} is the file number, {count} its number of data lines
+    #[arg(long = "split-header", value_name = "TEMPLATE", requires = "split")]
+    split_header: Option<String>,
+
+    /// Worker threads for --vanity (default: 1)
+    #[arg(short = 'j', long = "jobs", default_value = "1")]
+    jobs: usize,
+
+    /// Prefix each line with the UTC generation time, e.g. 2024-01-01T00:00:00.123Z
+    #[arg(long = "timestamped")]
+    timestamped: bool,
+
+    /// Language of messages: en or zh (default: from ZUUID_LANG, then LANG, LC_ALL or LC_MESSAGES)
+    #[arg(long = "lang", value_name = "LANG", env = "ZUUID_LANG", value_parser = parse_language, global = true)]
+    lang: Option<Language>,
+
+    /// Apply a named [profiles.NAME] section from the config file
+    #[arg(long = "profile", env = "ZUUID_PROFILE")]
+    profile: Option<String>,
+
+    /// Silence warnings by code or name, e.g. W001 or format-conflict (comma-separated)
+    #[arg(long = "no-warn", value_name = "CODE,...", value_delimiter = ',', env = "ZUUID_NO_WARN", global = true)]
+    no_warn: Vec<String>,
+
+    /// Script-friendly mode: only UUIDs on stdout, `zuuid: <code>: <message>` diagnostics on stderr,
+    /// exit codes 0 (success), 1 (generation failure), 2 (usage), 74 (I/O), 4 (invalid value)
+    #[arg(long = "porcelain", conflicts_with_all = ["timestamped", "short", "qr"])]
+    porcelain: bool,
+
+    /// Append a line per generated UUID (timestamp, UUID, version, user) to this file
+    /// The whole batch is logged before any of it is printed, so the streaming --per-line and --rate do not combine with it
+    #[arg(long = "audit-log", conflicts_with_all = ["per_line", "rate"])]
+    audit_log: Option<std::path::PathBuf>,
+
+    /// Keep generating with a warning if the audit log cannot be written
+    #[arg(long = "audit-best-effort", requires = "audit_log")]
+    audit_best_effort: bool,
+
+    /// Never issue a UUID listed in this file (one per line); the ones generated are appended under a lock
+    #[arg(long = "registry", value_name = "PATH", conflicts_with_all = ["per_line", "vanity", "from_phrase", "all_versions", "repl"])]
+    registry: Option<std::path::PathBuf>,
+
+    /// Keep the --registry file as 16-byte big-endian records, memory-mapped instead of loaded, for large registries
+    #[arg(long = "registry-binary", requires = "registry")]
+    registry_binary: bool,
+
+    /// Emit the batch in ascending order (buffers all UUIDs in memory)
+    #[arg(long = "sort")]
+    sort: bool,
+
+    /// Emit the batch in random order, reproducible with --seed (buffers all UUIDs in memory)
+    #[arg(long = "shuffle", conflicts_with_all = ["sort", "per_line", "vanity"])]
+    shuffle: bool,
+
+    /// Read stdin and emit one UUID per input line as it arrives, until stdin closes
+    #[arg(long = "per-line", conflicts_with_all = ["count", "sort"])]
+    per_line: bool,
+
+    /// With --per-line, each input line names the version of its UUID: v4 or v7
+    #[arg(long = "versions-from-stdin", requires = "per_line", conflicts_with_all = ["version", "mix", "v7_precision", "timestamp_ms", "counter_start", "names", "domain", "local_id", "target_partition"])]
+    versions_from_stdin: bool,
+
+    /// Seed the random bits for reproducible output
+    #[arg(long = "seed")]
+    seed: Option<u64>,
+
+    /// End every output line with CRLF instead of LF (also for sample and shuffle)
+    #[arg(long = "crlf", global = true)]
+    crlf: bool,
+
+    /// End the output with a "# sha256:<hex>" line covering every byte before it
+    #[arg(long = "checksum", conflicts_with_all = ["split", "append", "pg_copy", "pg_copy_binary", "xml", "verify_checksum"])]
+    checksum: bool,
+
+    /// Check that FILE matches its trailing --checksum line, then exit
+    #[arg(long = "verify-checksum", value_name = "FILE")]
+    verify_checksum: Option<std::path::PathBuf>,
+
+    /// Start the --output (or each --split) file with a UTF-8 byte order mark, for Excel and other Windows tools
+    #[arg(long = "bom", requires = "output_file")]
+    bom: bool,
+
+    /// Start the interactive session, like the repl subcommand
+    #[arg(long = "repl")]
+    repl: bool,
+
+    /// Print which entropy source is in use to stderr, then generate as usual
+    #[arg(long = "entropy-info")]
+    entropy_info: bool,
+
+    /// After the batch, print its count, rate and smallest/largest UUID (with v7 timestamps) to stderr
+    #[arg(long = "summary")]
+    summary: bool,
+
+    /// Like --summary, but as one JSON line on stderr
+    #[arg(long = "summary-json", conflicts_with = "summary")]
+    summary_json: bool,
+
+    /// After a v4 batch, print a chi-square test of the first byte's distribution to stderr, warning when it is skewed
+    #[arg(long = "distribution-check", conflicts_with_all = ["mix", "vanity", "from_phrase", "target_partition", "all_versions"])]
+    distribution_check: bool,
+
+    /// Leave out informational stderr lines: --summary, --summary-json, --distribution-check, --entropy-info and --verbose
+    #[arg(short = 'q', long = "quiet")]
+    quiet: bool,
+
+    /// Log the resolved version, format, entropy source and count to stderr before generating
+    #[arg(long = "verbose")]
+    verbose: bool,
+
+    /// Print one UUID of each version, labeled, instead of --count UUIDs of one version, between nil and max
+    /// v2 is included when a local ID is known, and v3 and v5 when --name is given, using the first name;
+    /// v1 and v6 are left out, since zuuid does not generate them
+    #[arg(long = "all-versions", conflicts_with_all = ["per_line", "vanity", "from_phrase", "rate", "c_array", "qr", "short", "export", "env_file", "markdown", "xml", "pg_copy", "pg_copy_binary", "columns", "split", "snippet", "audit_log", "summary", "summary_json", "repl"])]
+    all_versions: bool,
+
+    /// Print the UUIDs as one JSON array, or the --all-versions output as one JSON object keyed by version
+    /// An empty batch prints [], so -n 0 needs no --allow-empty; with --with-hash, --with-partition or
+    /// --show-variant each element is an object carrying those values as hash, partition and variant fields
+    #[arg(long = "json", conflicts_with_all = ["per_line", "rate", "qr", "c_array", "export", "env_file", "split", "markdown", "xml", "pg_copy", "pg_copy_binary", "columns", "snippet", "comment", "timestamped", "check_digit"])]
+    json: bool,
+
+    /// Print each UUID as a JSON object on its own line, with its version and variant, e.g. {"uuid":"...","version":7,"variant":"RFC4122"}
+    #[arg(long = "ndjson", conflicts_with_all = ["json", "all_versions", "qr", "c_array", "short", "export", "env_file", "markdown", "xml", "pg_copy", "pg_copy_binary", "columns", "snippet", "comment", "show_variant", "with_hash", "with_partition", "timestamped"])]
+    ndjson: bool,
+
+    /// Add a 0-based "index" field to each --ndjson object, counting lines in output order
+    #[arg(long = "with-index", requires = "ndjson")]
+    with_index: bool,
+
+    /// UUIDs to inspect instead of generating, one summary line each, as with decode --stdin
+    #[arg(value_name = "UUID", conflicts_with_all = ["repl", "split", "all_versions"])]
+    inputs: Vec<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Operations on existing UUID streams
+#[derive(Subcommand)]
+enum Command {
+    /// Pick a uniform random subset of input lines (reservoir sampling)
+    Sample(SampleArgs),
+    /// Print input lines in random order
+    Shuffle(ShuffleArgs),
+    /// Show the canonical form, version and variant of existing UUIDs
+    #[command(visible_alias = "inspect")]
+    Decode(DecodeArgs),
+    /// Measure generation and formatting throughput
+    Bench(BenchArgs),
+    /// Interactive session: generate and inspect UUIDs without retyping flags
+    Repl,
+    /// Check the RNG, clock, locale and config file, and report each; exits 1 if any check fails
+    Doctor,
+    /// Serve UUIDs over HTTP: GET /uuid?version=7&count=5&format=simple and GET /healthz
+    Serve(ServeArgs),
+    /// List the well-known UUIDs accepted by v3/v5 --namespace
+    Namespaces(NamespacesArgs),
+    /// Find the UUIDs in files and directories and count them per file
+    Scan(ScanArgs),
+    /// Convert UUIDs between encodings, e.g. --from hex --to uuid25
+    Convert(ConvertArgs),
+    /// Print only the input UUIDs that match every given predicate, lines unchanged
+    Filter(FilterArgs),
+    /// Give each input key a random v4 UUID, reusing the one recorded in --map-file for keys seen before
+    Map(MapArgs),
+    /// Read JSON from stdin and replace every placeholder string with a fresh v4 UUID
+    JsonFill(JsonFillArgs),
+    /// Show how long ago the timestamp of each v1, v6 or v7 UUID was, e.g. 3d 4h ago
+    Age(AgeArgs),
+    /// Pair each input UUID with a fresh one of the same version, as old<TAB>new, for key rotation
+    Rotate(RotateArgs),
+}
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct SampleArgs {
+    /// Read lines from standard input
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// Number of lines to sample (default: 1)
+    #[arg(short = 'n', long = "count", default_value = "1")]
+    count: usize,
+
+    /// Seed for a reproducible sample
+    #[arg(long = "seed")]
+    seed: Option<u64>,
+}
+
+/// Default upper bound on lines buffered by `shuffle`
+const DEFAULT_SHUFFLE_MAX_LINES: usize = 10_000_000;
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct ShuffleArgs {
+    /// Read lines from standard input
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// Maximum number of lines held in memory (default: 10000000)
+    #[arg(long = "max-lines", default_value_t = DEFAULT_SHUFFLE_MAX_LINES)]
+    max_lines: usize,
+
+    /// Seed for a reproducible order
+    #[arg(long = "seed")]
+    seed: Option<u64>,
+}
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct FilterArgs {
+    /// Read UUIDs from standard input, one per line, in any form decode accepts
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// Keep UUIDs of this version (1-8)
+    #[arg(long = "version", value_name = "N", value_parser = parse_version_number)]
+    version: Option<u8>,
+
+    /// Keep UUIDs whose embedded time is at or after TIME: YYYY-MM-DD, YYYY-MM-DDTHH:MM:SSZ or now
+    #[arg(long = "after", value_name = "TIME", value_parser = parse_time)]
+    after: Option<u64>,
+
+    /// Keep UUIDs whose embedded time is before TIME; UUIDs without a time never match --after or --before
+    #[arg(long = "before", value_name = "TIME", value_parser = parse_time)]
+    before: Option<u64>,
+
+    /// Keep UUIDs whose hex digits start with PREFIX, ignoring case and hyphens
+    #[arg(long = "prefix", value_parser = parse_hex_prefix)]
+    prefix: Option<String>,
+
+    /// Print the valid UUIDs that do not match instead
+    #[arg(long = "invert")]
+    invert: bool,
+
+    /// Report lines that are not UUIDs on stderr and go on, instead of failing on the first one
+    #[arg(long = "skip-invalid")]
+    skip_invalid: bool,
+}
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct DecodeArgs {
+    /// UUIDs in any accepted form: hyphenated, simple, braced or urn:uuid:, any case
+    #[arg(required_unless_present_any = ["stats", "stdin"])]
+    uuids: Vec<String>,
+
+    /// Print only the canonical lowercase hyphenated form, one per line
+    #[arg(long = "canonical-only")]
+    canonical_only: bool,
+
+    /// Require each UUID to end in a --check-digit suffix; it is verified, then stripped
+    #[arg(long = "expect-check-digit", conflicts_with_all = ["stats", "stdin"])]
+    expect_check_digit: bool,
+
+    /// Count versions and variants of the UUIDs on stdin, one per line
+    #[arg(long = "stats", conflicts_with_all = ["uuids", "canonical_only"])]
+    stats: bool,
+
+    /// Print each UUID as a hex dump with the version, variant and v7 timestamp bits marked
+    #[arg(long = "dump", conflicts_with_all = ["canonical_only", "stats", "stdin", "json", "json_pretty"])]
+    dump: bool,
+
+    /// Show the UUIDs on stdin, one per line, as a table; invalid lines get a marked row
+    #[arg(long = "stdin", conflicts_with_all = ["uuids", "canonical_only", "stats"])]
+    stdin: bool,
+
+    /// Fail on the first UUID whose version is not N (1 to 8), also when reading --stats input
+    #[arg(long = "require-version", value_name = "N", value_parser = parse_version_number, conflicts_with = "stdin")]
+    require_version: Option<u8>,
+
+    /// Print the --stats counts or the --stdin table as JSON
+    #[arg(long = "json", conflicts_with_all = ["uuids", "canonical_only"])]
+    json: bool,
+
+    /// Like --json, but indented with one array element per line
+    #[arg(long = "json-pretty", conflicts_with_all = ["uuids", "canonical_only"])]
+    json_pretty: bool,
+}
+
+impl DecodeArgs {
+    fn json_layout(&self) -> Option<JsonLayout> {
+        JsonLayout::from_flags(self.json, self.json_pretty)
+    }
+}
+
+/// How `--json` output is laid out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JsonLayout {
+    /// A single line
+    Compact,
+    /// Two-space indentation, one array element per line (`--json-pretty`)
+    Pretty,
+}
+
+impl JsonLayout {
+    fn from_flags(json: bool, pretty: bool) -> Option<Self> {
+        match (json, pretty) {
+            (_, true) => Some(JsonLayout::Pretty),
+            (true, false) => Some(JsonLayout::Compact),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Serialize `items` as a JSON array: one line, or with `Pretty` one compact element per indented line
+fn json_array_lines<T: Serialize>(items: &[T], layout: JsonLayout) -> Result<Vec<String>, AppError> {
+    let failure = |e: serde_json::Error| AppError::Failure(e.to_string());
+    if layout == JsonLayout::Compact || items.is_empty() {
+        return Ok(vec![serde_json::to_string(items).map_err(failure)?]);
+    }
+
+    let mut lines = vec!["[".to_string()];
+    for (i, item) in items.iter().enumerate() {
+        let comma = if i + 1 < items.len() { "," } else { "" };
+        lines.push(format!("  {}{}", serde_json::to_string(item).map_err(failure)?, comma));
+    }
+    lines.push("]".to_string());
+    Ok(lines)
+}
+
+/// How the 128 bits are written out
+/// Serialized under its command-line name, e.g. "base64url"
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Encoding {
+    /// Hexadecimal, shaped by -f/-s (default)
+    #[default]
+    Hex,
+    /// Exactly 25 base-36 digits, alphanumeric only and case-insensitive
+    Uuid25,
+    /// EFI_GUID C initializer: { 0x8BE4DF61, 0x93CA, 0x11D2, { 0xAA, 0x0D, ... }}
+    Efi,
+    /// ISO/IEC 9834-8 OID: 2.25. followed by the 128-bit value in decimal
+    Oid,
+    /// RFC 4648 base32 of the 16 bytes, unpadded: 26 uppercase letters and digits
+    Base32,
+    /// RFC 4648 base64 of the 16 bytes, unpadded: 22 characters
+    Base64,
+    /// RFC 4648 URL- and filename-safe base64 ("-" and "_" for "+" and "/"), unpadded: 22 characters
+    #[value(name = "base64url")]
+    Base64Url,
+    /// The 16 bytes themselves; only for convert --binary-in/--binary-out
+    Raw,
+}
+
+impl Encoding {
+    /// Length of the fixed-width text that --short can cut down, for encodings that have one
+    fn text_length(self) -> Option<usize> {
+        match self {
+            Encoding::Hex => Some(32),
+            Encoding::Uuid25 => Some(UUID25_LEN),
+            Encoding::Base32 | Encoding::Base64 | Encoding::Base64Url => {
+                self.rfc4648_alphabet().map(|(_, bits)| 128u32.div_ceil(bits) as usize)
+            }
+            Encoding::Efi | Encoding::Oid | Encoding::Raw => None,
+        }
+    }
+
+    /// Alphabet and bits per symbol of the RFC 4648 encodings
+    fn rfc4648_alphabet(self) -> Option<(&'static [u8], u32)> {
+        match self {
+            Encoding::Base32 => Some((BASE32_ALPHABET, 5)),
+            Encoding::Base64 => Some((BASE64_ALPHABET, 6)),
+            Encoding::Base64Url => Some((BASE64URL_ALPHABET, 6)),
+            Encoding::Hex | Encoding::Uuid25 | Encoding::Efi | Encoding::Oid | Encoding::Raw => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Hex => write!(f, "hex"),
+            Encoding::Uuid25 => write!(f, "uuid25"),
+            Encoding::Efi => write!(f, "efi"),
+            Encoding::Oid => write!(f, "oid"),
+            Encoding::Base32 => write!(f, "base32"),
+            Encoding::Base64 => write!(f, "base64"),
+            Encoding::Base64Url => write!(f, "base64url"),
+            Encoding::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+/// Text format of a generated UUID, as chosen by `bench --formats` or `serve`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TextFormat {
+    /// Hyphenated, 36 characters
+    Full,
+    /// No hyphens, 32 characters
+    Simple,
+    /// Unpadded RFC 4648 base64, 22 characters
+    Base64,
+}
+
+impl TextFormat {
+    /// Render a UUID in this format; `bench --formats` and `serve` always use lowercase hex
+    fn render(self, id: &Uuid) -> String {
+        let style = match self {
+            TextFormat::Full => Style::Hyphenated,
+            TextFormat::Simple => Style::Simple,
+            TextFormat::Base64 => return encode_rfc4648(id, BASE64_ALPHABET, 6),
+        };
+        format_uuid(id, &FormatOptions { style, case: Case::Lower })
+    }
+}
+
+impl std::str::FromStr for TextFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(TextFormat::Full),
+            "simple" => Ok(TextFormat::Simple),
+            "base64" => Ok(TextFormat::Base64),
+            _ => Err(Messages::global().invalid_text_format(s)),
+        }
+    }
+}
+
+impl std::fmt::Display for TextFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextFormat::Full => write!(f, "full"),
+            TextFormat::Simple => write!(f, "simple"),
+            TextFormat::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+/// Parse a duration such as 500ms, 3s or 1.5s (a bare number means seconds)
+fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let (number, scale) = match s.strip_suffix("ms") {
+        Some(millis) => (millis, 1e-3),
+        None => (s.strip_suffix('s').unwrap_or(s), 1.0),
+    };
+    match number.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value > 0.0 => std::time::Duration::try_from_secs_f64(value * scale)
+            .map_err(|_| Messages::global().invalid_duration(s)),
+        _ => Err(Messages::global().invalid_duration(s)),
+    }
+}
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct BenchArgs {
+    /// Timed run length per combination, e.g. 500ms or 3s (default: 3s)
+    #[arg(long = "duration", default_value = "3s", value_parser = parse_duration)]
+    duration: std::time::Duration,
+
+    /// UUIDs to generate and discard before each timed run (default: as many as a tenth of --duration allows)
+    #[arg(long = "warmup", value_name = "N")]
+    warmup: Option<u64>,
+
+    /// Comma-separated UUID versions to measure (default: 4,7)
+    #[arg(long = "versions", value_delimiter = ',', default_value = "4,7")]
+    versions: Vec<UuidVersion>,
+
+    /// Comma-separated formats to measure: full, simple, base64 (default: full,simple)
+    #[arg(long = "formats", value_delimiter = ',', default_value = "full,simple")]
+    formats: Vec<TextFormat>,
+
+    /// Print the results as JSON for tracking across releases
+    #[arg(long = "json")]
+    json: bool,
+
+    /// Like --json, but indented with one result per line
+    #[arg(long = "json-pretty")]
+    json_pretty: bool,
+}
+
+/// Default for --max-count: generous, but stops a mistyped count from running for hours
+const DEFAULT_MAX_COUNT: usize = 100_000_000;
+
+#[derive(Args)]
+struct NamespacesArgs {
+    /// Print the namespaces as JSON
+    #[arg(long = "json")]
+    json: bool,
+
+    /// Like --json, but indented with one namespace per line
+    #[arg(long = "json-pretty")]
+    json_pretty: bool,
+}
+
+#[derive(Args)]
+struct MapArgs {
+    /// Read keys from standard input, one per line; every line is a key, blank ones included
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// CSV of key,uuid records, created if missing; new keys are appended while it is locked
+    #[arg(long = "map-file", value_name = "PATH")]
+    map_file: std::path::PathBuf,
+}
+
+#[derive(Args)]
+struct AgeArgs {
+    /// UUIDs in any form decode accepts
+    #[arg(required_unless_present = "stdin", conflicts_with = "stdin")]
+    uuids: Vec<String>,
+
+    /// Read UUIDs from standard input, one per line; blank lines are skipped
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// Print the age in whole seconds, negative for timestamps in the future
+    #[arg(long = "seconds")]
+    seconds: bool,
+
+    /// Print n/a for UUIDs without a timestamp instead of failing
+    #[arg(long = "lenient")]
+    lenient: bool,
+}
+
+#[derive(Args)]
+struct RotateArgs {
+    /// Read UUIDs from standard input, one per line, in any form decode accepts; blank lines are skipped
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// Give each new v7 the old one's timestamp, with fresh random bits, instead of the current time
+    #[arg(long = "preserve-timestamp")]
+    preserve_timestamp: bool,
+}
+
+#[derive(Args)]
+struct JsonFillArgs {
+    /// String value to replace; only exact matches are replaced
+    #[arg(long = "placeholder", default_value = "$uuid")]
+    placeholder: String,
+
+    /// Also replace "<placeholder>:<key>" strings, giving every use of the same key the same UUID
+    #[arg(long = "shared-key")]
+    shared_key: bool,
+
+    /// Indent the output instead of writing it on one line
+    #[arg(long = "pretty")]
+    pretty: bool,
+}
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct ConvertArgs {
+    /// UUIDs to convert, in the --from encoding
+    #[arg(required_unless_present = "binary_in", value_name = "UUID")]
+    values: Vec<String>,
+
+    /// Encoding of the input; hex accepts every form decode does
+    #[arg(long = "from", value_enum, default_value_t)]
+    from: Encoding,
+
+    /// Encoding of the output; hex is the canonical hyphenated form
+    #[arg(long = "to", value_enum, default_value_t)]
+    to: Encoding,
+
+    /// Read back-to-back 16-byte records from stdin, with --from raw
+    #[arg(long = "binary-in", conflicts_with = "values")]
+    binary_in: bool,
+
+    /// Write each UUID as its 16 bytes, with --to raw
+    #[arg(long = "binary-out")]
+    binary_out: bool,
+
+    /// Drop a trailing partial record with a warning instead of failing
+    #[arg(long = "lenient", requires = "binary_in")]
+    lenient: bool,
+
+    /// Leave out the first N UUIDs
+    #[arg(long = "skip", value_name = "N", default_value_t = 0)]
+    skip: usize,
+
+    /// Convert at most N UUIDs after --skip
+    #[arg(long = "take", value_name = "N")]
+    take: Option<usize>,
+}
+
+/// Report format of `scan`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum)]
+enum ScanFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct ScanArgs {
+    /// Files and directories to scan; directories are walked recursively
+    #[arg(required = true, value_name = "PATH")]
+    paths: Vec<std::path::PathBuf>,
+
+    /// Directory levels to descend below each PATH; 0 scans only the files directly inside (default: unlimited)
+    #[arg(long = "max-depth", value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Also scan files that contain a NUL byte, which are otherwise skipped as binary
+    #[arg(long = "binary")]
+    binary: bool,
+
+    /// Report as text or as one JSON object
+    #[arg(long = "format", value_enum, default_value_t)]
+    format: ScanFormat,
+
+    /// Show every match as file:line:uuid (in JSON, a matches array per file)
+    #[arg(long = "list")]
+    list: bool,
+}
+
+/// Default per-request limit for `serve`
+const DEFAULT_SERVE_MAX_COUNT: usize = 10_000;
+
+#[derive(Args)]
+#[command(allow_negative_numbers = true)]
+struct ServeArgs {
+    /// Port to listen on (default: 8080)
+    #[arg(long = "port", default_value_t = 8080)]
+    port: u16,
+
+    /// Address to bind; only localhost by default
+    #[arg(long = "bind", default_value = "127.0.0.1")]
+    bind: std::net::IpAddr,
+
+    /// Largest count a single request may ask for (default: 10000)
+    #[arg(long = "max-count", default_value_t = DEFAULT_SERVE_MAX_COUNT)]
+    max_count: usize,
+
+    /// Worker threads handling requests (default: 4)
+    #[arg(short = 'j', long = "jobs", default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: u16,
+
+    /// Listen on a Unix domain socket instead of TCP; clients send "VERSION [COUNT [FORMAT]]"
+    #[arg(long = "unix", value_name = "PATH", conflicts_with_all = ["port", "bind", "jobs"])]
+    unix: Option<std::path::PathBuf>,
+
+    /// Replace an existing file at the --unix path
+    #[arg(long = "force", requires = "unix")]
+    force: bool,
+}
+
+/// Output settings that the config file can provide, at top level or per profile
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Settings {
+    /// UUID version, spelled as on the command line ("7", "v7")
+    version: Option<UuidVersion>,
+    /// "full" or "simple"
+    format: Option<Style>,
+    /// Uppercase output
+    upper: Option<bool>,
+    /// Largest count a run may generate
+    max_count: Option<usize>,
+}
+
+impl Settings {
+    /// Layer `other` on top of these settings; values set in `other` win
+    fn merge(self, other: Settings) -> Settings {
+        Settings {
+            version: other.version.or(self.version),
+            format: other.format.or(self.format),
+            upper: other.upper.or(self.upper),
+            max_count: other.max_count.or(self.max_count),
+        }
+    }
+}
+
+/// Config file contents: top-level defaults plus named `[profiles.NAME]` sections
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    version: Option<UuidVersion>,
+    format: Option<Style>,
+    upper: Option<bool>,
+    max_count: Option<usize>,
+    #[serde(default)]
+    profiles: std::collections::BTreeMap<String, Settings>,
+}
+
+impl Config {
+    /// Parse the TOML config text
+    fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.message().to_string())
+    }
+
+    /// Top-level settings with the selected profile layered on top
+    fn settings(self, profile: Option<&str>, msgs: &Messages) -> Result<Settings, String> {
+        let mut profiles = self.profiles;
+        let base = Settings {
+            version: self.version,
+            format: self.format,
+            upper: self.upper,
+            max_count: self.max_count,
+        };
+        let Some(name) = profile else {
+            return Ok(base);
+        };
+        match profiles.remove(name) {
+            Some(selected) => Ok(base.merge(selected)),
+            None => {
+                let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                Err(msgs.unknown_profile(name, &available))
+            }
+        }
+    }
+}
+
+/// Location of the config file: $ZUUID_CONFIG, else zuuid/config.toml in the user config directory
+fn config_path() -> Option<std::path::PathBuf> {
+    if let Some(path) = std::env::var_os("ZUUID_CONFIG") {
+        return Some(path.into());
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(std::path::PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config")))?;
+    Some(config_dir.join("zuuid").join("config.toml"))
+}
+
+/// Read the config file, treating a missing file as an empty config
+fn load_config(msgs: &Messages) -> Result<Config, AppError> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Config::parse(&text).map_err(|e| AppError::Invalid(msgs.config_error(&path, &e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(AppError::Io(msgs.config_error(&path, &e.to_string()))),
+    }
+}
+
+/// Fill in options from config settings, unless they were given explicitly on the command line
+/// Precedence: built-in defaults < config file < profile < command-line flags
+/// (and environment variables, for the options that read one)
+fn apply_settings(cli: &mut Cli, matches: &ArgMatches, settings: Settings) -> Result<(), String> {
+    let explicit = |id: &str| matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable));
+
+    if let Some(version) = settings.version
+        && !explicit("version")
+    {
+        cli.version = version;
+    }
+    if let Some(format) = settings.format
+        && !["simple", "full", "braced", "urn"].into_iter().any(explicit)
+    {
+        match format {
+            Style::Simple => cli.simple = true,
+            Style::Hyphenated => cli.full = true,
+            Style::Braced => cli.braced = true,
+            Style::Urn => cli.urn = true,
+        }
+    }
+    if let Some(upper) = settings.upper
+        && !explicit("uppercase")
+    {
+        cli.uppercase = upper;
+    }
+    if let Some(max_count) = settings.max_count
+        && !explicit("max_count")
+    {
+        cli.max_count = max_count;
+    }
+    Ok(())
+}
+
+/// Parse the command line with clap alone, before any config file is read
+/// Help and version requests exit directly; clap errors keep clap's own output
+/// unless porcelain mode asks for a single prefixed line, which restates rejected values as an `InputError`
+/// Rejected option values exit with the validation code (4), other clap errors with the usage code (2)
+fn parse_args(args: &[String], msgs: &Messages) -> Result<(Cli, ArgMatches), AppError> {
+    let parsed = Cli::command()
+        .try_get_matches_from(args)
+        .and_then(|matches| Ok((Cli::from_arg_matches(&matches)?, matches)));
+    match parsed {
+        Ok(parsed) => Ok(parsed),
+        Err(e) if !e.use_stderr() || !PORCELAIN.load(Ordering::Relaxed) => Err(AppError::Clap(localize_clap_error(e, msgs.lang))),
+        Err(e) => {
+            if let Some(err) = clap_input_error(&e, args, msgs) {
+                return Err(err.into());
+            }
+            let text = e.to_string();
+            let message = text.lines().next().unwrap_or_default().trim_start_matches("error: ").to_string();
+            Err(AppError::Usage(message))
+        }
+    }
+}
+
+/// Parse the command line (`parse_args`) and apply the config file and selected profile
+fn parse_cli(args: &[String], msgs: &Messages) -> Result<Cli, AppError> {
+    let (mut cli, matches) = parse_args(args, msgs)?;
+
+    // doctor reports on the config file itself, so a broken one must not stop it
+    if matches!(cli.command, Some(Command::Doctor)) {
+        return Ok(cli);
+    }
+    let settings = load_config(msgs)?.settings(cli.profile.as_deref(), msgs).map_err(AppError::Invalid)?;
+    apply_settings(&mut cli, &matches, settings).map_err(AppError::Invalid)?;
+    Ok(cli)
+}
+
+/// Attach the localized --uuid-version message to clap's English "possible values" error as a tip
+fn localize_clap_error(mut e: clap::Error, lang: Language) -> clap::Error {
+    let Some(ContextValue::String(value)) = e.get(ContextKind::InvalidValue) else {
+        return e;
+    };
+    let is_version = matches!(e.get(ContextKind::InvalidArg), Some(ContextValue::String(arg)) if arg.starts_with("--uuid-version"));
+    if e.kind() == ErrorKind::InvalidValue && is_version && lang != Language::English {
+        let tip = Messages::new(lang).invalid_version(value);
+        e.insert(ContextKind::Suggested, ContextValue::StyledStrs(vec![tip.into()]));
+    }
+    e
+}
+
+/// Restate clap's rejection of an option value as an `InputError`, finding the value's position among
+/// every value given to that option in `args`, counting delimited lists item by item
+fn clap_input_error(e: &clap::Error, args: &[String], msgs: &Messages) -> Option<InputError> {
+    if !matches!(e.kind(), ErrorKind::InvalidValue | ErrorKind::ValueValidation) {
+        return None;
+    }
+    let Some(ContextValue::String(value)) = e.get(ContextKind::InvalidValue) else {
+        return None;
+    };
+    let Some(ContextValue::String(shown)) = e.get(ContextKind::InvalidArg) else {
+        return None;
+    };
+    let name = shown.split_whitespace().next().unwrap_or(shown);
+    let long = name.strip_prefix("--");
+    let arg = long.and_then(|long| find_long_arg(&Cli::command(), long));
+
+    let reason = match e.get(ContextKind::ValidValue) {
+        _ if long == Some("uuid-version") || long == Some("versions") => msgs.version_reason().to_string(),
+        Some(ContextValue::Strings(valid)) => msgs.expected_one_of(&valid.join(", ")),
+        _ => std::error::Error::source(e).map(ToString::to_string).unwrap_or_default(),
+    };
+
+    let mut given = Vec::new();
+    if let Some(arg) = &arg {
+        let mut flags: Vec<String> = arg.get_long_and_visible_aliases().into_iter().flatten().map(|l| format!("--{}", l)).collect();
+        let shorts: Vec<String> = arg.get_short_and_visible_aliases().into_iter().flatten().map(|c| format!("-{}", c)).collect();
+        flags.extend(shorts.iter().cloned());
+        let mut iter = args.iter().skip(1);
+        while let Some(word) = iter.next() {
+            if word == "--" {
+                break;
+            }
+            let attached = flags.iter().find_map(|flag| {
+                let rest = word.strip_prefix(flag.as_str())?;
+                match rest.strip_prefix('=') {
+                    Some(value) => Some(Some(value)),
+                    None if rest.is_empty() => Some(None),
+                    None if !flag.starts_with("--") => Some(Some(rest)),
+                    None => None,
+                }
+            });
+            let values = match attached {
+                Some(None) => iter.next().map(String::as_str),
+                Some(value) => value,
+                None => None,
+            };
+            match (values, arg.get_value_delimiter()) {
+                (Some(values), Some(delimiter)) => given.extend(values.split(delimiter)),
+                (Some(values), None) => given.push(values),
+                (None, _) => {}
+            }
+        }
+    }
+    let index = given.iter().position(|given| given == value).map_or(1, |i| i + 1);
+    Some(InputError::new(name, index, value, reason))
+}
+
+/// The option spelled `--long` in `cmd` or any of its subcommands
+fn find_long_arg(cmd: &clap::Command, long: &str) -> Option<clap::Arg> {
+    cmd.get_arguments()
+        .find(|arg| arg.get_long() == Some(long))
+        .cloned()
+        .or_else(|| cmd.get_subcommands().find_map(|sub| find_long_arg(sub, long)))
+}
+
+/// Determine format precedence based on argument order
+/// Returns (prefer_full, conflict_detected)
+fn determine_format_precedence(args: &[String]) -> (bool, bool) {
+    // Find positions of format-related flags
+    let mut full_pos = None;
+    let mut simple_pos = None;
+
+    for (i, arg) in args.iter().enumerate() {
+        // Long flags only count on an exact match, so --sort or --seed are not mistaken for -s
+        match arg.as_str() {
+            "--full" if full_pos.is_none() => full_pos = Some(i * 1000),
+            "--simple" if simple_pos.is_none() => simple_pos = Some(i * 1000),
+            _ => {}
+        }
+
+        // Check for combined flags like -fs, -sf, -fS, -Sf, etc.
+        if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 1 {
+            let flags = &arg[1..]; // Remove leading '-'
+            for (j, ch) in flags.chars().enumerate() {
+                match ch {
+                    'f' | 'F' if full_pos.is_none() => {
+                        full_pos = Some(i * 1000 + j); // Use composite position
+                    }
+                    's' | 'S' if simple_pos.is_none() => {
+                        simple_pos = Some(i * 1000 + j);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    match (full_pos, simple_pos) {
+        (Some(f), Some(s)) => {
+            // Both flags present, check order
+            let prefer_full = f < s;
+            (prefer_full, true)
+        }
+        _ => (true, false), // Default to full, no conflict
+    }
+}
+
+/// Whether diagnostics use the stable porcelain format, set once from the command line
+static PORCELAIN: AtomicBool = AtomicBool::new(false);
+
+/// How diagnostics are written to stderr
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DiagnosticStyle {
+    /// `zuuid: <code>: <message>`
+    Porcelain,
+    /// Message wrapped in ANSI color
+    Color,
+    /// Message only, for redirected stderr and NO_COLOR
+    Plain,
+}
+
+/// Pick the diagnostic style; color is only used on a terminal and when NO_COLOR is not set
+fn diagnostic_style(porcelain: bool, stderr_is_terminal: bool, no_color: bool) -> DiagnosticStyle {
+    if porcelain {
+        DiagnosticStyle::Porcelain
+    } else if stderr_is_terminal && !no_color {
+        DiagnosticStyle::Color
+    } else {
+        DiagnosticStyle::Plain
+    }
+}
+
+/// The diagnostic style for this process's stderr
+fn stderr_style() -> DiagnosticStyle {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    diagnostic_style(PORCELAIN.load(Ordering::Relaxed), std::io::stderr().is_terminal(), no_color)
+}
+
+/// Whether output for stdout may be colored: it is a terminal and NO_COLOR is not set
+fn stdout_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    std::io::stdout().is_terminal() && !no_color
+}
+
+/// Format a diagnostic for stderr in the given style
+/// In color the reset always ends the line, even for an empty message; a reset inside the message
+/// (say, in an echoed input line) re-opens the color, so the rest of the line keeps it
+fn format_diagnostic(code: &str, color: &str, msg: &str, style: DiagnosticStyle) -> String {
+    match style {
+        DiagnosticStyle::Porcelain => format!("zuuid: {}: {}", code, msg),
+        DiagnosticStyle::Color => {
+            let msg = msg.replace("\x1b[0m", &format!("\x1b[0m\x1b[{}m", color));
+            format!("\x1b[{}m{}\x1b[0m", color, msg)
+        }
+        DiagnosticStyle::Plain => msg.to_string(),
+    }
+}
+
+/// A warning that --no-warn can silence
+/// Codes and names are stable across releases and are never translated, so wrappers can match on them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WarningCode {
+    /// -f and -s both given
+    FormatConflict = 1,
+    /// convert --lenient dropped a short trailing record
+    PartialRecord = 2,
+    /// --audit-best-effort stopped logging
+    AuditLogSkipped = 3,
+    /// scan could not read a file or directory
+    UnreadablePath = 4,
+    /// filter --skip-invalid skipped a line
+    InvalidLine = 5,
+    /// --env-file --append reuses a key
+    EnvKeyExists = 6,
+    /// --vanity prefix long enough to take a while
+    SlowVanity = 7,
+    /// --from-phrase UUIDs are guessable
+    InsecurePhrase = 8,
+    /// --warn-dup-names found a repeat
+    DuplicateName = 9,
+    /// --no-warn named a code that does not exist
+    UnknownCode = 10,
+    /// --mix weights did not add up to 100
+    MixNormalized = 11,
+    /// --distribution-check found the first byte far from uniform
+    SkewedDistribution = 12,
+}
+
+const WARNING_CODES: [WarningCode; 12] = [
+    WarningCode::FormatConflict,
+    WarningCode::PartialRecord,
+    WarningCode::AuditLogSkipped,
+    WarningCode::UnreadablePath,
+    WarningCode::InvalidLine,
+    WarningCode::EnvKeyExists,
+    WarningCode::SlowVanity,
+    WarningCode::InsecurePhrase,
+    WarningCode::DuplicateName,
+    WarningCode::UnknownCode,
+    WarningCode::MixNormalized,
+    WarningCode::SkewedDistribution,
+];
+
+impl WarningCode {
+    fn name(self) -> &'static str {
+        match self {
+            WarningCode::FormatConflict => "format-conflict",
+            WarningCode::PartialRecord => "partial-record",
+            WarningCode::AuditLogSkipped => "audit-log-skipped",
+            WarningCode::UnreadablePath => "unreadable-path",
+            WarningCode::InvalidLine => "invalid-line",
+            WarningCode::EnvKeyExists => "env-key-exists",
+            WarningCode::SlowVanity => "slow-vanity",
+            WarningCode::InsecurePhrase => "insecure-phrase",
+            WarningCode::DuplicateName => "duplicate-name",
+            WarningCode::UnknownCode => "unknown-warning-code",
+            WarningCode::MixNormalized => "mix-normalized",
+            WarningCode::SkewedDistribution => "skewed-distribution",
+        }
+    }
+
+    /// Look a warning up by code (W001) or name (format-conflict), in any case
+    fn lookup(s: &str) -> Option<Self> {
+        WARNING_CODES.into_iter().find(|code| s.eq_ignore_ascii_case(&code.to_string()) || s.eq_ignore_ascii_case(code.name()))
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl std::fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "W{:03}", *self as u32)
+    }
+}
+
+/// Warnings silenced by --no-warn, one bit per `WarningCode`, set once per run
+static SUPPRESSED_WARNINGS: AtomicU32 = AtomicU32::new(0);
+
+/// The --no-warn entries as a `SUPPRESSED_WARNINGS` mask, plus the entries that name no warning
+fn suppressed_warnings(entries: &[String]) -> (u32, Vec<&str>) {
+    let mut mask = 0;
+    let mut unknown = Vec::new();
+    for entry in entries.iter().map(|entry| entry.trim()).filter(|entry| !entry.is_empty()) {
+        match WarningCode::lookup(entry) {
+            Some(code) => mask |= code.bit(),
+            None => unknown.push(entry),
+        }
+    }
+    (mask, unknown)
+}
+
+/// Format a warning as `zuuid: warning[W001]: <message>` in every style, in yellow on a terminal
+fn format_warning(code: WarningCode, msg: &str, style: DiagnosticStyle) -> String {
+    let line = format_diagnostic(&format!("warning[{}]", code), "33", msg, DiagnosticStyle::Porcelain);
+    match style {
+        DiagnosticStyle::Color => format_diagnostic("warning", "33", &line, style),
+        DiagnosticStyle::Porcelain | DiagnosticStyle::Plain => line,
+    }
+}
+
+/// Print a warning unless --no-warn silenced its code
+fn print_warning(code: WarningCode, msg: &str) {
+    if SUPPRESSED_WARNINGS.load(Ordering::Relaxed) & code.bit() == 0 {
+        eprintln!("{}", format_warning(code, msg, stderr_style()));
+    }
+}
+
+/// Print an informational message in cyan, or with the `info` code in porcelain mode
+fn print_info(msg: &str) {
+    eprintln!("{}", format_diagnostic("info", "36", msg, stderr_style()));
+}
+
+/// Print the -f/-s conflict warning and which format won
+fn print_conflict_warning(prefer_full: bool, msgs: &Messages) {
+    print_warning(WarningCode::FormatConflict, msgs.conflict_warning());
+    if prefer_full {
+        print_warning(WarningCode::FormatConflict, msgs.using_full());
+    } else {
+        print_warning(WarningCode::FormatConflict, msgs.using_simple());
+    }
+}
+
+/// Source of random bits for UUID generation
+/// The OS RNG can fail on locked-down systems; the trait lets tests inject such failures
+trait EntropySource {
+    fn fill_random(&mut self, buf: &mut [u8]) -> Result<(), String>;
+}
+
+/// The operating system RNG (getrandom)
+struct OsEntropy;
+
+impl EntropySource for OsEntropy {
+    fn fill_random(&mut self, buf: &mut [u8]) -> Result<(), String> {
+        rand::rngs::OsRng.try_fill_bytes(buf).map_err(|e| e.to_string())
+    }
+}
+
+/// A seeded RNG for reproducible output; never fails
+impl EntropySource for StdRng {
+    fn fill_random(&mut self, buf: &mut [u8]) -> Result<(), String> {
+        self.fill_bytes(buf);
+        Ok(())
+    }
+}
+
+/// Attempts made to read the RNG before giving up
+const RNG_ATTEMPTS: u32 = 4;
+
+/// Delay before the first RNG retry, doubled after each failure
+const RNG_BACKOFF: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Fill `buf` with random bytes, retrying transient RNG failures with exponential backoff
+fn fill_random_with_retry(source: &mut dyn EntropySource, buf: &mut [u8], msgs: &Messages) -> Result<(), String> {
+    let mut delay = RNG_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match source.fill_random(buf) {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt >= RNG_ATTEMPTS => {
+                return Err(msgs.rng_unavailable(RNG_ATTEMPTS, &err));
+            }
+            Err(_) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Most recent v7 UUID issued by this process
+static LAST_V7: std::sync::Mutex<u128> = std::sync::Mutex::new(0);
+
+const RAND_B_BITS: u32 = 62;
+const RAND_B_MASK: u128 = (1 << RAND_B_BITS) - 1;
+
+/// Assemble a v7 value from a millisecond timestamp, a 42-bit counter and 32 random bits
+/// The counter fills rand_a and the top of rand_b, the same layout as uuid's `ContextV7`
+fn v7_from_parts(millis: u128, counter: u128, random: u32) -> u128 {
+    let payload = (counter << 32) | random as u128;
+    (millis << 80) | (0x7 << 76) | ((payload >> RAND_B_BITS) << 64) | (0b10 << 62) | (payload & RAND_B_MASK)
+}
+
+/// The next v7 value after `last`, keeping the low 32 random bits of `fresh`
+/// The 74 random bits are treated as a 42-bit counter over 32 random bits;
+/// the counter carries into the timestamp on overflow
+fn v7_successor(last: u128, fresh: u128) -> u128 {
+    let random_bits = |value: u128| (((value >> 64) & 0xfff) << RAND_B_BITS) | (value & RAND_B_MASK);
+
+    let millis = last >> 80;
+    let (millis, counter) = match (random_bits(last) >> 32) + 1 {
+        c if c >> 42 == 0 => (millis, c),
+        _ => (millis + 1, 0),
+    };
+    v7_from_parts(millis, counter, random_bits(fresh) as u32)
+}
+
+/// Build a v7 UUID that sorts after every v7 this process has issued before
+/// Within one millisecond (or if the clock steps back) the previous value's counter is bumped instead
+fn next_v7(unix_millis: u64, random: &[u8; 10]) -> Uuid {
+    let candidate = uuid::Builder::from_unix_timestamp_millis(unix_millis, random).into_uuid().as_u128();
+    let mut last = LAST_V7.lock().unwrap_or_else(|e| e.into_inner());
+    *last = if candidate > *last { candidate } else { v7_successor(*last, candidate) };
+    Uuid::from_u128(*last)
+}
+
+/// Largest timestamp the 48-bit unix_ts_ms field holds
+const V7_MAX_MILLIS: u64 = (1 << 48) - 1;
+
+/// Bits of the 42-bit sub-ms counter left below its 12 bits of sub-millisecond time
+const V7_COUNTER_BITS: u32 = 30;
+const V7_COUNTER_MAX: i64 = (1 << V7_COUNTER_BITS) - 1;
+
+/// Build a v7 UUID at --timestamp-ms (or now) with the counter seeded by --counter-start
+/// The first UUID's counter is 12 bits of sub-millisecond time over `start`; every later one
+/// bumps the whole 42-bit counter of `last`, carrying into the timestamp at rollover
+fn pinned_v7(
+    precision: V7Precision,
+    timestamp_ms: Option<u64>,
+    counter_start: Option<u32>,
+    last: Option<Uuid>,
+    source: &mut dyn EntropySource,
+    msgs: &Messages,
+) -> Result<Uuid, String> {
+    let mut random = [0u8; 16];
+    fill_random_with_retry(source, &mut random, msgs)?;
+    let random32 = u32::from_be_bytes(random[..4].try_into().unwrap());
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let (seconds, subsec_nanos) = match timestamp_ms {
+        Some(ms) => (ms / 1000, (ms % 1000) as u32 * 1_000_000),
+        None => (now.as_secs(), now.subsec_nanos()),
+    };
+
+    Ok(match (counter_start, last) {
+        (Some(_), Some(last)) => Uuid::from_u128(v7_successor(last.as_u128(), u128::from(random32))),
+        (Some(start), None) => {
+            let millis = u128::from(seconds) * 1000 + u128::from(subsec_nanos / 1_000_000);
+            let fraction = u128::from(subsec_nanos % 1_000_000) * 4096 / 1_000_000;
+            Uuid::from_u128(v7_from_parts(millis, (fraction << V7_COUNTER_BITS) | u128::from(start), random32))
+        }
+        (None, _) if precision == V7Precision::SubMillis => {
+            let context = SUB_MS_V7.lock().unwrap_or_else(|e| e.into_inner());
+            sub_ms_v7(&context, seconds, subsec_nanos, random32)
+        }
+        (None, _) => next_v7(seconds * 1000 + u64::from(subsec_nanos / 1_000_000), random[..10].try_into().unwrap()),
+    })
+}
+
+/// Shared clock sequence for sub-millisecond v7 UUIDs
+static SUB_MS_V7: std::sync::LazyLock<std::sync::Mutex<uuid::ContextV7>> =
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(uuid::ContextV7::new().with_additional_precision()));
+
+/// Build a v7 UUID whose counter comes from `context` at the given time
+/// With additional precision the counter starts with 12 bits of sub-millisecond time,
+/// and the context bumps it so UUIDs within one tick still sort in creation order
+fn sub_ms_v7(context: &uuid::ContextV7, seconds: u64, subsec_nanos: u32, random: u32) -> Uuid {
+    let (counter, seconds, subsec_nanos) = context.generate_timestamp_sequence(seconds, subsec_nanos);
+    let millis = seconds as u128 * 1000 + (subsec_nanos / 1_000_000) as u128;
+    Uuid::from_u128(v7_from_parts(millis, counter as u128, random))
+}
+
+/// Create a UUID of the given version from the given source of random bits
+/// Fails with a localized message if the RNG stays unavailable after retrying
+fn new_uuid(version: UuidVersion, precision: V7Precision, source: &mut dyn EntropySource, msgs: &Messages) -> Result<Uuid, String> {
+    let mut random = [0u8; 16];
+    fill_random_with_retry(source, &mut random, msgs)?;
+
+    Ok(match (version, precision) {
+        (UuidVersion::V2, _) => unreachable!("DCE Security UUIDs are built by new_dce_uuid"),
+        (UuidVersion::V3 | UuidVersion::V5, _) => unreachable!("name-based UUIDs are built by name_based_uuid"),
+        (UuidVersion::V4, _) => uuid::Builder::from_random_bytes(random).into_uuid(),
+        (UuidVersion::V7, V7Precision::Millis) => next_v7(unix_millis_now(), random[..10].try_into().unwrap()),
+        (UuidVersion::V7, V7Precision::SubMillis) => {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+            let context = SUB_MS_V7.lock().unwrap_or_else(|e| e.into_inner());
+            sub_ms_v7(&context, now.as_secs(), now.subsec_nanos(), u32::from_be_bytes(random[..4].try_into().unwrap()))
+        }
+    })
+}
+
+/// Pick the source of random bits: a seeded RNG when reproducibility is requested, else the OS
+fn entropy_source(seed: Option<u64>) -> Box<dyn EntropySource> {
+    match seed {
+        Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+        None => Box::new(OsEntropy),
+    }
+}
+
+/// A v4-shaped UUID from the first 16 bytes of SHA-256 over the phrase
+/// `index` numbers the UUIDs of a batch by hashing "phrase#index" instead
+fn phrase_uuid(phrase: &str, index: Option<usize>) -> Uuid {
+    use sha2::{Digest, Sha256};
+    let digest = match index {
+        Some(i) => Sha256::digest(format!("{}#{}", phrase, i)),
+        None => Sha256::digest(phrase),
+    };
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
+}
+
+/// The --comment annotation: "# v7 2024-06-01T12:00:00.000Z", or just "# v4" without a timestamp
+fn record_comment(prefix: &str, id: &Uuid) -> String {
+    let mut comment = format!("{} v{}", prefix, id.get_version_num());
+    if let Some(millis) = signed_unix_millis(id) {
+        comment.push(' ');
+        comment.push_str(&format_rfc3339_millis(millis));
+    }
+    comment
+}
+
+/// Lowercase hex SHA-256 of the UUID's 16 bytes, for --with-hash
+fn uuid_sha256(id: &Uuid) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(id.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Digits of the uuid25 encoding
+const BASE36_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Length of every uuid25 string; 36^25 is the smallest power of 36 above 2^128
+const UUID25_LEN: usize = 25;
+
+/// The UUID's 128 bits as base-36 digits, left-padded with zeros to exactly 25 (so the nil UUID is 25 zeros)
+fn encode_uuid25(id: &Uuid, case: Case) -> String {
+    let mut value = id.as_u128();
+    let mut digits = [b'0'; UUID25_LEN];
+    for digit in digits.iter_mut().rev() {
+        *digit = BASE36_DIGITS[(value % 36) as usize];
+        value /= 36;
+    }
+    let text = String::from_utf8_lossy(&digits).into_owned();
+    match case {
+        Case::Lower => text,
+        Case::Upper => text.to_uppercase(),
+    }
+}
+
+/// Parse 25 base-36 digits in any case; values past the largest UUID (f5lxx1zz5pnorynqglhzmsp33) are rejected
+fn decode_uuid25(s: &str) -> Option<Uuid> {
+    if s.len() != UUID25_LEN {
+        return None;
+    }
+    s.chars().try_fold(0u128, |value, c| value.checked_mul(36)?.checked_add(u128::from(c.to_digit(36)?))).map(Uuid::from_u128)
+}
+
+/// RFC 4648 base32 alphabet
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// RFC 4648 base64 alphabet
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// RFC 4648 URL- and filename-safe base64 alphabet
+const BASE64URL_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The 128 bits, most significant first, in symbols of `bits` bits from `alphabet`, without padding
+/// The last symbol is filled up with zero bits, so base32 is 26 characters and base64 22
+fn encode_rfc4648(id: &Uuid, alphabet: &[u8], bits: u32) -> String {
+    let value = id.as_u128();
+    let mask = (1u128 << bits) - 1;
+    (1..=128u32.div_ceil(bits))
+        .map(|i| {
+            let symbol = match 128u32.checked_sub(bits * i) {
+                Some(shift) => value >> shift,
+                None => value << (bits * i - 128),
+            };
+            char::from(alphabet[(symbol & mask) as usize])
+        })
+        .collect()
+}
+
+/// Parse what `encode_rfc4648` writes; the fill bits of the last symbol must be zero
+/// Base32 letters are accepted in either case, base64 is case-sensitive
+fn decode_rfc4648(s: &str, alphabet: &[u8], bits: u32) -> Option<Uuid> {
+    let symbols = 128u32.div_ceil(bits);
+    if s.len() != symbols as usize {
+        return None;
+    }
+    let fill = bits * symbols - 128;
+    let mut value = 0u128;
+    for (i, c) in s.bytes().enumerate() {
+        let c = if bits == 5 { c.to_ascii_uppercase() } else { c };
+        let symbol = alphabet.iter().position(|&a| a == c)? as u128;
+        value = if i + 1 == symbols as usize {
+            if symbol & ((1 << fill) - 1) != 0 {
+                return None;
+            }
+            (value << (bits - fill)) | (symbol >> fill)
+        } else {
+            (value << bits) | symbol
+        };
+    }
+    Some(Uuid::from_u128(value))
+}
+
+/// Concatenate `items` and break the result into lines of `width` characters; 0 keeps it one line
+fn wrap_concatenated(items: &[&str], width: usize) -> Vec<String> {
+    let text = items.concat();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    if width == 0 {
+        return vec![text];
+    }
+    text.as_bytes().chunks(width).map(|chunk| String::from_utf8_lossy(chunk).into_owned()).collect()
+}
+
+/// The EFI_GUID C initializer of `id`: Data1, Data2 and Data3 are the first three groups of the
+/// canonical form as numbers, Data4 the last eight bytes, all in uppercase hex
+fn efi_guid(id: &Uuid) -> String {
+    let (data1, data2, data3, data4) = id.as_fields();
+    let bytes: Vec<String> = data4.iter().map(|b| format!("0x{:02X}", b)).collect();
+    format!("{{ 0x{:08X}, 0x{:04X}, 0x{:04X}, {{ {} }}}}", data1, data2, data3, bytes.join(", "))
+}
+
+/// Parse an EFI_GUID initializer as `efi_guid` writes it, ignoring whitespace and the case of the hex digits
+fn parse_efi_guid(s: &str) -> Option<Uuid> {
+    let compact: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let (head, tail) = compact.strip_prefix('{')?.strip_suffix("}}")?.split_once(",{")?;
+    let number = |field: &str, digits: usize| {
+        let hex = field.strip_prefix("0x").or_else(|| field.strip_prefix("0X"))?;
+        (hex.len() <= digits).then(|| u32::from_str_radix(hex, 16).ok()).flatten()
+    };
+    let fields: Vec<&str> = head.split(',').collect();
+    let [data1, data2, data3] = fields[..] else {
+        return None;
+    };
+    let data4: Vec<u8> = tail.split(',').map(|b| number(b, 2).map(|b| b as u8)).collect::<Option<_>>()?;
+    Some(Uuid::from_fields(
+        number(data1, 8)?,
+        number(data2, 4)? as u16,
+        number(data3, 4)? as u16,
+        &data4.try_into().ok()?,
+    ))
+}
+
+/// The arc under which ISO/IEC 9834-8 places UUIDs as OIDs
+const UUID_OID_PREFIX: &str = "2.25.";
+
+/// The ISO/IEC 9834-8 OID of `id`: 2.25. followed by its 128 bits as one unsigned decimal number
+fn uuid_oid(id: &Uuid) -> String {
+    format!("{}{}", UUID_OID_PREFIX, id.as_u128())
+}
+
+/// Parse a 2.25.N OID; N must be a decimal arc without leading zeros that fits in 128 bits
+fn parse_uuid_oid(s: &str) -> Option<Uuid> {
+    let arc = s.strip_prefix(UUID_OID_PREFIX)?;
+    if arc.is_empty() || !arc.bytes().all(|b| b.is_ascii_digit()) || (arc.len() > 1 && arc.starts_with('0')) {
+        return None;
+    }
+    arc.parse::<u128>().ok().map(Uuid::from_u128)
+}
+
+/// Convert the UUIDs of `convert` from one encoding to the other: one line each, or 16 bytes each with --binary-out
+/// With --binary-in they are read from `input` as raw records instead of from the arguments
+fn convert<R: std::io::Read>(args: &ConvertArgs, input: R, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    if args.binary_in != (args.from == Encoding::Raw) || args.binary_out != (args.to == Encoding::Raw) {
+        return Err(AppError::Usage(msgs.raw_needs_binary().to_string()));
+    }
+    let mut emit = |id: Uuid| {
+        match args.to {
+            Encoding::Hex => write!(out, "{}{}", format_uuid(&id, &FormatOptions::default()), eol),
+            Encoding::Uuid25 => write!(out, "{}{}", encode_uuid25(&id, Case::Lower), eol),
+            Encoding::Efi => write!(out, "{}{}", efi_guid(&id), eol),
+            Encoding::Oid => write!(out, "{}{}", uuid_oid(&id), eol),
+            Encoding::Base32 | Encoding::Base64 | Encoding::Base64Url => {
+                let (alphabet, bits) = args.to.rfc4648_alphabet().expect("an RFC 4648 encoding");
+                write!(out, "{}{}", encode_rfc4648(&id, alphabet, bits), eol)
+            }
+            Encoding::Raw => out.write_all(id.as_bytes()),
+        }
+        .map_err(|e| output_error(e, msgs))
+    };
+    let take = args.take.unwrap_or(usize::MAX);
+    if args.binary_in {
+        read_raw_uuids(input, args.skip, take, args.lenient, &mut emit, msgs)?;
+    } else {
+        for (i, value) in args.values.iter().enumerate().skip(args.skip).take(take) {
+            let id = match args.from {
+                Encoding::Hex => Uuid::parse_str(value.trim()).ok(),
+                Encoding::Uuid25 => decode_uuid25(value.trim()),
+                Encoding::Efi => parse_efi_guid(value),
+                Encoding::Oid => parse_uuid_oid(value.trim()),
+                Encoding::Base32 | Encoding::Base64 | Encoding::Base64Url => {
+                    let (alphabet, bits) = args.from.rfc4648_alphabet().expect("an RFC 4648 encoding");
+                    decode_rfc4648(value.trim(), alphabet, bits)
+                }
+                Encoding::Raw => unreachable!("raw input is only read with --binary-in"),
+            };
+            emit(id.ok_or_else(|| InputError::new("UUID", i + 1, value, msgs.not_encoded(args.from)))?)?;
+        }
+    }
+    out.flush().map_err(|e| output_error(e, msgs))
+}
+
+/// Read back-to-back 16-byte UUIDs, leaving out the first `skip` and stopping after `take`
+/// A trailing partial record is an error, or is dropped with a warning when `lenient`
+fn read_raw_uuids<R: std::io::Read>(
+    mut reader: R,
+    skip: usize,
+    take: usize,
+    lenient: bool,
+    emit: &mut dyn FnMut(Uuid) -> Result<(), AppError>,
+    msgs: &Messages,
+) -> Result<(), AppError> {
+    let mut index = 0;
+    let mut emitted = 0;
+    while emitted < take {
+        let mut record = [0u8; 16];
+        let mut filled = 0;
+        while filled < record.len() {
+            match reader.read(&mut record[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(AppError::Io(msgs.read_error(&e))),
+            }
+        }
+        match filled {
+            0 => break,
+            16 => {}
+            partial => {
+                let message = msgs.partial_record(partial, index * 16);
+                if lenient {
+                    print_warning(WarningCode::PartialRecord, &message);
+                    break;
+                }
+                return Err(AppError::Invalid(message));
+            }
+        }
+        if index >= skip {
+            emit(Uuid::from_bytes(record))?;
+            emitted += 1;
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Crockford's base32 symbols followed by the five extra check symbols, indexed by value 0 to 36
+const CHECK_SYMBOLS: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+/// Crockford check symbol of the UUID: its 128 bits read as one big-endian number, mod 37
+/// 37 is prime and does not divide any 16^k, so changing one hex digit, or swapping two different
+/// adjacent ones (a change of (a - b) * 15 * 16^k), always changes the symbol
+fn check_symbol(id: &Uuid) -> char {
+    char::from(CHECK_SYMBOLS[(id.as_u128() % 37) as usize])
+}
+
+/// The formatted UUID with its check symbol: "-X" after the hyphenated form, "*X" after the simple one
+fn with_check_digit(id: &Uuid, options: &FormatOptions) -> String {
+    let separator = match options.style {
+        Style::Hyphenated | Style::Braced | Style::Urn => '-',
+        Style::Simple => '*',
+    };
+    let symbol = match options.case {
+        Case::Lower => check_symbol(id).to_ascii_lowercase(),
+        Case::Upper => check_symbol(id),
+    };
+    format!("{}{}{}", format_uuid(id, options), separator, symbol)
+}
+
+/// Verify and strip a --check-digit suffix; the symbol is compared without regard to case
+fn strip_check_digit(input: &str, msgs: &Messages) -> Result<Uuid, String> {
+    let mut chars = input.chars();
+    let (Some(symbol), Some('-' | '*')) = (chars.next_back(), chars.next_back()) else {
+        return Err(msgs.check_digit_missing(input));
+    };
+    let id = Uuid::parse_str(chars.as_str()).map_err(|_| msgs.check_digit_missing(input))?;
+    let expected = check_symbol(&id);
+    if !symbol.eq_ignore_ascii_case(&expected) {
+        return Err(msgs.check_digit_mismatch(input, expected));
+    }
+    Ok(id)
+}
+
+/// Kafka's murmur2 hash of a message key, as used by its default partitioner
+fn kafka_murmur2(data: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    let mut h = 0x9747_b28c ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M) ^ k;
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, &byte) in tail.iter().enumerate() {
+            h ^= u32::from(byte) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^ (h >> 15)
+}
+
+/// The partition Kafka's default partitioner picks for `key` among `partitions`
+fn kafka_partition(key: &str, partitions: u32) -> u32 {
+    (kafka_murmur2(key.as_bytes()) & 0x7fff_ffff) % partitions
+}
+
+/// Most UUIDs tried for one --target-partition match; about a million times the odds for each partition
+const PARTITION_SEARCH_ATTEMPTS: u32 = 1_000_000;
+
+/// Draw UUIDs from `next` until one formatted with `options` lands in partition `target`
+fn find_partition(
+    target: u32,
+    partitions: u32,
+    options: &FormatOptions,
+    mut next: impl FnMut() -> Result<Uuid, String>,
+    msgs: &Messages,
+) -> Result<Uuid, String> {
+    for _ in 0..PARTITION_SEARCH_ATTEMPTS {
+        let id = next()?;
+        if kafka_partition(&format_uuid(&id, options), partitions) == target {
+            return Ok(id);
+        }
+    }
+    Err(msgs.partition_not_found(target, PARTITION_SEARCH_ATTEMPTS))
+}
+
+/// The variant of `id` as decided by its top variant bits, for --show-variant
+fn variant_label(id: &Uuid) -> &'static str {
+    match id.get_variant() {
+        uuid::Variant::NCS => "NCS",
+        uuid::Variant::RFC4122 => "RFC4122",
+        uuid::Variant::Microsoft => "Microsoft",
+        _ => "Future",
+    }
+}
+
+/// Derive a v3 or v5 UUID from a namespace and name
+fn name_based_uuid(version: UuidVersion, namespace: &Uuid, name: &str) -> Uuid {
+    match version {
+        UuidVersion::V3 => Uuid::new_v3(namespace, name.as_bytes()),
+        _ => Uuid::new_v5(namespace, name.as_bytes()),
+    }
+}
+
+/// 100-nanosecond intervals between the Gregorian epoch (1582-10-15) and the Unix epoch
+const GREGORIAN_OFFSET: u64 = 0x01b2_1dd2_1381_4000;
+
+/// The local ID a v2 UUID gets without --local-id: the process's real UID or GID
+/// There is no default for the org domain, nor on systems without POSIX IDs
+fn default_local_id(domain: DceDomain) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid and getgid cannot fail and touch no memory of ours
+        match domain {
+            DceDomain::Person => Some(unsafe { libc::getuid() }),
+            DceDomain::Group => Some(unsafe { libc::getgid() }),
+            DceDomain::Org => None,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = domain;
+        None
+    }
+}
+
+/// Build a DCE Security (v2) UUID from a v1 layout: the local ID takes the place of time_low
+/// and the domain that of clock_seq_low, leaving 6 bits of clock sequence in `random[0]`
+/// and a random node with the multicast bit set in `random[1..]`
+fn dce_uuid(domain: DceDomain, local_id: u32, timestamp: u64, random: &[u8; 7]) -> Uuid {
+    let mut bytes = [0u8; 16];
+    bytes[..4].copy_from_slice(&local_id.to_be_bytes());
+    bytes[4..6].copy_from_slice(&((timestamp >> 32) as u16).to_be_bytes());
+    bytes[6..8].copy_from_slice(&((timestamp >> 48) as u16 & 0x0fff | 0x2000).to_be_bytes());
+    bytes[8] = 0x80 | (random[0] & 0x3f);
+    bytes[9] = domain as u8;
+    bytes[10..].copy_from_slice(&random[1..]);
+    bytes[10] |= 0x01;
+    Uuid::from_bytes(bytes)
+}
+
+/// Create a v2 UUID for the current time
+fn new_dce_uuid(domain: DceDomain, local_id: u32, source: &mut dyn EntropySource, msgs: &Messages) -> Result<Uuid, String> {
+    let mut random = [0u8; 7];
+    fill_random_with_retry(source, &mut random, msgs)?;
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let timestamp = (now.as_nanos() / 100) as u64 + GREGORIAN_OFFSET;
+    Ok(dce_uuid(domain, local_id, timestamp, &random))
+}
+
+/// Domain octet and local ID of a v2 UUID
+fn dce_fields(id: &Uuid) -> Option<(u8, u32)> {
+    if id.get_version_num() != 2 {
+        return None;
+    }
+    let bytes = id.as_bytes();
+    Some((bytes[9], u32::from_be_bytes(bytes[..4].try_into().unwrap())))
+}
+
+/// Name of a v2 domain octet, or the number itself for one DCE does not define
+fn dce_domain_label(domain: u8) -> String {
+    match DceDomain::value_variants().iter().find(|&&known| known as u8 == domain) {
+        Some(known) => known.to_string(),
+        None => domain.to_string(),
+    }
+}
+
+/// Names that appear more than once, each reported once in order of first repeat
+fn duplicate_names(names: &[String]) -> Vec<&str> {
+    let mut seen = std::collections::HashSet::new();
+    let mut reported = std::collections::HashSet::new();
+    names
+        .iter()
+        .filter(|name| !seen.insert(name.as_str()) && reported.insert(name.as_str()))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Names with later repeats removed, keeping first-occurrence order
+fn unique_names(names: &[String]) -> Vec<&str> {
+    let mut seen = std::collections::HashSet::new();
+    names.iter().map(String::as_str).filter(|name| seen.insert(*name)).collect()
+}
+
+/// Generate v4 UUIDs on `jobs` threads until one's simple form starts with `prefix` (lowercase hex)
+/// `progress` is called about once per second with the attempts so far and the elapsed time
+fn vanity_search(prefix: &str, jobs: usize, mut progress: impl FnMut(u64, std::time::Duration)) -> Uuid {
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+    // Attempts per worker between checks of the shared state
+    const BATCH: u64 = 1024;
+
+    let found = std::sync::Mutex::new(None);
+    let done = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let start = std::time::Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| {
+                let mut rng = rand::rng();
+                let mut buf = Uuid::encode_buffer();
+                while !done.load(Ordering::Relaxed) {
+                    for _ in 0..BATCH {
+                        let id = uuid::Builder::from_random_bytes(rng.random()).into_uuid();
+                        if id.as_simple().encode_lower(&mut buf).starts_with(prefix) {
+                            found.lock().unwrap().get_or_insert(id);
+                            done.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+                    attempts.fetch_add(BATCH, Ordering::Relaxed);
+                }
+            });
+        }
+
+        let mut next_report = std::time::Duration::from_secs(1);
+        while !done.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            let elapsed = start.elapsed();
+            if elapsed >= next_report {
+                progress(attempts.load(Ordering::Relaxed), elapsed);
+                next_report += std::time::Duration::from_secs(1);
+            }
+        }
+    });
+
+    found.into_inner().unwrap().expect("a worker stores its match before signalling done")
+}
+
+/// Generate `count` UUIDs and return them in ascending order
+fn generate_sorted(
+    version: UuidVersion,
+    precision: V7Precision,
+    count: usize,
+    source: &mut dyn EntropySource,
+    msgs: &Messages,
+) -> Result<Vec<Uuid>, String> {
+    let mut ids = (0..count).map(|_| new_uuid(version, precision, source, msgs)).collect::<Result<Vec<_>, _>>()?;
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Keep only the last `len` characters of the --encoding text (hex digits of the simple form, uuid25,
+/// base32 or base64), for compact display IDs; `len` must be within `Encoding::text_length`
+fn short_id(id: &Uuid, len: usize, case: Case, encoding: Encoding) -> String {
+    let text = match (encoding, encoding.rfc4648_alphabet()) {
+        (_, Some((alphabet, bits))) => encode_rfc4648(id, alphabet, bits),
+        (Encoding::Uuid25, None) => encode_uuid25(id, case),
+        _ => format_uuid(id, &FormatOptions { style: Style::Simple, case }),
+    };
+    text[text.len() - len..].to_string()
+}
+
+/// Format the 16 bytes as a C initializer, e.g. {0x12, 0x34, ...}
+/// Uppercase applies to the hex digits only, keeping the 0x prefix
+fn c_array(id: &Uuid, case: Case, name: Option<&str>) -> String {
+    let bytes: Vec<String> = id
+        .as_bytes()
+        .iter()
+        .map(|b| match case {
+            Case::Lower => format!("0x{:02x}", b),
+            Case::Upper => format!("0x{:02X}", b),
+        })
+        .collect();
+    let literal = format!("{{{}}}", bytes.join(", "));
+    match name {
+        Some(name) => format!("const uint8_t {}[16] = {};", name, literal),
+        None => literal,
+    }
+}
+
+/// Quote `value` as a single-quoted string literal of `shell`
+fn shell_quote(value: &str, shell: ShellDialect) -> String {
+    let escaped = match shell {
+        ShellDialect::Sh => value.replace('\'', r"'\''"),
+        ShellDialect::Fish => value.replace('\\', r"\\").replace('\'', r"\'"),
+        ShellDialect::PowerShell => value.replace('\'', "''"),
+    };
+    format!("'{}'", escaped)
+}
+
+/// A statement assigning `value` to the environment variable `name`
+fn export_statement(name: &str, value: &str, shell: ShellDialect) -> String {
+    let value = shell_quote(value, shell);
+    match shell {
+        ShellDialect::Sh => format!("export {}={}", name, value),
+        ShellDialect::Fish => format!("set -gx {} {}", name, value),
+        ShellDialect::PowerShell => format!("$env:{} = {}", name, value),
+    }
+}
+
+/// Encode `text` as a QR code
+fn qr_code(text: &str, msgs: &Messages) -> Result<qrcode::QrCode, String> {
+    qrcode::QrCode::new(text).map_err(|e| msgs.qr_error(&e.to_string()))
+}
+
+/// Draw a QR code with Unicode half blocks, two modules per character cell
+/// Colors are inverted so the code reads correctly as light blocks on a dark terminal
+fn render_qr(code: &qrcode::QrCode) -> String {
+    use qrcode::render::unicode::Dense1x2;
+    code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build()
+}
+
+/// Text layout of a formatted UUID, spelled "full" or "simple" as in the config file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+enum Style {
+    /// 8-4-4-4-12 with hyphens, 36 characters
+    #[default]
+    Hyphenated,
+    /// No hyphens, 32 characters
+    Simple,
+    /// Hyphenated inside braces, 38 characters
+    Braced,
+    /// Hyphenated after urn:uuid:, 45 characters
+    Urn,
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(Style::Hyphenated),
+            "simple" => Ok(Style::Simple),
+            "braced" => Ok(Style::Braced),
+            "urn" => Ok(Style::Urn),
+            _ => Err(Messages::global().invalid_format(s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Style {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Style> for String {
+    fn from(style: Style) -> Self {
+        style.to_string()
+    }
+}
+
+impl std::fmt::Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Style::Hyphenated => write!(f, "full"),
+            Style::Simple => write!(f, "simple"),
+            Style::Braced => write!(f, "braced"),
+            Style::Urn => write!(f, "urn"),
+        }
+    }
+}
+
+/// Letter case of the hex digits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Case {
+    #[default]
+    Lower,
+    Upper,
+}
+
+/// How `format_uuid` renders a UUID, resolved once from the flags
+/// Serialized as `{"style": "simple", "case": "upper"}`; missing fields take the defaults
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FormatOptions {
+    style: Style,
+    case: Case,
+}
+
+/// The -s/-f precedence table: -s alone is simple, -f alone or neither is hyphenated,
+/// and when both are given `prefer_full` (the later flag wins) picks between them
+fn resolve_output_style(simple: bool, full: bool, prefer_full: bool) -> Style {
+    match (simple, full) {
+        (true, true) if prefer_full => Style::Hyphenated,
+        (true, _) => Style::Simple,
+        (false, _) => Style::Hyphenated,
+    }
+}
+
+impl FormatOptions {
+    /// Resolve -U, -s and -f; see `resolve_output_style` for how -s and -f combine
+    fn from_flags(uppercase: bool, simple: bool, full: bool, prefer_full: bool) -> Self {
+        let style = resolve_output_style(simple, full, prefer_full);
+        let case = if uppercase { Case::Upper } else { Case::Lower };
+        FormatOptions { style, case }
+    }
+
+    /// The options selected on the command line, see `determine_format_precedence` for `prefer_full`
+    /// --braced and --urn conflict with -s and -f, so they need no precedence
+    fn from_cli(cli: &Cli, prefer_full: bool) -> Self {
+        let options = Self::from_flags(cli.uppercase, cli.simple, cli.full, prefer_full);
+        let style = match (cli.braced, cli.urn) {
+            (true, _) => Style::Braced,
+            (_, true) => Style::Urn,
+            _ => options.style,
+        };
+        FormatOptions { style, ..options }
+    }
+}
+
+/// Render a UUID in the given style and case
+/// Uppercase applies to the hex digits only: braces stay, and so does the lowercase urn:uuid: prefix
+fn format_uuid(id: &Uuid, options: &FormatOptions) -> String {
+    let mut buf = Uuid::encode_buffer();
+    let upper = options.case == Case::Upper;
+    let text = match (options.style, upper) {
+        (Style::Hyphenated, false) => id.hyphenated().encode_lower(&mut buf),
+        (Style::Hyphenated, true) => id.hyphenated().encode_upper(&mut buf),
+        (Style::Simple, false) => id.simple().encode_lower(&mut buf),
+        (Style::Simple, true) => id.simple().encode_upper(&mut buf),
+        (Style::Braced, false) => id.braced().encode_lower(&mut buf),
+        (Style::Braced, true) => id.braced().encode_upper(&mut buf),
+        (Style::Urn, false) => id.urn().encode_lower(&mut buf),
+        (Style::Urn, true) => return format!("urn:uuid:{}", id.hyphenated().encode_upper(&mut buf)),
+    };
+    text.to_string()
+}
+
+/// Create the RNG for stream operations, seeded when reproducibility is requested
+fn stream_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    }
+}
+
+/// Select `count` lines uniformly at random using reservoir sampling (Algorithm R)
+/// Memory use is bounded by `count` regardless of input size
+fn sample_lines<R: BufRead>(reader: R, count: usize, rng: &mut impl Rng, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    let mut reservoir = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        if i < count {
+            reservoir.push(line);
+        } else {
+            // Keep the new line with probability count / (i + 1)
+            let j = rng.random_range(0..=i);
+            if j < count {
+                reservoir[j] = line;
+            }
+        }
+    }
+
+    Ok(reservoir)
+}
+
+/// Read up to `max_lines` lines and return them in random order
+/// Fails instead of buffering an unbounded amount of input
+fn shuffle_lines<R: BufRead>(reader: R, max_lines: usize, rng: &mut impl Rng, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    let mut lines = Vec::new();
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        if lines.len() == max_lines {
+            return Err(AppError::Invalid(msgs.too_many_lines(max_lines)));
+        }
+        lines.push(line);
+    }
+
+    lines.shuffle(rng);
+    Ok(lines)
+}
+
+/// UTF-8 byte order mark written by --bom
+const UTF8_BOM: &[u8] = "\u{feff}".as_bytes();
+
+/// Record terminator: CRLF with --crlf, else LF on every platform
+fn line_ending(crlf: bool) -> &'static str {
+    if crlf { "\r\n" } else { "\n" }
+}
+
+/// Write each line to `out`, terminated by `eol`
+fn print_lines(lines: &[String], eol: &str, out: &mut dyn Write) -> std::io::Result<()> {
+    for line in lines {
+        write!(out, "{}{}", line, eol)?;
+    }
+    out.flush()
+}
+
+/// Emit one UUID per input line as soon as the line arrives, flushing after each
+/// `next` gets the 1-based line number and the raw line, which only --versions-from-stdin reads
+/// Ends quietly at end of input, or when the output pipe is closed by the reader
+/// Returns the number of UUIDs written
+fn emit_per_line<R: BufRead>(
+    mut reader: R,
+    out: &mut dyn Write,
+    eol: &str,
+    mut next: impl FnMut(usize, &[u8]) -> Result<String, AppError>,
+    msgs: &Messages,
+) -> Result<usize, AppError> {
+    let mut line = Vec::new();
+    let mut emitted = 0;
+
+    for number in 1.. {
+        // Lines need not be valid UTF-8 unless `next` reads them
+        line.clear();
+        if reader.read_until(b'\n', &mut line).map_err(|e| AppError::Io(msgs.read_error(&e)))? == 0 {
+            break;
+        }
+
+        match write!(out, "{}{}", next(number, &line)?, eol).and_then(|_| out.flush()) {
+            Ok(()) => emitted += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => break,
+            Err(e) => return Err(AppError::Io(msgs.write_error(&e))),
+        }
+    }
+    Ok(emitted)
+}
+
+/// The version named by a --versions-from-stdin line, in any spelling -V accepts; only v4 and v7 can be generated
+fn line_version(number: usize, line: &[u8], msgs: &Messages) -> Result<UuidVersion, AppError> {
+    let token = String::from_utf8_lossy(line);
+    let token = token.trim();
+    match token.parse::<UuidVersion>() {
+        Ok(version) if version.is_standalone() => Ok(version),
+        _ => Err(AppError::Invalid(msgs.invalid_version_line(number, token))),
+    }
+}
+
+/// Space between columns for --columns
+const COLUMN_GAP: usize = 2;
+
+/// Terminal width assumed for --columns when it cannot be queried
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// How many columns of `item_len` characters fit in `width`, always at least one
+fn column_count(width: usize, item_len: usize) -> usize {
+    ((width + COLUMN_GAP) / (item_len + COLUMN_GAP)).max(1)
+}
+
+/// Arrange `items` in aligned columns within `width`, filling down each column first like ls
+fn columns(items: &[&str], width: usize) -> Vec<String> {
+    let item_len = items.iter().map(|item| item.chars().count()).max().unwrap_or(0);
+    let rows = items.len().div_ceil(column_count(width, item_len));
+    (0..rows)
+        .map(|row| {
+            let cells: Vec<String> =
+                items.iter().skip(row).step_by(rows).map(|item| format!("{:<item_len$}", item)).collect();
+            cells.join(&" ".repeat(COLUMN_GAP)).trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Spreads output lines over numbered files for --split
+/// Each file is held until it is full so its header can state the line count
+struct SplitWriter<'a> {
+    prefix: &'a str,
+    lines_per_file: usize,
+    header: Option<&'a str>,
+    eol: &'static str,
+    bom: bool,
+    pending: Vec<u8>,
+    lines: usize,
+    index: usize,
+}
+
+impl<'a> SplitWriter<'a> {
+    fn new(prefix: &'a str, lines_per_file: usize, header: Option<&'a str>, eol: &'static str, bom: bool) -> Self {
+        SplitWriter { prefix, lines_per_file, header, eol, bom, pending: Vec::new(), lines: 0, index: 0 }
+    }
+
+    /// Write the pending lines as the next file
+    fn write_file(&mut self) -> std::io::Result<()> {
+        self.index += 1;
+        let path = std::path::PathBuf::from(format!("{}{}.txt", self.prefix, self.index));
+        let with_path = |err: std::io::Error| std::io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&path).map_err(with_path)?);
+        if self.bom {
+            file.write_all(UTF8_BOM).map_err(with_path)?;
+        }
+        if let Some(template) = self.header {
+            let header = template.replace("{index}", &self.index.to_string()).replace("{count}", &self.lines.to_string());
+            write!(file, "{}{}", header, self.eol).map_err(with_path)?;
+        }
+        file.write_all(&self.pending).map_err(with_path)?;
+        file.flush().map_err(with_path)?;
+
+        self.pending.clear();
+        self.lines = 0;
+        Ok(())
+    }
+
+    /// Write the last, possibly short, file
+    fn finish(mut self) -> std::io::Result<()> {
+        if self.lines > 0 || !self.pending.is_empty() { self.write_file() } else { Ok(()) }
+    }
+}
+
+impl Write for SplitWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for line in buf.split_inclusive(|&b| b == b'\n') {
+            self.pending.extend_from_slice(line);
+            if line.ends_with(b"\n") {
+                self.lines += 1;
+                if self.lines == self.lines_per_file {
+                    self.write_file()?;
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Start of the line --checksum appends, followed by the hex SHA-256 of everything before it
+const CHECKSUM_PREFIX: &str = "# sha256:";
+
+/// Passes output through to `inner`, hashing it along the way when --checksum is on
+struct ChecksumWriter<'a> {
+    inner: &'a mut dyn Write,
+    hasher: Option<sha2::Sha256>,
+}
+
+impl<'a> ChecksumWriter<'a> {
+    fn new(inner: &'a mut dyn Write, enabled: bool) -> Self {
+        use sha2::Digest;
+        ChecksumWriter { inner, hasher: enabled.then(sha2::Sha256::new) }
+    }
+
+    /// Write the checksum line for everything so far, if enabled
+    fn finish(&mut self, eol: &str) -> std::io::Result<()> {
+        use sha2::Digest;
+        let Some(hasher) = self.hasher.take() else {
+            return Ok(());
+        };
+        let hex: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+        write!(self.inner, "{}{}{}", CHECKSUM_PREFIX, hex, eol)
+    }
+}
+
+impl Write for ChecksumWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(hasher) = self.hasher.as_mut() {
+            sha2::Digest::update(hasher, &buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Passes output through to `inner`, holding back a trailing line ending that is dropped unless more output follows
+/// This is --no-final-newline: only the newline after the last line goes, the ones between lines stay
+struct FinalNewlineWriter<'a> {
+    inner: &'a mut dyn Write,
+    enabled: bool,
+    held: Vec<u8>,
+}
+
+impl<'a> FinalNewlineWriter<'a> {
+    fn new(inner: &'a mut dyn Write, enabled: bool) -> Self {
+        FinalNewlineWriter { inner, enabled, held: Vec::new() }
+    }
+}
+
+impl Write for FinalNewlineWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !self.enabled || buf.is_empty() {
+            return self.inner.write(buf);
+        }
+        self.inner.write_all(&self.held)?;
+        self.held.clear();
+        let keep = if buf.ends_with(b"\r\n") {
+            2
+        } else {
+            usize::from(buf.ends_with(b"\n"))
+        };
+        let (body, ending) = buf.split_at(buf.len() - keep);
+        self.inner.write_all(body)?;
+        self.held.extend_from_slice(ending);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Split `content` into the bytes covered by its trailing checksum line and the recorded hex digest
+fn split_checksum(content: &[u8]) -> Option<(&[u8], &str)> {
+    let trimmed = content.strip_suffix(b"\n")?;
+    let trimmed = trimmed.strip_suffix(b"\r").unwrap_or(trimmed);
+    let start = trimmed.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let hex = std::str::from_utf8(&trimmed[start..]).ok()?.strip_prefix(CHECKSUM_PREFIX)?;
+    Some((&content[..start], hex))
+}
+
+/// Recompute the checksum of the file at `path` and compare it with the recorded one
+fn verify_checksum_file(path: &std::path::Path, msgs: &Messages) -> Result<(), AppError> {
+    use sha2::{Digest, Sha256};
+    let content = std::fs::read(path).map_err(|e| AppError::Io(msgs.checksum_read_error(path, &e)))?;
+    let Some((body, expected)) = split_checksum(&content) else {
+        return Err(AppError::Invalid(msgs.checksum_missing(path)));
+    };
+    let actual: String = Sha256::digest(body).iter().map(|b| format!("{:02x}", b)).collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(AppError::Invalid(msgs.checksum_mismatch(path, expected, &actual)));
+    }
+    Ok(())
+}
+
+/// Spaces emissions evenly for --rate
+/// After a stall the schedule restarts from now instead of catching up, so output never bursts
+struct Pacer {
+    interval: std::time::Duration,
+    next: Option<std::time::Instant>,
+}
+
+impl Pacer {
+    fn new(per_second: f64) -> Self {
+        Self {
+            interval: std::time::Duration::from_secs_f64(1.0 / per_second),
+            next: None,
+        }
+    }
+
+    /// Sleep until the next emission is due; the first one is due immediately
+    fn wait(&mut self) {
+        let now = std::time::Instant::now();
+        let due = self.next.map_or(now, |next| next.max(now));
+        if due > now {
+            std::thread::sleep(due - now);
+        }
+        self.next = Some(due + self.interval);
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch
+fn unix_millis_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date
+/// Uses Howard Hinnant's days-to-civil algorithm for the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert a (year, month, day) civil date to days since 1970-01-01, the inverse of `civil_from_days`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = i64::from((153 * ((month + 9) % 12) + 2) / 5 + day - 1);
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Parse a point in time as milliseconds since the Unix epoch: now, YYYY-MM-DD (midnight UTC),
+/// or YYYY-MM-DDTHH:MM:SS with optional fractional seconds and a trailing Z
+fn parse_time(s: &str) -> Result<u64, String> {
+    let invalid = || Messages::global().time_reason().to_string();
+    if s.eq_ignore_ascii_case("now") {
+        return Ok(unix_millis_now());
+    }
+    let (date, time) = match s.split_once(['T', 't']) {
+        Some((date, time)) => (date, Some(time.strip_suffix(['Z', 'z']).ok_or_else(invalid)?)),
+        None => (s, None),
+    };
+
+    let number = |field: &str, digits: usize| {
+        if field.len() == digits && field.bytes().all(|b| b.is_ascii_digit()) {
+            field.parse::<u32>().map_err(|_| invalid())
+        } else {
+            Err(invalid())
+        }
+    };
+    let [year, month, day] = date.split('-').collect::<Vec<_>>()[..] else {
+        return Err(invalid());
+    };
+    let (year, month, day) = (number(year, 4)?, number(month, 2)?, number(day, 2)?);
+    let days = days_from_civil(i64::from(year), month, day);
+    if !(1..=12).contains(&month) || civil_from_days(days) != (i64::from(year), month, day) || days < 0 {
+        return Err(invalid());
+    }
+
+    let mut millis = days as u64 * 86_400_000;
+    if let Some(time) = time {
+        let (clock, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let [hour, minute, second] = clock.split(':').collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        let (hour, minute, second) = (number(hour, 2)?, number(minute, 2)?, number(second, 2)?);
+        if hour > 23 || minute > 59 || second > 59 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let fraction_millis = format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse::<u64>().map_err(|_| invalid())?;
+        millis += u64::from(hour * 3600 + minute * 60 + second) * 1000 + fraction_millis;
+    }
+    Ok(millis)
+}
+
+/// Format milliseconds since the Unix epoch as an RFC 3339 UTC timestamp, e.g. 2024-01-01T00:00:00.123Z
+/// Negative values are times before 1970, as v1/v6 UUIDs can carry
+fn format_rfc3339_millis(unix_millis: i64) -> String {
+    let secs = unix_millis.div_euclid(1000);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let time = secs.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        unix_millis.rem_euclid(1000)
+    )
+}
+
+/// Escape text for XML content or a double-quoted attribute value
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// One --ndjson line: the UUID as formatted, with the version and variant read from its bits
+/// With --with-index the line's 0-based position in the output comes first
+fn ndjson_record(id: &Uuid, text: &str, index: Option<usize>) -> String {
+    let mut record = serde_json::Map::new();
+    if let Some(index) = index {
+        record.insert("index".to_string(), index.into());
+    }
+    record.insert("uuid".to_string(), text.into());
+    record.insert("version".to_string(), id.get_version_num().into());
+    record.insert("variant".to_string(), variant_label(id).into());
+    serde_json::Value::Object(record).to_string()
+}
+
+/// One --json array element when per-UUID values are requested: the UUID as formatted plus
+/// the --with-partition, --show-variant and --with-hash values as fields instead of text suffixes
+fn json_record(id: &Uuid, text: &str, partitions: Option<u32>, variant: bool, hash: bool) -> String {
+    let mut record = serde_json::Map::new();
+    record.insert("uuid".to_string(), text.into());
+    if let Some(partitions) = partitions {
+        record.insert("partition".to_string(), kafka_partition(text, partitions).into());
+    }
+    if variant {
+        record.insert("variant".to_string(), variant_label(id).into());
+    }
+    if hash {
+        record.insert("hash".to_string(), uuid_sha256(id).into());
+    }
+    serde_json::Value::Object(record).to_string()
+}
+
+/// Whether --json elements are `json_record` objects rather than plain strings
+fn json_objects(cli: &Cli) -> bool {
+    cli.json && (cli.with_hash || cli.with_partition.is_some() || cli.show_variant)
+}
+
+/// One indented --xml element carrying the UUID's version as an attribute
+fn xml_element(element: &str, version: usize, text: &str) -> String {
+    format!("  <{0} version=\"{1}\">{2}</{0}>", element, version, xml_escape(text))
+}
+
+/// Start of a binary COPY stream: signature, flags field (no OIDs) and an empty header extension
+const PG_COPY_HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0\0\0\0\0\0\0\0\0";
+
+/// End of a binary COPY stream: a field count of -1
+const PG_COPY_TRAILER: &[u8] = &(-1i16).to_be_bytes();
+
+/// One binary COPY tuple: a single field holding the 16 bytes of a uuid, all integers big-endian
+fn pg_copy_tuple(id: Uuid) -> [u8; 22] {
+    let mut tuple = [0; 22];
+    tuple[..2].copy_from_slice(&1i16.to_be_bytes());
+    tuple[2..6].copy_from_slice(&16i32.to_be_bytes());
+    tuple[6..].copy_from_slice(id.as_bytes());
+    tuple
+}
+
+/// Header and alignment rows of the --markdown table; v7 adds the embedded timestamp
+fn markdown_header(v7: bool) -> [&'static str; 2] {
+    if v7 {
+        ["| # | UUID | Timestamp |", "| ---: | --- | --- |"]
+    } else {
+        ["| # | UUID |", "| ---: | --- |"]
+    }
+}
+
+/// One --markdown table row, numbered from 1
+fn markdown_row(index: usize, text: &str, id: Uuid, v7: bool) -> String {
+    match signed_unix_millis(&id).filter(|_| v7) {
+        Some(millis) => format!("| {} | `{}` | {} |", index, text, format_rfc3339_millis(millis)),
+        None => format!("| {} | `{}` |", index, text),
+    }
+}
+
+/// Prefix an output line with its generation time, separated by a space
+fn with_timestamp(unix_millis: u64, text: &str) -> String {
+    format!("{} {}", format_rfc3339_millis(unix_millis as i64), text)
+}
+
+/// Name of the invoking user, as `whoami` would report it
+fn current_user() -> String {
+    ["USER", "LOGNAME", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|user| !user.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Format one audit log line: timestamp, UUID, version and user, tab-separated
+fn audit_line(unix_millis: u64, id: &Uuid, version: UuidVersion, user: &str) -> String {
+    format!("{}\t{}\t{}\t{}\n", format_rfc3339_millis(unix_millis as i64), id, version, user)
+}
+
+/// Append-only record of generated UUIDs
+/// Each entry is written with a single append, so concurrent runs do not interleave within a line;
+/// `generate_batch` logs a whole batch before printing any of it, so no UUID reaches stdout unlogged
+struct AuditLog {
+    path: std::path::PathBuf,
+    file: Option<Box<dyn Write>>,
+    best_effort: bool,
+    user: String,
+    msgs: Messages,
+}
+
+impl AuditLog {
+    /// Open the log in append mode, creating it if needed
+    /// In best-effort mode an open failure only warns and disables logging
+    fn open(path: &std::path::Path, best_effort: bool, msgs: &Messages) -> Result<Self, AppError> {
+        let mut log = Self {
+            path: path.to_path_buf(),
+            file: None,
+            best_effort,
+            user: current_user(),
+            msgs: *msgs,
+        };
+        match std::fs::OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => log.file = Some(Box::new(file)),
+            Err(e) => log.fail(&e)?,
+        }
+        Ok(log)
+    }
+
+    /// Record a generated UUID
+    fn record(&mut self, id: &Uuid, version: UuidVersion) -> Result<(), AppError> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        let line = audit_line(unix_millis_now(), id, version, &self.user);
+        match file.write_all(line.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(e) => self.fail(&e),
+        }
+    }
+
+    /// Fail closed, or warn once and stop logging in best-effort mode
+    fn fail(&mut self, err: &std::io::Error) -> Result<(), AppError> {
+        let msg = self.msgs.audit_log_error(&self.path, err);
+        if !self.best_effort {
+            return Err(AppError::Io(msg));
+        }
+        print_warning(WarningCode::AuditLogSkipped, &self.msgs.audit_log_skipped(&msg));
+        self.file = None;
+        Ok(())
+    }
+}
+
+/// Replace the placeholder strings anywhere in `value`, each with a fresh UUID from `next`
+/// With --shared-key, "<placeholder>:<key>" strings take the UUID already given to their key in `shared`
+fn fill_placeholders(
+    value: &mut serde_json::Value,
+    args: &JsonFillArgs,
+    shared: &mut std::collections::HashMap<String, Uuid>,
+    next: &mut dyn FnMut() -> Result<Uuid, String>,
+) -> Result<(), String> {
+    match value {
+        serde_json::Value::String(text) if *text == args.placeholder => *text = next()?.to_string(),
+        serde_json::Value::String(text) if args.shared_key => {
+            let Some(key) = text.strip_prefix(args.placeholder.as_str()).and_then(|rest| rest.strip_prefix(':')) else {
+                return Ok(());
+            };
+            let id = match shared.get(key) {
+                Some(&id) => id,
+                None => {
+                    let id = next()?;
+                    shared.insert(key.to_string(), id);
+                    id
+                }
+            };
+            *text = id.to_string();
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                fill_placeholders(item, args, shared, next)?;
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for field in fields.values_mut() {
+                fill_placeholders(field, args, shared, next)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parse the JSON document in `input`, fill its placeholders and write it back out with its keys in their original order
+fn json_fill<R: std::io::Read>(args: &JsonFillArgs, input: R, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut document: serde_json::Value =
+        serde_json::from_reader(input).map_err(|e| match e.io_error_kind() {
+            Some(_) => AppError::Io(msgs.read_error(&e.into())),
+            None => AppError::Invalid(msgs.invalid_json_input(&e)),
+        })?;
+    let mut next = || new_uuid(UuidVersion::V4, V7Precision::Millis, &mut OsEntropy, msgs);
+    fill_placeholders(&mut document, args, &mut std::collections::HashMap::new(), &mut next).map_err(AppError::Failure)?;
+
+    let text = if args.pretty { serde_json::to_string_pretty(&document) } else { serde_json::to_string(&document) };
+    let text = text.map_err(|e| AppError::Failure(e.to_string()))?;
+    write!(out, "{}{}", text, eol).map_err(|e| output_error(e, msgs))
+}
+
+/// Quote a map file key as a CSV field when it holds a comma or a quote, doubling its quotes
+fn map_record(key: &str, id: &Uuid) -> String {
+    if key.contains([',', '"']) {
+        format!("\"{}\",{}\n", key.replace('"', "\"\""), id)
+    } else {
+        format!("{},{}\n", key, id)
+    }
+}
+
+/// Parse one key,uuid record written by `map_record`
+fn parse_map_record(line: &str) -> Option<(String, Uuid)> {
+    let (key, id) = line.rsplit_once(',')?;
+    let id = Uuid::try_parse(id).ok()?;
+    let key = match key.strip_prefix('"') {
+        Some(quoted) => {
+            let inner = quoted.strip_suffix('"')?;
+            if inner.replace("\"\"", "").contains('"') {
+                return None;
+            }
+            inner.replace("\"\"", "\"")
+        }
+        None if key.contains('"') => return None,
+        None => key.to_string(),
+    };
+    Some((key, id))
+}
+
+/// Persistent key-to-UUID mapping for `map`
+/// The file stays exclusively locked from open to drop, so concurrent runs take turns
+struct UuidMap {
+    path: std::path::PathBuf,
+    file: std::fs::File,
+    entries: std::collections::HashMap<String, Uuid>,
+    msgs: Messages,
+}
+
+impl UuidMap {
+    /// Open (or create) the map file, wait for the lock, then load every record
+    /// A malformed record or a repeated key is reported as corruption rather than guessed at
+    fn open(path: &std::path::Path, msgs: &Messages) -> Result<Self, AppError> {
+        let io_error = |e: std::io::Error| AppError::Io(msgs.map_file_error(path, &e));
+        let mut file = std::fs::OpenOptions::new().read(true).append(true).create(true).open(path).map_err(io_error)?;
+        file.lock().map_err(io_error)?;
+
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut file, &mut content).map_err(io_error)?;
+        let mut entries = std::collections::HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let corrupt = || AppError::Invalid(msgs.map_file_corrupt(path, i + 1, line));
+            let (key, id) = parse_map_record(line).ok_or_else(corrupt)?;
+            if entries.insert(key, id).is_some() {
+                return Err(corrupt());
+            }
+        }
+        // A record cut short would otherwise run into the first one appended
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n").map_err(io_error)?;
+        }
+        Ok(UuidMap { path: path.to_path_buf(), file, entries, msgs: *msgs })
+    }
+
+    /// The UUID recorded for `key`, or a fresh v4 that is recorded first
+    fn get_or_insert(&mut self, key: &str) -> Result<Uuid, AppError> {
+        if let Some(&id) = self.entries.get(key) {
+            return Ok(id);
+        }
+        let id = new_uuid(UuidVersion::V4, V7Precision::Millis, &mut OsEntropy, &self.msgs).map_err(AppError::Failure)?;
+        self.file
+            .write_all(map_record(key, &id).as_bytes())
+            .map_err(|e| AppError::Io(self.msgs.map_file_error(&self.path, &e)))?;
+        self.entries.insert(key.to_string(), id);
+        Ok(id)
+    }
+}
+
+/// A fresh UUID to replace `old`: a v4 for a v4, a v7 for a v7, stamped now or with the old timestamp
+fn rotated_uuid(old: &Uuid, preserve_timestamp: bool, msgs: &Messages) -> Result<Uuid, String> {
+    match old.get_timestamp() {
+        Some(timestamp) if preserve_timestamp && old.get_version_num() == 7 => {
+            let mut random = [0u8; 10];
+            fill_random_with_retry(&mut OsEntropy, &mut random, msgs)?;
+            let (secs, nanos) = timestamp.to_unix();
+            Ok(uuid::Builder::from_unix_timestamp_millis(secs * 1000 + u64::from(nanos / 1_000_000), &random).into_uuid())
+        }
+        _ if old.get_version_num() == 7 => new_uuid(UuidVersion::V7, V7Precision::Millis, &mut OsEntropy, msgs),
+        _ => new_uuid(UuidVersion::V4, V7Precision::Millis, &mut OsEntropy, msgs),
+    }
+}
+
+/// Write `old<TAB>new` for each UUID read from `reader`, the old one as given
+/// A UUID seen before in the run, in any spelling, gets the same new UUID again
+fn rotate_lines<R: BufRead>(args: &RotateArgs, reader: R, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut rotated = std::collections::HashMap::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let old = Uuid::parse_str(trimmed).map_err(|_| AppError::Invalid(msgs.invalid_line(i + 1, trimmed)))?;
+        if !matches!(old.get_version_num(), 4 | 7) {
+            return Err(AppError::Invalid(msgs.rotate_version_not_supported(i + 1, old.get_version_num())));
+        }
+        let new = match rotated.get(&old) {
+            Some(&new) => new,
+            None => {
+                let new = rotated_uuid(&old, args.preserve_timestamp, msgs).map_err(AppError::Failure)?;
+                rotated.insert(old, new);
+                new
+            }
+        };
+        write!(out, "{}\t{}{}", trimmed, new, eol).map_err(|e| output_error(e, msgs))?;
+    }
+    Ok(())
+}
+
+/// Write the mapped UUID of each key read from `reader`, one line per key in input order
+fn map_keys<R: BufRead>(args: &MapArgs, reader: R, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut map = UuidMap::open(&args.map_file, msgs)?;
+    for line in reader.lines() {
+        let key = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        let id = map.get_or_insert(&key)?;
+        write!(out, "{}{}", id, eol).map_err(|e| output_error(e, msgs))?;
+    }
+    Ok(())
+}
+
+/// How many v4 UUIDs `doctor` generates for its duplicate and distribution checks
+const DOCTOR_SAMPLE: usize = 10_000;
+
+/// Chi-square critical value for 15 degrees of freedom at p = 1e-6, so a sound RNG practically never fails
+const DOCTOR_CHI_SQUARE_LIMIT: f64 = 56.49;
+
+/// The clock range `doctor` accepts for v7: 2020-01-01 up to 2100-01-01, in Unix milliseconds
+const DOCTOR_CLOCK_RANGE: std::ops::Range<u64> = 1_577_836_800_000..4_102_444_800_000;
+
+/// The result of one `doctor` check; `name` is a fixed identifier, `detail` is localized
+#[derive(Debug)]
+struct DoctorCheck {
+    name: &'static str,
+    ok: bool,
+    detail: String,
+}
+
+/// Chi-square statistic of the hex digits of v4 UUIDs against a uniform distribution
+/// The version digit and the variant digit are not random, so they are left out
+fn nibble_chi_square(ids: &[Uuid]) -> f64 {
+    let mut counts = [0u64; 16];
+    for id in ids {
+        let value = id.as_u128();
+        for nibble in (0..32).filter(|&i| i != 12 && i != 16) {
+            counts[(value >> (124 - 4 * nibble)) as usize & 0xf] += 1;
+        }
+    }
+    chi_square(&counts)
+}
+
+/// Whether the config file at `path` can be used: absent is fine, unreadable or unparsable is not
+fn doctor_config(path: Option<&std::path::Path>, msgs: &Messages) -> (bool, String) {
+    let Some(path) = path else {
+        return (true, msgs.doctor_no_config_location().to_string());
+    };
+    match std::fs::read_to_string(path) {
+        Ok(text) => match Config::parse(&text) {
+            Ok(_) => (true, path.display().to_string()),
+            Err(e) => (false, msgs.config_error(path, &e)),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (true, msgs.doctor_config_absent(path)),
+        Err(e) => (false, msgs.config_error(path, &e.to_string())),
+    }
+}
+
+/// Run every `doctor` check against `source`, the clock reading `now_millis` and the config file at `config`
+/// The RNG is probed once without retrying; the sample checks only run when that succeeds
+fn doctor_checks(source: &mut dyn EntropySource, now_millis: u64, config: Option<&std::path::Path>, msgs: &Messages) -> Vec<DoctorCheck> {
+    let check = |name, ok, detail: String| DoctorCheck { name, ok, detail };
+    let mut checks = Vec::new();
+
+    match source.fill_random(&mut [0u8; 16]) {
+        Ok(()) => {
+            checks.push(check("rng", true, msgs.doctor_rng_ok().to_string()));
+            let sample: Result<Vec<Uuid>, String> =
+                (0..DOCTOR_SAMPLE).map(|_| new_uuid(UuidVersion::V4, V7Precision::Millis, source, msgs)).collect();
+            match sample {
+                Ok(ids) => {
+                    let unique: std::collections::HashSet<&Uuid> = ids.iter().collect();
+                    let duplicates = ids.len() - unique.len();
+                    checks.push(check("uniqueness", duplicates == 0, msgs.doctor_duplicates(ids.len(), duplicates)));
+                    let chi_square = nibble_chi_square(&ids);
+                    let detail = msgs.doctor_distribution(chi_square, DOCTOR_CHI_SQUARE_LIMIT);
+                    checks.push(check("distribution", chi_square <= DOCTOR_CHI_SQUARE_LIMIT, detail));
+                }
+                Err(e) => {
+                    checks.push(check("uniqueness", false, e.clone()));
+                    checks.push(check("distribution", false, e));
+                }
+            }
+        }
+        Err(e) => {
+            checks.push(check("rng", false, e));
+            checks.push(check("uniqueness", false, msgs.doctor_needs_rng().to_string()));
+            checks.push(check("distribution", false, msgs.doctor_needs_rng().to_string()));
+        }
+    }
+
+    let clock_ok = DOCTOR_CLOCK_RANGE.contains(&now_millis);
+    checks.push(check("clock", clock_ok, msgs.doctor_clock(&format_rfc3339_millis(now_millis as i64), clock_ok)));
+
+    let locale: Vec<String> = ["ZUUID_LANG", "LANG", "LC_ALL", "LC_MESSAGES"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok().map(|value| format!("{}={}", var, value)))
+        .collect();
+    let lang = match msgs.lang {
+        Language::English => "en",
+        Language::Chinese => "zh",
+    };
+    let detail = if locale.is_empty() { lang.to_string() } else { format!("{} ({})", lang, locale.join(", ")) };
+    checks.push(check("locale", true, detail));
+
+    let (config_ok, detail) = doctor_config(config, msgs);
+    checks.push(check("config", config_ok, detail));
+    checks
+}
+
+/// Print the `doctor` report, one "status  name  detail" line per check, failing if any check did
+fn doctor(
+    source: &mut dyn EntropySource,
+    now_millis: u64,
+    config: Option<&std::path::Path>,
+    eol: &str,
+    out: &mut dyn Write,
+    msgs: &Messages,
+) -> Result<(), AppError> {
+    let checks = doctor_checks(source, now_millis, config, msgs);
+    let lines: Vec<String> = checks
+        .iter()
+        .map(|check| format!("{:<4}  {:<12}  {}", msgs.doctor_status(check.ok), check.name, check.detail))
+        .collect();
+    print_lines(&lines, eol, out).map_err(|e| output_error(e, msgs))?;
+    match checks.iter().filter(|check| !check.ok).count() {
+        0 => Ok(()),
+        failed => Err(AppError::Failure(msgs.doctor_failed(failed, checks.len()))),
+    }
+}
+
+/// Back-to-back 16-byte records of a --registry-binary file, memory-mapped read-only where possible
+/// so a registry of any size is checked without loading it into a hash set
+struct MappedRecords {
+    #[cfg(unix)]
+    ptr: *mut libc::c_void,
+    #[cfg(unix)]
+    len: usize,
+    #[cfg(not(unix))]
+    bytes: Vec<u8>,
+}
+
+impl MappedRecords {
+    #[cfg(unix)]
+    fn map(file: &std::fs::File, len: usize) -> std::io::Result<Self> {
+        use std::os::fd::AsRawFd;
+        if len == 0 {
+            return Ok(MappedRecords { ptr: std::ptr::null_mut(), len });
+        }
+        // SAFETY: the file is open for reading and held under an exclusive lock, so cooperating runs
+        // cannot truncate it while mapped; the mapping is private to this struct and unmapped on drop
+        let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, file.as_raw_fd(), 0) };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(MappedRecords { ptr, len })
+    }
+
+    #[cfg(not(unix))]
+    fn map(mut file: &std::fs::File, len: usize) -> std::io::Result<Self> {
+        let mut bytes = Vec::with_capacity(len);
+        std::io::Read::read_to_end(&mut file, &mut bytes)?;
+        Ok(MappedRecords { bytes })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        #[cfg(unix)]
+        if self.len == 0 {
+            &[]
+        } else {
+            // SAFETY: `ptr` maps `len` readable bytes for as long as `self` lives
+            unsafe { std::slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) }
+        }
+        #[cfg(not(unix))]
+        &self.bytes
+    }
+
+    fn ids(&self) -> impl Iterator<Item = u128> + '_ {
+        self.bytes().chunks_exact(16).map(|record| u128::from_be_bytes(record.try_into().unwrap()))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MappedRecords {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: `ptr` and `len` are exactly what mmap returned
+            unsafe { libc::munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+/// Every UUID issued so far, as the text registry's hash set or the binary registry's mapped records
+enum RegistryRecords {
+    Text(std::collections::HashSet<u128>),
+    Binary(MappedRecords),
+}
+
+/// A --registry file of every UUID ever issued, held under an exclusive lock for the whole run
+/// Text registries hold one UUID per line; binary ones hold 16-byte big-endian records
+struct Registry {
+    path: std::path::PathBuf,
+    file: std::fs::File,
+    records: RegistryRecords,
+    msgs: Messages,
+}
+
+impl Registry {
+    /// Open (or create) the registry, wait for the lock, then load or map its records
+    fn open(path: &std::path::Path, binary: bool, msgs: &Messages) -> Result<Self, AppError> {
+        let io_error = |e: std::io::Error| AppError::Io(msgs.registry_error(path, &e));
+        let mut file = std::fs::OpenOptions::new().read(true).append(true).create(true).open(path).map_err(io_error)?;
+        file.lock().map_err(io_error)?;
+
+        let records = if binary {
+            let len = file.metadata().map_err(io_error)?.len();
+            if len % 16 != 0 {
+                return Err(AppError::Invalid(msgs.registry_truncated(path, len)));
+            }
+            RegistryRecords::Binary(MappedRecords::map(&file, len as usize).map_err(io_error)?)
+        } else {
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut file, &mut content).map_err(io_error)?;
+            let mut seen = std::collections::HashSet::new();
+            for (i, line) in content.lines().enumerate() {
+                let id = Uuid::parse_str(line.trim()).map_err(|_| AppError::Invalid(msgs.registry_corrupt(path, i + 1, line)))?;
+                seen.insert(id.as_u128());
+            }
+            // A line cut short would otherwise run into the first one appended
+            if !content.is_empty() && !content.ends_with('\n') {
+                file.write_all(b"\n").map_err(io_error)?;
+            }
+            RegistryRecords::Text(seen)
+        };
+        Ok(Registry { path: path.to_path_buf(), file, records, msgs: *msgs })
+    }
+
+    /// The candidates already in the registry; a binary registry is scanned once for the whole set
+    fn issued(&self, candidates: &std::collections::HashSet<u128>) -> Vec<u128> {
+        match &self.records {
+            RegistryRecords::Text(seen) => candidates.intersection(seen).copied().collect(),
+            RegistryRecords::Binary(mapped) => mapped.ids().filter(|id| candidates.contains(id)).collect(),
+        }
+    }
+
+    /// Draw `count` UUIDs from `next` that repeat neither each other nor the registry, then append them
+    /// They are recorded before being returned, so every UUID that gets printed is in the registry;
+    /// the lock is released once they are written
+    fn issue(mut self, count: usize, mut next: impl FnMut() -> Result<Uuid, String>) -> Result<Vec<Uuid>, AppError> {
+        let mut ids = Vec::with_capacity(count);
+        let mut candidates = std::collections::HashSet::with_capacity(count);
+        while ids.len() < count {
+            while ids.len() < count {
+                let id = next().map_err(AppError::Failure)?;
+                if candidates.insert(id.as_u128()) {
+                    ids.push(id);
+                }
+            }
+            // Redraw only the repeats, which then get checked against the registry in turn
+            let issued = self.issued(&candidates);
+            ids.retain(|id| !issued.contains(&id.as_u128()));
+            candidates.retain(|id| !issued.contains(id));
+        }
+
+        let mut records = Vec::new();
+        for id in &ids {
+            match self.records {
+                RegistryRecords::Text(_) => records.extend(format!("{}\n", id).into_bytes()),
+                RegistryRecords::Binary(_) => records.extend(id.as_bytes()),
+            }
+        }
+        self.file.write_all(&records).map_err(|e| AppError::Io(self.msgs.registry_error(&self.path, &e)))?;
+        Ok(ids)
+    }
+}
+
+/// Fewest UUIDs --distribution-check judges: five expected per first-byte value
+const FIRST_BYTE_MIN_SAMPLE: u64 = 5 * 256;
+
+/// Chi-square critical value for 255 degrees of freedom at p = 1e-6, so a sound RNG practically never trips it
+const FIRST_BYTE_CHI_SQUARE_LIMIT: f64 = 377.08;
+
+/// How often each byte value occurred
+#[derive(Debug)]
+struct ByteHistogram([u64; 256]);
+
+impl Default for ByteHistogram {
+    fn default() -> Self {
+        ByteHistogram([0; 256])
+    }
+}
+
+impl ByteHistogram {
+    fn add(&mut self, byte: u8) {
+        self.0[byte as usize] += 1;
+    }
+
+    fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    /// Chi-square statistic against a uniform distribution, or None below `FIRST_BYTE_MIN_SAMPLE`
+    fn chi_square(&self) -> Option<f64> {
+        (self.total() >= FIRST_BYTE_MIN_SAMPLE).then(|| chi_square(&self.0))
+    }
+}
+
+/// Chi-square statistic of `counts` against equal expected counts in every bin
+fn chi_square(counts: &[u64]) -> f64 {
+    let expected = counts.iter().sum::<u64>() as f64 / counts.len() as f64;
+    counts.iter().map(|&count| (count as f64 - expected).powi(2) / expected).sum()
+}
+
+/// Count and extremes of a generated batch, for --summary
+#[derive(Default, Debug)]
+struct BatchSummary {
+    count: u64,
+    min: Option<Uuid>,
+    max: Option<Uuid>,
+    /// UUIDs per version number, reported when a --mix batch has more than one
+    versions: std::collections::BTreeMap<usize, u64>,
+    /// Values of the first byte, for --distribution-check
+    first_bytes: ByteHistogram,
+}
+
+impl BatchSummary {
+    fn add(&mut self, id: Uuid) {
+        self.count += 1;
+        self.first_bytes.add(id.as_bytes()[0]);
+        *self.versions.entry(id.get_version_num()).or_default() += 1;
+        self.min = Some(self.min.map_or(id, |min| min.min(id)));
+        self.max = Some(self.max.map_or(id, |max| max.max(id)));
+    }
+
+    /// Smallest and largest UUID, in canonical form
+    fn range(&self) -> Option<(String, String)> {
+        Some((self.min?.to_string(), self.max?.to_string()))
+    }
+
+    /// Embedded timestamps of the smallest and largest UUID, when they carry one (v7)
+    fn timestamps(&self) -> Option<(String, String)> {
+        let timestamp = |id: Uuid| signed_unix_millis(&id).map(format_rfc3339_millis);
+        Some((timestamp(self.min?)?, timestamp(self.max?)?))
+    }
+}
+
+/// --summary-json output: the same facts as the text line
+#[derive(Serialize, Debug)]
+struct SummaryReport {
+    count: u64,
+    version: String,
+    seconds: f64,
+    per_second: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versions: Option<std::collections::BTreeMap<String, u64>>,
+}
+
+impl SummaryReport {
+    fn new(summary: &BatchSummary, version: &str, elapsed: std::time::Duration) -> Self {
+        let seconds = elapsed.as_secs_f64();
+        let (min, max) = summary.range().unzip();
+        let (min_timestamp, max_timestamp) = summary.timestamps().unzip();
+        SummaryReport {
+            count: summary.count,
+            version: version.to_string(),
+            seconds,
+            per_second: summary.count as f64 / seconds.max(f64::EPSILON),
+            min,
+            max,
+            min_timestamp,
+            max_timestamp,
+            versions: (summary.versions.len() > 1)
+                .then(|| summary.versions.iter().map(|(version, count)| (format!("v{}", version), *count)).collect()),
+        }
+    }
+}
+
+/// A rate such as 950/s, 238k/s or 1.5M/s
+fn human_rate(per_second: f64) -> String {
+    if per_second >= 1e6 {
+        format!("{:.1}M/s", per_second / 1e6)
+    } else if per_second >= 1e3 {
+        format!("{:.0}k/s", per_second / 1e3)
+    } else {
+        format!("{:.0}/s", per_second)
+    }
+}
+
+/// Errors that end a run, each with a stable exit code
+/// Messages are already localized
+#[derive(Debug)]
+enum AppError {
+    /// Command-line errors, --help and --version, printed by clap itself
+    /// (exit code 4 for rejected values, 2 for other usage errors, 0 for help and version)
+    Clap(clap::Error),
+    /// Missing arguments or unsupported option combinations (exit code 2)
+    Usage(String),
+    /// Reading input or writing output, the audit log or the config file failed (exit code 74, EX_IOERR)
+    Io(String),
+    /// An option value, the config file or the input was rejected (exit code 4)
+    Invalid(String),
+    /// Generation failure, such as an unavailable random source (exit code 1)
+    Failure(String),
+    /// The reader closed the output pipe; `run` turns this into a quiet success
+    Closed,
+}
+
+impl AppError {
+    /// Process exit code: 1 for failures, 2 for usage, 4 for validation errors and 74 for I/O
+    /// 74 is EX_IOERR from sysexits.h, so scripts can tell a full disk from bad input
+    fn exit_code(&self) -> u8 {
+        match self {
+            AppError::Clap(e) if !e.use_stderr() => 0,
+            AppError::Clap(e) if matches!(e.kind(), ErrorKind::InvalidValue | ErrorKind::ValueValidation) => 4,
+            AppError::Failure(_) => 1,
+            AppError::Clap(_) | AppError::Usage(_) => 2,
+            AppError::Invalid(_) => 4,
+            AppError::Io(_) => 74,
+            AppError::Closed => 0,
+        }
+    }
+
+    /// Stable diagnostic code used in porcelain mode
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::Clap(_) if self.exit_code() == 4 => "invalid",
+            AppError::Clap(_) | AppError::Usage(_) => "usage",
+            AppError::Io(_) => "io",
+            AppError::Invalid(_) => "invalid",
+            AppError::Failure(_) => "error",
+            AppError::Closed => "closed",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            AppError::Usage(msg) | AppError::Io(msg) | AppError::Invalid(msg) | AppError::Failure(msg) => msg,
+            AppError::Clap(_) | AppError::Closed => "",
+        }
+    }
+}
+
+/// Plain localized messages from lower layers are runtime failures
+impl From<String> for AppError {
+    fn from(msg: String) -> Self {
+        AppError::Failure(msg)
+    }
+}
+
+/// Classify a failed write to the output: a closed pipe ends the run, anything else is an I/O error
+fn output_error(err: std::io::Error, msgs: &Messages) -> AppError {
+    if err.kind() == std::io::ErrorKind::BrokenPipe {
+        AppError::Closed
+    } else {
+        AppError::Io(msgs.write_error(&err))
+    }
+}
+
+/// Print error message in red, or with its stable code in porcelain mode
+fn report_error(err: &AppError) {
+    if let AppError::Clap(e) = err {
+        let _ = e.print();
+        return;
+    }
+    eprintln!("{}", format_diagnostic(err.code(), "31", err.message(), stderr_style()));
+}
+
+/// Reject `id` (read as `input`) unless it has the --require-version version
+fn check_version(id: &Uuid, input: &str, required: Option<u8>, msgs: &Messages) -> Result<(), AppError> {
+    match required {
+        Some(expected) if id.get_version_num() != usize::from(expected) => Err(AppError::Invalid(
+            msgs.version_mismatch(input, id.get_version_num(), expected),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Describe each UUID given to `decode`, or just normalize it with --canonical-only
+/// Several UUIDs get their descriptions (or --dump output) separated by a blank line
+fn decode_lines(args: &DecodeArgs, color: bool, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    let mut lines = Vec::new();
+    for (i, input) in args.uuids.iter().enumerate() {
+        let id = if args.expect_check_digit {
+            strip_check_digit(input.trim(), msgs).map_err(AppError::Invalid)?
+        } else {
+            Uuid::parse_str(input.trim()).map_err(|_| InputError::new("UUID", i + 1, input, msgs.not_a_uuid()))?
+        };
+        check_version(&id, input, args.require_version, msgs)?;
+        if args.canonical_only {
+            lines.push(format_uuid(&id, &FormatOptions::default()));
+            continue;
+        }
+        if i > 0 {
+            lines.push(String::new());
+        }
+        if args.dump {
+            lines.extend(hex_dump(&id, color, msgs));
+        } else {
+            lines.extend(msgs.decoded(&id));
+        }
+    }
+    Ok(lines)
+}
+
+/// Throughput of one version and format combination
+#[derive(Serialize, Debug)]
+struct BenchResult {
+    version: String,
+    format: String,
+    /// Discarded before the timer started; not part of `uuids`
+    warmup: u64,
+    uuids: u64,
+    seconds: f64,
+    per_second: f64,
+}
+
+/// Generate and format UUIDs until `duration` passes, returning how many were made and the time taken
+/// The clock is read every `BENCH_BATCH` UUIDs so timing stays out of the measured work
+fn bench_loop(version: UuidVersion, format: TextFormat, duration: std::time::Duration, msgs: &Messages) -> Result<(u64, f64), String> {
+    const BENCH_BATCH: u64 = 256;
+    let start = std::time::Instant::now();
+    let mut made = 0;
+    while start.elapsed() < duration {
+        for _ in 0..BENCH_BATCH {
+            let id = new_uuid(version, V7Precision::Millis, &mut OsEntropy, msgs)?;
+            std::hint::black_box(format.render(std::hint::black_box(&id)));
+        }
+        made += BENCH_BATCH;
+    }
+    Ok((made, start.elapsed().as_secs_f64()))
+}
+
+/// Generate and format exactly `count` UUIDs, untimed, to settle caches and the allocator
+fn bench_warmup(version: UuidVersion, format: TextFormat, count: u64, msgs: &Messages) -> Result<u64, String> {
+    for _ in 0..count {
+        let id = new_uuid(version, V7Precision::Millis, &mut OsEntropy, msgs)?;
+        std::hint::black_box(format.render(std::hint::black_box(&id)));
+    }
+    Ok(count)
+}
+
+/// Measure each requested combination after a warmup of --warmup UUIDs, or a tenth of the duration
+fn run_bench(args: &BenchArgs, msgs: &Messages) -> Result<Vec<BenchResult>, AppError> {
+    if let Some(&version) = args.versions.iter().find(|version| !version.is_standalone()) {
+        return Err(AppError::Usage(msgs.bench_version_not_supported(version)));
+    }
+
+    let mut results = Vec::new();
+    for &version in &args.versions {
+        for &format in &args.formats {
+            let warmup = match args.warmup {
+                Some(count) => bench_warmup(version, format, count, msgs)?,
+                None => bench_loop(version, format, args.duration / 10, msgs)?.0,
+            };
+            let (uuids, seconds) = bench_loop(version, format, args.duration, msgs)?;
+            results.push(BenchResult {
+                version: version.to_string(),
+                format: format.to_string(),
+                warmup,
+                uuids,
+                seconds,
+                per_second: uuids as f64 / seconds,
+            });
+        }
+    }
+    Ok(results)
+}
+
+/// Bench results as an aligned table, or a JSON array with --json or --json-pretty
+fn bench_report(args: &BenchArgs, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    let results = run_bench(args, msgs)?;
+    if let Some(layout) = JsonLayout::from_flags(args.json, args.json_pretty) {
+        return json_array_lines(&results, layout);
+    }
+
+    let [version, format, warmup, measured, rate] = msgs.bench_header();
+    let mut lines = vec![format!("{:<8} {:<8} {:>10} {:>12} {:>14}", version, format, warmup, measured, rate)];
+    for result in &results {
+        lines.push(format!(
+            "{:<8} {:<8} {:>10} {:>12} {:>14.0}",
+            result.version, result.format, result.warmup, result.uuids, result.per_second
+        ));
+    }
+    Ok(lines)
+}
+
+/// Version keys reported by `decode --stats`, in display order
+const STATS_VERSIONS: [&str; 11] = ["1", "2", "3", "4", "5", "6", "7", "8", "nil", "max", "other"];
+
+/// Variant keys reported by `decode --stats`, in display order
+const STATS_VARIANTS: [(&str, uuid::Variant); 4] = [
+    ("rfc9562", uuid::Variant::RFC4122),
+    ("ncs", uuid::Variant::NCS),
+    ("microsoft", uuid::Variant::Microsoft),
+    ("future", uuid::Variant::Future),
+];
+
+/// One line of the `decode --stats` text report
+#[derive(Clone, Copy)]
+enum StatsLabel {
+    Total,
+    Invalid,
+    Version(&'static str),
+    Variant(uuid::Variant),
+}
+
+/// Version and variant counts over a UUID dataset; every key is present, even at zero
+#[derive(Serialize, Debug, PartialEq)]
+struct UuidStats {
+    total: u64,
+    invalid: u64,
+    versions: std::collections::BTreeMap<&'static str, u64>,
+    variants: std::collections::BTreeMap<&'static str, u64>,
+}
+
+/// The `STATS_VERSIONS` key for a UUID
+fn stats_version_key(id: &Uuid) -> &'static str {
+    if id.is_nil() {
+        "nil"
+    } else if id.is_max() {
+        "max"
+    } else {
+        match id.get_version_num() {
+            n @ 1..=8 => STATS_VERSIONS[n - 1],
+            _ => "other",
+        }
+    }
+}
+
+/// Colors of the marked fields in `decode --dump`
+const DUMP_VERSION_COLOR: &str = "35";
+const DUMP_VARIANT_COLOR: &str = "33";
+const DUMP_TIMESTAMP_COLOR: &str = "36";
+
+/// Whether the first 6 bytes are a plain Unix millisecond timestamp, as in v7
+fn version_has_dump_timestamp(id: &Uuid) -> bool {
+    id.get_version_num() == 7
+}
+
+/// The 16 bytes of `id` as two offset-annotated rows of 8, followed by a legend
+/// The version nibble, variant bits and v7 timestamp are colored, or without color
+/// bracketed as [x], {x} and <..>; the variant marker covers the whole hex digit holding its bits
+fn hex_dump(id: &Uuid, color: bool, msgs: &Messages) -> Vec<String> {
+    let bytes = id.as_bytes();
+    let variant_len = match id.get_variant() {
+        uuid::Variant::NCS => 1,
+        uuid::Variant::RFC4122 => 2,
+        _ => 3,
+    };
+    let variant_bits = format!("{:08b}", bytes[8])[..variant_len].to_string();
+    let paint = |code: &str, open: &str, close: &str, text: &str| {
+        if color { format!("\x1b[{}m{}\x1b[0m", code, text) } else { format!("{}{}{}", open, text, close) }
+    };
+
+    let cells: Vec<String> = bytes
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| {
+            let hex = format!("{:02x}", byte);
+            match i {
+                6 => paint(DUMP_VERSION_COLOR, "[", "]", &hex[..1]) + &hex[1..],
+                8 => paint(DUMP_VARIANT_COLOR, "{", "}", &hex[..1]) + &hex[1..],
+                _ => hex,
+            }
+        })
+        .collect();
+
+    let mut lines: Vec<String> = cells
+        .chunks(8)
+        .enumerate()
+        .map(|(row, cells)| {
+            let text = match row {
+                0 if version_has_dump_timestamp(id) => {
+                    format!("{} {}", paint(DUMP_TIMESTAMP_COLOR, "<", ">", &cells[..6].join(" ")), cells[6..].join(" "))
+                }
+                _ => cells.join(" "),
+            };
+            format!("{:02x}  {}", row * 8, text)
+        })
+        .collect();
+    lines.extend(msgs.dump_legend(id, &variant_bits, color));
+    lines
+}
+
+/// The UUIDs in one line of text: hyphenated, any case, possibly braced or urn:uuid: prefixed
+/// A match must not be glued to further letters or digits, so longer hex runs are not split up
+fn extract_uuids(line: &str) -> Vec<Uuid> {
+    const LEN: usize = 36;
+    let bytes = line.as_bytes();
+    let is_word = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_alphanumeric);
+    let mut ids = Vec::new();
+    let mut i = 0;
+    while i + LEN <= bytes.len() {
+        let candidate = &bytes[i..i + LEN];
+        let shaped = candidate.iter().enumerate().all(|(j, b)| match j {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        });
+        if shaped
+            && (i == 0 || !is_word(i - 1))
+            && !is_word(i + LEN)
+            && let Ok(id) = Uuid::try_parse_ascii(candidate)
+        {
+            ids.push(id);
+            i += LEN;
+        } else {
+            i += 1;
+        }
+    }
+    ids
+}
+
+/// One UUID found by `scan`
+#[derive(Serialize, Debug, PartialEq)]
+struct ScanMatch {
+    line: usize,
+    uuid: String,
+}
+
+/// The UUIDs found in one file; files without any are left out of the report
+#[derive(Serialize, Debug)]
+struct ScanFile {
+    path: String,
+    count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    matches: Vec<ScanMatch>,
+}
+
+/// Result of `scan` over all paths; `unique` counts distinct UUIDs across every file
+#[derive(Serialize, Debug, Default)]
+struct ScanReport {
+    files: Vec<ScanFile>,
+    files_scanned: usize,
+    skipped_binary: usize,
+    total: usize,
+    unique: usize,
+}
+
+/// Walk the scan paths and collect the UUIDs of every text file
+/// Directories are entered once each by canonical path, so symlink loops end; unreadable entries are warned about and skipped
+fn scan_paths(args: &ScanArgs, msgs: &Messages) -> Result<ScanReport, AppError> {
+    let mut report = ScanReport::default();
+    let mut seen = std::collections::HashSet::new();
+    let mut visited = std::collections::HashSet::new();
+
+    // (path, depth below its PATH argument); entries of a directory are pushed in reverse so they pop in name order
+    let mut pending: Vec<(std::path::PathBuf, usize)> = args.paths.iter().rev().map(|path| (path.clone(), 0)).collect();
+    while let Some((path, depth)) = pending.pop() {
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) if depth == 0 => return Err(AppError::Io(msgs.scan_error(&path, &e))),
+            Err(e) => {
+                print_warning(WarningCode::UnreadablePath, &msgs.scan_error(&path, &e));
+                continue;
+            }
+        };
+        if metadata.is_dir() {
+            if args.max_depth.is_some_and(|max| depth > max) {
+                continue;
+            }
+            let entered = std::fs::canonicalize(&path).map(|real| visited.insert(real));
+            let entries = entered.and_then(|entered| {
+                if !entered {
+                    return Ok(Vec::new());
+                }
+                let mut entries = std::fs::read_dir(&path)?.map(|entry| entry.map(|e| e.path())).collect::<Result<Vec<_>, _>>()?;
+                entries.sort();
+                Ok(entries)
+            });
+            match entries {
+                Ok(entries) => pending.extend(entries.into_iter().rev().map(|entry| (entry, depth + 1))),
+                Err(e) => print_warning(WarningCode::UnreadablePath, &msgs.scan_error(&path, &e)),
+            }
+            continue;
+        }
+
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                print_warning(WarningCode::UnreadablePath, &msgs.scan_error(&path, &e));
+                continue;
+            }
+        };
+        if !args.binary && bytes.contains(&0) {
+            report.skipped_binary += 1;
+            continue;
+        }
+        report.files_scanned += 1;
+        let text = String::from_utf8_lossy(&bytes);
+        let mut file = ScanFile { path: path.display().to_string(), count: 0, matches: Vec::new() };
+        for (number, line) in text.lines().enumerate() {
+            for id in extract_uuids(line) {
+                file.count += 1;
+                seen.insert(id);
+                if args.list {
+                    file.matches.push(ScanMatch { line: number + 1, uuid: format_uuid(&id, &FormatOptions::default()) });
+                }
+            }
+        }
+        if file.count > 0 {
+            report.total += file.count;
+            report.files.push(file);
+        }
+    }
+    report.unique = seen.len();
+    Ok(report)
+}
+
+/// Render a scan report: "path: count" lines (or file:line:uuid with --list) and a summary, or one JSON object
+fn scan_lines(report: &ScanReport, args: &ScanArgs, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    if args.format == ScanFormat::Json {
+        return serde_json::to_string(report).map(|json| vec![json]).map_err(|e| AppError::Failure(e.to_string()));
+    }
+    let mut lines = Vec::new();
+    for file in &report.files {
+        if args.list {
+            lines.extend(file.matches.iter().map(|m| format!("{}:{}:{}", file.path, m.line, m.uuid)));
+        } else {
+            lines.push(format!("{}: {}", file.path, file.count));
+        }
+    }
+    lines.push(msgs.scan_summary(report));
+    Ok(lines)
+}
+
+/// One well-known UUID as listed by `namespaces`
+#[derive(Serialize, Debug)]
+struct NamespaceRow {
+    name: &'static str,
+    uuid: String,
+    description: &'static str,
+}
+
+/// The well-known UUIDs as aligned "name  uuid  description" lines, or a JSON array
+fn namespace_lines(json: Option<JsonLayout>, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    let rows: Vec<NamespaceRow> = WELL_KNOWN_UUIDS
+        .iter()
+        .map(|&(name, id)| NamespaceRow {
+            name,
+            uuid: format_uuid(&id, &FormatOptions::default()),
+            description: msgs.namespace_description(name),
+        })
+        .collect();
+    if let Some(layout) = json {
+        return json_array_lines(&rows, layout);
+    }
+    let width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0);
+    Ok(rows.iter().map(|row| format!("{:<width$}  {}  {}", row.name, row.uuid, row.description)).collect())
+}
+
+/// Clock sequence and node of a v1 or v6 UUID, the node as colon-separated hex bytes
+/// Both versions keep them in the same place: the low 14 bits of octets 8-9 and octets 10-15
+fn time_mac_fields(id: &Uuid) -> Option<(u16, String)> {
+    if !matches!(id.get_version_num(), 1 | 6) {
+        return None;
+    }
+    let bytes = id.as_bytes();
+    let clock_seq = u16::from_be_bytes([bytes[8], bytes[9]]) & 0x3fff;
+    let node: Vec<String> = bytes[10..].iter().map(|b| format!("{:02x}", b)).collect();
+    Some((clock_seq, node.join(":")))
+}
+
+/// One line of `decode --stdin`, valid or not; the JSON keys match those of `decode --stats`
+#[derive(Serialize, Debug, PartialEq)]
+struct InspectRow {
+    input: String,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canonical: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variant: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clock_seq: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    node: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_id: Option<u32>,
+}
+
+impl InspectRow {
+    fn new(input: &str) -> Self {
+        let Ok(id) = Uuid::parse_str(input) else {
+            return InspectRow {
+                input: input.to_string(),
+                valid: false,
+                canonical: None,
+                version: None,
+                variant: None,
+                timestamp: None,
+                clock_seq: None,
+                node: None,
+                domain: None,
+                local_id: None,
+            };
+        };
+        let variant = STATS_VARIANTS.iter().find(|&&(_, v)| v == id.get_variant()).map(|&(key, _)| key);
+        let timestamp = signed_unix_millis(&id).map(format_rfc3339_millis);
+        let (clock_seq, node) = time_mac_fields(&id).unzip();
+        let (domain, local_id) = dce_fields(&id).map(|(domain, local_id)| (dce_domain_label(domain), local_id)).unzip();
+        InspectRow {
+            input: input.to_string(),
+            valid: true,
+            canonical: Some(format_uuid(&id, &FormatOptions::default())),
+            version: Some(stats_version_key(&id)),
+            variant,
+            timestamp,
+            clock_seq,
+            node,
+            domain,
+            local_id,
+        }
+    }
+
+    /// Table cells in header order; an invalid line keeps its input in the UUID column
+    fn cells(&self, msgs: &Messages) -> [String; 4] {
+        let cell = |value: Option<&str>| value.unwrap_or("-").to_string();
+        match &self.canonical {
+            Some(canonical) => [
+                cell(self.version),
+                cell(self.variant),
+                cell(self.timestamp.as_deref()),
+                canonical.clone(),
+            ],
+            None => [msgs.invalid_label().to_string(), cell(None), cell(None), self.input.clone()],
+        }
+    }
+}
+
+/// Read `decode --stdin` rows, one per non-blank line
+fn inspect_rows<R: BufRead>(reader: R, msgs: &Messages) -> Result<Vec<InspectRow>, AppError> {
+    let mut rows = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        if !line.trim().is_empty() {
+            rows.push(InspectRow::new(line.trim()));
+        }
+    }
+    Ok(rows)
+}
+
+/// Render `decode --stdin` rows: an aligned table with a header for a terminal, TSV otherwise,
+/// or one JSON array; `color` marks invalid rows in red
+fn inspect_lines(rows: &[InspectRow], tty: bool, color: bool, json: Option<JsonLayout>, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    if let Some(layout) = json {
+        return json_array_lines(rows, layout);
+    }
+
+    let cells: Vec<[String; 4]> = rows.iter().map(|row| row.cells(msgs)).collect();
+    if !tty {
+        return Ok(cells.iter().map(|cells| cells.join("\t")).collect());
+    }
+
+    let header = msgs.inspect_header().map(String::from);
+    let mut widths = [0; 4];
+    for row in std::iter::once(&header).chain(&cells) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let align = |row: &[String; 4]| {
+        let padded: Vec<String> = row.iter().zip(widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        padded.join("  ").trim_end().to_string()
+    };
+
+    let mut lines = vec![align(&header)];
+    for (row, cells) in rows.iter().zip(&cells) {
+        let line = align(cells);
+        lines.push(if color && !row.valid { format!("\x1b[31m{}\x1b[0m", line) } else { line });
+    }
+    Ok(lines)
+}
+
+/// Inspect the UUIDs given as arguments to the default command, one TSV row each as with `decode --stdin`
+/// Every input is shown before an invalid one fails the run
+fn inspect_inputs(inputs: &[String], eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let rows: Vec<InspectRow> = inputs.iter().map(|input| InspectRow::new(input.trim())).collect();
+    print_lines(&inspect_lines(&rows, false, false, None, msgs)?, eol, out).map_err(|e| output_error(e, msgs))?;
+    match rows.iter().filter(|row| !row.valid).count() {
+        0 => Ok(()),
+        invalid => Err(AppError::Invalid(msgs.invalid_inputs(invalid, rows.len()))),
+    }
+}
+
+/// Whether a UUID passes every predicate given to `filter`, flipped by --invert
+fn filter_matches(args: &FilterArgs, id: &Uuid) -> bool {
+    let millis = signed_unix_millis(id);
+    let matched = args.version.is_none_or(|version| id.get_version_num() == usize::from(version))
+        && args.after.is_none_or(|after| millis.is_some_and(|millis| i128::from(millis) >= i128::from(after)))
+        && args.before.is_none_or(|before| millis.is_some_and(|millis| i128::from(millis) < i128::from(before)))
+        && args.prefix.as_ref().is_none_or(|prefix| id.simple().to_string().starts_with(prefix.as_str()));
+    matched != args.invert
+}
+
+/// Copy the matching lines of `reader` to `out` as they are read, keeping their original form
+/// Blank lines are skipped; a line that is not a UUID fails the run unless --skip-invalid is given
+fn filter_lines<R: BufRead>(args: &FilterArgs, reader: R, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let Ok(id) = Uuid::parse_str(trimmed) else {
+            if args.skip_invalid {
+                print_warning(WarningCode::InvalidLine, &msgs.invalid_line(i + 1, trimmed));
+                continue;
+            }
+            return Err(AppError::Invalid(msgs.invalid_line(i + 1, trimmed)));
+        };
+        if filter_matches(args, &id) {
+            write!(out, "{}{}", line, eol).map_err(|e| output_error(e, msgs))?;
+        }
+    }
+    Ok(())
+}
+
+/// Milliseconds since the Unix epoch of a v1, v6 or v7 UUID's timestamp, negative before 1970
+/// Counted from the Gregorian ticks, since `Timestamp::to_unix` wraps around for v1/v6 times before the epoch
+fn signed_unix_millis(id: &Uuid) -> Option<i64> {
+    let (ticks, _) = id.get_timestamp()?.to_gregorian();
+    Some((i128::from(ticks) - i128::from(GREGORIAN_OFFSET)).div_euclid(10_000) as i64)
+}
+
+/// A duration in its two largest units, e.g. 3d 4h, 5m 2s or 45s; a zero second unit is left out
+fn humanize_seconds(seconds: u64) -> String {
+    const UNITS: [(u64, &str); 4] = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+    let Some(i) = UNITS.iter().position(|&(size, _)| seconds >= size) else {
+        return "0s".to_string();
+    };
+    let (size, unit) = UNITS[i];
+    let mut text = format!("{}{}", seconds / size, unit);
+    if let Some(&(next, next_unit)) = UNITS.get(i + 1)
+        && (seconds % size) / next > 0
+    {
+        text += &format!(" {}{}", (seconds % size) / next, next_unit);
+    }
+    text
+}
+
+/// How far the timestamp of `id` lies before `now_millis`: humanized, or whole seconds with --seconds
+/// Timestamps ahead of the clock are marked as in the future (negative seconds); None without a timestamp
+fn uuid_age(id: &Uuid, now_millis: u64, seconds: bool, msgs: &Messages) -> Option<String> {
+    let age = (i128::from(now_millis) - i128::from(signed_unix_millis(id)?)) / 1000;
+    if seconds {
+        return Some(age.to_string());
+    }
+    let duration = humanize_seconds(age.unsigned_abs() as u64);
+    Some(match age.signum() {
+        0 => msgs.age_now().to_string(),
+        1 => msgs.age_ago(&duration),
+        _ => msgs.age_ahead(&duration),
+    })
+}
+
+/// Write the canonical form and age of each UUID given to `age`, as one TSV row each
+/// With --stdin, lines are read from `reader` and positions in errors are line numbers;
+/// `now_millis` is the clock every age is measured against
+fn age_report<R: BufRead>(
+    args: &AgeArgs,
+    reader: R,
+    now_millis: u64,
+    eol: &str,
+    out: &mut dyn Write,
+    msgs: &Messages,
+) -> Result<(), AppError> {
+    let mut report = |arg_name: &str, index: usize, input: &str| -> Result<(), AppError> {
+        let id = Uuid::parse_str(input.trim()).map_err(|_| InputError::new(arg_name, index, input, msgs.not_a_uuid()))?;
+        let age = match uuid_age(&id, now_millis, args.seconds, msgs) {
+            Some(age) => age,
+            None if args.lenient => "n/a".to_string(),
+            None => return Err(InputError::new(arg_name, index, input, msgs.no_timestamp(id.get_version_num())).into()),
+        };
+        write!(out, "{}\t{}{}", id, age, eol).map_err(|e| output_error(e, msgs))
+    };
+    if args.stdin {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+            if !line.trim().is_empty() {
+                report("--stdin", i + 1, &line)?;
+            }
+        }
+    } else {
+        for (i, input) in args.uuids.iter().enumerate() {
+            report("UUID", i + 1, input)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tally the UUIDs read one per line; blank lines are skipped, unparsable ones count as invalid
+fn uuid_stats<R: BufRead>(reader: R, require_version: Option<u8>, msgs: &Messages) -> Result<UuidStats, AppError> {
+    let mut stats = UuidStats {
+        total: 0,
+        invalid: 0,
+        versions: STATS_VERSIONS.iter().map(|&key| (key, 0)).collect(),
+        variants: STATS_VARIANTS.iter().map(|&(key, _)| (key, 0)).collect(),
+    };
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        stats.total += 1;
+        let Ok(id) = Uuid::parse_str(line) else {
+            stats.invalid += 1;
+            continue;
+        };
+        check_version(&id, line, require_version, msgs)?;
+        *stats.versions.entry(stats_version_key(&id)).or_default() += 1;
+        let variant = id.get_variant();
+        if let Some(&(key, _)) = STATS_VARIANTS.iter().find(|&&(_, v)| v == variant) {
+            *stats.variants.entry(key).or_default() += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// The stats as localized "label: count" lines, or JSON on one line (`Compact`) or indented (`Pretty`)
+fn stats_lines(stats: &UuidStats, json: Option<JsonLayout>, msgs: &Messages) -> Result<Vec<String>, AppError> {
+    let failure = |e: serde_json::Error| AppError::Failure(e.to_string());
+    match json {
+        Some(JsonLayout::Compact) => return Ok(vec![serde_json::to_string(stats).map_err(failure)?]),
+        Some(JsonLayout::Pretty) => {
+            let json = serde_json::to_string_pretty(stats).map_err(failure)?;
+            return Ok(json.lines().map(String::from).collect());
+        }
+        None => {}
+    }
+
+    let mut lines = vec![
+        msgs.stats_line(StatsLabel::Total, stats.total),
+        msgs.stats_line(StatsLabel::Invalid, stats.invalid),
+    ];
+    for key in STATS_VERSIONS {
+        lines.push(msgs.stats_line(StatsLabel::Version(key), stats.versions[key]));
+    }
+    for (key, variant) in STATS_VARIANTS {
+        lines.push(msgs.stats_line(StatsLabel::Variant(variant), stats.variants[key]));
+    }
+    Ok(lines)
+}
+
+/// What a REPL command produced
+#[derive(Debug, PartialEq)]
+enum ReplReply {
+    /// Lines to print, possibly none
+    Lines(Vec<String>),
+    /// Generate this many UUIDs, streamed one line at a time
+    Generate(usize),
+    /// End the session
+    Quit,
+}
+
+/// Version and format state carried between REPL commands
+/// `gen` counts are checked against `max_count` like the main command's `-n`
+struct ReplSession {
+    version: UuidVersion,
+    format: FormatOptions,
+    max_count: usize,
+    msgs: Messages,
+}
+
+impl ReplSession {
+    fn new(max_count: usize, msgs: &Messages) -> Self {
+        Self {
+            version: UuidVersion::V4,
+            format: FormatOptions::default(),
+            max_count,
+            msgs: *msgs,
+        }
+    }
+
+    /// Run one command line; user mistakes come back as localized errors and leave the session running
+    fn handle(&mut self, line: &str) -> Result<ReplReply, String> {
+        let msgs = &self.msgs;
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(ReplReply::Lines(Vec::new()));
+        };
+        let arg = words.next();
+
+        match (command.to_lowercase().as_str(), arg) {
+            ("quit" | "exit", _) => return Ok(ReplReply::Quit),
+            ("help", _) => return Ok(ReplReply::Lines(msgs.repl_help().map(String::from).to_vec())),
+            ("gen", arg) => {
+                let count = arg.map_or(Ok(1), parse_count)?;
+                check_count(count, false, self.max_count, msgs).map_err(|e| e.message().to_string())?;
+                return Ok(ReplReply::Generate(count));
+            }
+            ("v4", None) => self.version = UuidVersion::V4,
+            ("v7", None) => self.version = UuidVersion::V7,
+            ("decode" | "inspect", Some(input)) => {
+                let id = Uuid::parse_str(input).map_err(|_| msgs.invalid_uuid(input))?;
+                return Ok(ReplReply::Lines(msgs.decoded(&id)));
+            }
+            ("decode" | "inspect", None) => return Err(msgs.repl_usage("decode <uuid>")),
+            ("simple", None) => self.format.style = Style::Simple,
+            ("full", None) => self.format.style = Style::Hyphenated,
+            ("format", Some("simple")) => self.format.style = Style::Simple,
+            ("format", Some("full")) => self.format.style = Style::Hyphenated,
+            ("format", _) => return Err(msgs.repl_usage("format simple|full")),
+            ("upper", Some("on")) => self.format.case = Case::Upper,
+            ("upper", Some("off")) => self.format.case = Case::Lower,
+            ("upper", _) => return Err(msgs.repl_usage("upper on|off")),
+            _ => return Err(msgs.unknown_repl_command(command)),
+        }
+        Ok(ReplReply::Lines(Vec::new()))
+    }
+
+    /// Handle a line and print its result; returns false once the session should end
+    fn step(&mut self, line: &str, eol: &str, out: &mut dyn Write) -> Result<bool, AppError> {
+        match self.handle(line) {
+            Ok(ReplReply::Quit) => Ok(false),
+            Ok(ReplReply::Lines(lines)) => print_lines(&lines, eol, out).map(|()| true).map_err(|e| output_error(e, &self.msgs)),
+            Ok(ReplReply::Generate(count)) => {
+                for _ in 0..count {
+                    let id = match new_uuid(self.version, V7Precision::Millis, &mut OsEntropy, &self.msgs) {
+                        Ok(id) => id,
+                        Err(msg) => {
+                            report_error(&AppError::Failure(msg));
+                            break;
+                        }
+                    };
+                    write!(out, "{}{}", format_uuid(&id, &self.format), eol).map_err(|e| output_error(e, &self.msgs))?;
+                }
+                out.flush().map(|()| true).map_err(|e| output_error(e, &self.msgs))
+            }
+            Err(msg) => {
+                report_error(&AppError::Usage(msg));
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Drive a REPL session from plain lines, as when stdin is a script or pipe
+fn repl_lines<R: BufRead>(reader: R, max_count: usize, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut session = ReplSession::new(max_count, msgs);
+    for line in reader.lines() {
+        let line = line.map_err(|e| AppError::Io(msgs.read_error(&e)))?;
+        if !session.step(&line, eol, out)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Drive a REPL session on a terminal with line editing and history
+/// Ctrl-C and Ctrl-D end the session like `quit`
+fn repl_terminal(max_count: usize, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut editor = rustyline::DefaultEditor::new().map_err(|e| AppError::Io(e.to_string()))?;
+    let mut session = ReplSession::new(max_count, msgs);
+    loop {
+        let line = match editor.readline(&msgs.repl_prompt(session.version)) {
+            Ok(line) => line,
+            Err(rustyline::error::ReadlineError::Eof | rustyline::error::ReadlineError::Interrupted) => return Ok(()),
+            Err(rustyline::error::ReadlineError::Io(e)) => return Err(AppError::Io(msgs.read_error(&e))),
+            Err(e) => return Err(AppError::Io(e.to_string())),
+        };
+        let _ = editor.add_history_entry(line.as_str());
+        if !session.step(&line, eol, out)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Status and body of an HTTP reply, before it is put on the wire
+#[derive(Debug, PartialEq)]
+struct HttpReply {
+    status: u16,
+    json: bool,
+    body: String,
+}
+
+impl HttpReply {
+    /// A successful reply carrying UUIDs, newline-separated or as {"uuids": [...]}
+    fn uuids(ids: Vec<String>, json: bool) -> Self {
+        let body = if json {
+            serde_json::json!({ "uuids": ids }).to_string()
+        } else {
+            ids.iter().map(|id| format!("{}\n", id)).collect()
+        };
+        Self { status: 200, json, body }
+    }
+
+    /// An error reply with a localized message, as plain text or {"error": "..."}
+    fn error(status: u16, msg: String, json: bool) -> Self {
+        let body = if json { serde_json::json!({ "error": msg }).to_string() } else { format!("{}\n", msg) };
+        Self { status, json, body }
+    }
+}
+
+/// Answer `GET /uuid?version=&count=&format=` from the query string
+fn uuid_endpoint(query: &str, max_count: usize, msgs: &Messages) -> Result<Vec<String>, (u16, String)> {
+    let bad_request = |msg| (400, msg);
+    let (mut version, mut count, mut format) = (UuidVersion::V4, 1, TextFormat::Full);
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "version" => {
+                version = value.parse().map_err(|e: InvalidVersion| bad_request(e.to_string()))?;
+                if !version.is_standalone() {
+                    return Err(bad_request(msgs.serve_version_not_supported(value)));
+                }
+            }
+            "count" => count = parse_count(value).map_err(bad_request)?,
+            "format" => format = value.parse().map_err(bad_request)?,
+            _ => return Err(bad_request(msgs.unknown_query_parameter(key))),
+        }
+    }
+    if count > max_count {
+        return Err(bad_request(msgs.count_over_limit(count, max_count)));
+    }
+
+    mint_uuids(version, count, format, msgs).map_err(|msg| (500, msg))
+}
+
+/// Generate `count` UUIDs for a server client
+/// v7 goes through `next_v7`, so every client of this process sees one global order
+fn mint_uuids(version: UuidVersion, count: usize, format: TextFormat, msgs: &Messages) -> Result<Vec<String>, String> {
+    (0..count)
+        .map(|_| {
+            let id = new_uuid(version, V7Precision::Millis, &mut OsEntropy, msgs)?;
+            Ok(format.render(&id))
+        })
+        .collect()
+}
+
+/// Parse a Unix socket request line: VERSION [COUNT [FORMAT]], e.g. "7 100 simple"
+fn parse_mint_request(line: &str, max_count: usize, msgs: &Messages) -> Result<(UuidVersion, usize, TextFormat), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (version, count, format) = match words[..] {
+        [version] => (version, "1", "full"),
+        [version, count] => (version, count, "full"),
+        [version, count, format] => (version, count, format),
+        _ => return Err(msgs.invalid_mint_request(line.trim_end())),
+    };
+
+    let version: UuidVersion = version.parse()?;
+    if !version.is_standalone() {
+        return Err(msgs.serve_version_not_supported(&version.to_string()));
+    }
+    let count = parse_count(count)?;
+    if count > max_count {
+        return Err(msgs.count_over_limit(count, max_count));
+    }
+    Ok((version, count, format.parse()?))
+}
+
+/// Answer one socket client: read its request line, write the UUIDs (or `error: ...`), then close
+fn serve_unix_client<S: std::io::Read + Write>(stream: S, max_count: usize, msgs: &Messages) -> std::io::Result<()> {
+    let mut reader = std::io::BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut out = std::io::BufWriter::new(reader.into_inner());
+
+    match parse_mint_request(&line, max_count, msgs).and_then(|(version, count, format)| mint_uuids(version, count, format, msgs)) {
+        Ok(ids) => ids.iter().try_for_each(|id| writeln!(out, "{}", id))?,
+        Err(msg) => writeln!(out, "error: {}", msg)?,
+    }
+    out.flush()
+}
+
+/// The socket file of a running server, removed again when the server stops
+#[cfg(unix)]
+struct SocketFile(std::path::PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Bind the Unix socket, refusing to replace an existing file unless `force` is set
+#[cfg(unix)]
+fn bind_unix_socket(
+    path: &std::path::Path,
+    force: bool,
+    msgs: &Messages,
+) -> Result<(std::os::unix::net::UnixListener, SocketFile), AppError> {
+    if std::fs::symlink_metadata(path).is_ok() {
+        if !force {
+            return Err(AppError::Usage(msgs.socket_exists(path)));
+        }
+        std::fs::remove_file(path).map_err(|e| AppError::Io(msgs.socket_error(path, &e)))?;
+    }
+    let listener =
+        std::os::unix::net::UnixListener::bind(path).map_err(|e| AppError::Io(msgs.socket_error(path, &e)))?;
+    Ok((listener, SocketFile(path.to_path_buf())))
+}
+
+/// Answer each connection on its own thread until the listener fails
+#[cfg(unix)]
+fn serve_unix_connections(listener: &std::os::unix::net::UnixListener, max_count: usize, msgs: &Messages) {
+    let msgs = *msgs;
+    for stream in listener.incoming().flatten() {
+        // A client that disconnects early only loses its own reply
+        std::thread::spawn(move || serve_unix_client(stream, max_count, &msgs));
+    }
+}
+
+/// Serve on a Unix socket until stopped; Ctrl-C and SIGTERM remove the socket file before exiting
+#[cfg(unix)]
+fn serve_unix(path: &std::path::Path, force: bool, max_count: usize, msgs: &Messages) -> Result<(), AppError> {
+    let (listener, socket_file) = bind_unix_socket(path, force, msgs)?;
+    let cleanup = path.to_path_buf();
+    let _ = ctrlc::set_handler(move || {
+        let _ = std::fs::remove_file(&cleanup);
+        std::process::exit(0);
+    });
+    print_info(&msgs.serve_listening_unix(path));
+    serve_unix_connections(&listener, max_count, msgs);
+    drop(socket_file);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_path: &std::path::Path, _force: bool, _max_count: usize, msgs: &Messages) -> Result<(), AppError> {
+    Err(AppError::Usage(msgs.unix_socket_unsupported().to_string()))
+}
+
+/// Route one request; kept free of sockets so tests can call it directly
+fn handle_http(method: &tiny_http::Method, url: &str, json: bool, max_count: usize, msgs: &Messages) -> HttpReply {
+    if *method != tiny_http::Method::Get {
+        return HttpReply::error(405, msgs.method_not_allowed().to_string(), json);
+    }
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match path {
+        "/healthz" => HttpReply { status: 200, json: false, body: "ok\n".to_string() },
+        "/uuid" => match uuid_endpoint(query, max_count, msgs) {
+            Ok(ids) => HttpReply::uuids(ids, json),
+            Err((status, msg)) => HttpReply::error(status, msg, json),
+        },
+        _ => HttpReply::error(404, msgs.not_found(path), json),
+    }
+}
+
+/// Answer requests on `jobs` worker threads until the server stops
+fn serve_requests(server: &tiny_http::Server, jobs: u16, max_count: usize, msgs: &Messages) {
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    let json = request.headers().iter().any(|header| {
+                        header.field.equiv("Accept") && header.value.as_str().contains("application/json")
+                    });
+                    let reply = handle_http(request.method(), request.url(), json, max_count, msgs);
+                    let content_type = if reply.json { "application/json" } else { "text/plain; charset=utf-8" };
+                    let response = tiny_http::Response::from_string(reply.body)
+                        .with_status_code(reply.status)
+                        .with_header(tiny_http::Header::from_bytes("Content-Type", content_type).unwrap());
+                    // A client that hung up only loses its own reply
+                    let _ = request.respond(response);
+                }
+            });
+        }
+    });
+}
+
+/// Bind the listener and serve until the process is stopped
+fn serve(args: &ServeArgs, msgs: &Messages) -> Result<(), AppError> {
+    if let Some(path) = &args.unix {
+        return serve_unix(path, args.force, args.max_count, msgs);
+    }
+    let addr = std::net::SocketAddr::new(args.bind, args.port);
+    let server = tiny_http::Server::http(addr).map_err(|e| AppError::Io(msgs.serve_bind_error(&addr, &e.to_string())))?;
+    let local = server.server_addr().to_ip().unwrap_or(addr);
+    print_info(&msgs.serve_listening(&local));
+    serve_requests(&server, args.jobs, args.max_count, msgs);
+    Ok(())
+}
+
+/// Run a subcommand, writing its output lines to `out`
+fn run_command(command: &Command, max_count: usize, eol: &str, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let use_stdin = match command {
+        Command::Sample(args) => args.stdin,
+        Command::Shuffle(args) => args.stdin,
+        Command::Decode(args) if args.stats => {
+            let lines = stats_lines(&uuid_stats(std::io::stdin().lock(), args.require_version, msgs)?, args.json_layout(), msgs)?;
+            return print_lines(&lines, eol, out).map_err(|e| output_error(e, msgs));
+        }
+        Command::Decode(args) if args.stdin => {
+            let rows = inspect_rows(std::io::stdin().lock(), msgs)?;
+            let lines = inspect_lines(&rows, std::io::stdout().is_terminal(), stdout_color(), args.json_layout(), msgs)?;
+            return print_lines(&lines, eol, out).map_err(|e| output_error(e, msgs));
+        }
+        Command::Decode(args) => {
+            return print_lines(&decode_lines(args, stdout_color(), msgs)?, eol, out).map_err(|e| output_error(e, msgs));
+        }
+        Command::Bench(args) => return print_lines(&bench_report(args, msgs)?, eol, out).map_err(|e| output_error(e, msgs)),
+        Command::Repl if std::io::stdin().is_terminal() => return repl_terminal(max_count, eol, out, msgs),
+        Command::Repl => return repl_lines(std::io::stdin().lock(), max_count, eol, out, msgs),
+        Command::Serve(args) => return serve(args, msgs),
+        Command::Doctor => return doctor(&mut OsEntropy, unix_millis_now(), config_path().as_deref(), eol, out, msgs),
+        Command::Namespaces(args) => {
+            let lines = namespace_lines(JsonLayout::from_flags(args.json, args.json_pretty), msgs)?;
+            return print_lines(&lines, eol, out).map_err(|e| output_error(e, msgs));
+        }
+        Command::Scan(args) => {
+            return print_lines(&scan_lines(&scan_paths(args, msgs)?, args, msgs)?, eol, out).map_err(|e| output_error(e, msgs));
+        }
+        Command::Convert(args) => return convert(args, std::io::stdin().lock(), eol, out, msgs),
+        Command::Filter(args) if args.stdin => return filter_lines(args, std::io::stdin().lock(), eol, out, msgs),
+        Command::Filter(_) => false,
+        Command::Map(args) if args.stdin => return map_keys(args, std::io::stdin().lock(), eol, out, msgs),
+        Command::Map(_) => false,
+        Command::JsonFill(args) => return json_fill(args, std::io::stdin().lock(), eol, out, msgs),
+        Command::Age(args) => return age_report(args, std::io::stdin().lock(), unix_millis_now(), eol, out, msgs),
+        Command::Rotate(args) if args.stdin => return rotate_lines(args, std::io::stdin().lock(), eol, out, msgs),
+        Command::Rotate(_) => false,
+    };
+    if !use_stdin {
+        return Err(AppError::Usage(msgs.missing_input().to_string()));
+    }
+
+    let stdin = std::io::stdin().lock();
+    let lines = match command {
+        Command::Sample(args) => sample_lines(stdin, args.count, &mut stream_rng(args.seed), msgs)?,
+        Command::Shuffle(args) => shuffle_lines(stdin, args.max_lines, &mut stream_rng(args.seed), msgs)?,
+        Command::Decode(_)
+        | Command::Bench(_)
+        | Command::Repl
+        | Command::Doctor
+        | Command::Serve(_)
+        | Command::Namespaces(_)
+        | Command::Scan(_)
+        | Command::Convert(_)
+        | Command::Filter(_)
+        | Command::Map(_)
+        | Command::JsonFill(_)
+        | Command::Age(_)
+        | Command::Rotate(_) => {
+            unreachable!("handled above")
+        }
+    };
+    print_lines(&lines, eol, out).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate UUIDs as configured (or run a subcommand), writing results to `out`
+/// `prefer_full` and `conflict` come from the raw argument order, see `determine_format_precedence`
+/// Like other Unix filters, stops quietly and succeeds once the reader closes the pipe
+fn run(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let (suppressed, unknown) = suppressed_warnings(&cli.no_warn);
+    SUPPRESSED_WARNINGS.store(suppressed, Ordering::Relaxed);
+    for code in unknown {
+        print_warning(WarningCode::UnknownCode, &msgs.unknown_warning_code(code));
+    }
+    if let Some(path) = &cli.verify_checksum {
+        verify_checksum_file(path, msgs)?;
+        let ok = msgs.checksum_ok(path);
+        return print_lines(&[ok], line_ending(cli.crlf), out).map_err(|e| output_error(e, msgs));
+    }
+    if cli.command.is_none() && cli.encoding == Encoding::Raw {
+        return Err(AppError::Usage(msgs.raw_needs_binary().to_string()));
+    }
+    if cli.command.is_none() && cli.inputs.is_empty() && !cli.all_versions {
+        let count = if cli.keys.is_empty() { cli.count } else { cli.keys.len() };
+        check_count(count, cli.allow_empty || cli.json, cli.max_count, msgs)?;
+        log_verbose(cli, prefer_full, conflict, &mut std::io::stderr(), msgs).map_err(|e| output_error(e, msgs))?;
+    }
+    if cli.command.is_none()
+        && let Some(lines) = cli.split
+    {
+        let header = cli.split_header.as_deref();
+        let mut files = SplitWriter::new(&cli.split_prefix, lines, header, line_ending(cli.crlf), cli.bom);
+        generate(cli, prefer_full, conflict, &mut files, msgs)?;
+        return files.finish().map_err(|e| output_error(e, msgs));
+    }
+    if let Some(path) = &cli.output {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(cli.append)
+            .truncate(!cli.append)
+            .open(path)
+            .map_err(|e| AppError::Io(msgs.output_file_error(path, &e)))?;
+        return write_output(cli, prefer_full, conflict, &mut std::io::BufWriter::new(file), msgs);
+    }
+    if let Some(fd) = cli.fd {
+        return write_output(cli, prefer_full, conflict, &mut std::io::BufWriter::new(open_fd(fd, msgs)?), msgs);
+    }
+    write_output(cli, prefer_full, conflict, out, msgs)
+}
+
+/// Write the --verbose lines to `err`, showing how the flags, config and argument order were resolved
+/// Writes nothing without --verbose or with --quiet
+fn log_verbose(cli: &Cli, prefer_full: bool, conflict: bool, err: &mut dyn Write, msgs: &Messages) -> std::io::Result<()> {
+    if !cli.verbose || cli.quiet {
+        return Ok(());
+    }
+    let winner = conflict.then_some(if prefer_full { "-f" } else { "-s" });
+    let count = if cli.keys.is_empty() { cli.count } else { cli.keys.len() };
+    let lines = [
+        msgs.verbose_version(cli.version),
+        msgs.verbose_format(&FormatOptions::from_cli(cli, prefer_full), winner),
+        msgs.entropy_info(cli.seed),
+        msgs.verbose_count(count),
+    ];
+    let style = stderr_style();
+    for line in lines {
+        writeln!(err, "{}", format_diagnostic("verbose", "90", &line, style))?;
+    }
+    Ok(())
+}
+
+/// Reject a count of 0 unless `allow_empty`, and any count above `max_count`
+/// Runs before any output file is opened, so a rejected run leaves existing files alone
+fn check_count(count: usize, allow_empty: bool, max_count: usize, msgs: &Messages) -> Result<(), AppError> {
+    if count == 0 && !allow_empty {
+        return Err(AppError::Invalid(msgs.count_zero().to_string()));
+    }
+    if count > max_count {
+        return Err(AppError::Invalid(msgs.count_over_max(count, max_count)));
+    }
+    Ok(())
+}
+
+/// A writable handle on the inherited descriptor `fd`
+/// It is duplicated first, which both checks that it is open and leaves the original to the parent
+#[cfg(unix)]
+fn open_fd(fd: i32, msgs: &Messages) -> Result<std::fs::File, AppError> {
+    use std::os::fd::BorrowedFd;
+    // SAFETY: the descriptor is only borrowed for the duplication, which fails cleanly if it is not open
+    let owned = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned();
+    owned.map(std::fs::File::from).map_err(|e| AppError::Io(msgs.fd_error(fd, &e)))
+}
+
+#[cfg(not(unix))]
+fn open_fd(_fd: i32, msgs: &Messages) -> Result<std::fs::File, AppError> {
+    Err(AppError::Usage(msgs.fd_unsupported().to_string()))
+}
+
+/// Write the BOM and the command's output to `out`
+/// With --checksum, the checksum line follows and covers all of it, BOM included
+fn write_output(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut out = FinalNewlineWriter::new(out, cli.no_final_newline);
+    let mut out = ChecksumWriter::new(&mut out, cli.checksum);
+    let out = &mut out;
+    let bom = if cli.bom { out.write_all(UTF8_BOM).map_err(|e| output_error(e, msgs)) } else { Ok(()) };
+    let result = bom.and_then(|()| match &cli.command {
+        Some(command) => run_command(command, cli.max_count, line_ending(cli.crlf), out, msgs),
+        None if !cli.inputs.is_empty() => inspect_inputs(&cli.inputs, line_ending(cli.crlf), out, msgs),
+        None if cli.all_versions => generate_all_versions(cli, prefer_full, out, msgs),
+        None if cli.repl => run_command(&Command::Repl, cli.max_count, line_ending(cli.crlf), out, msgs),
+        None if cli.columns && std::io::stdout().is_terminal() => generate_columns(cli, prefer_full, conflict, out, msgs),
+        None if cli.json => generate_json(cli, prefer_full, conflict, out, msgs),
+        None if cli.wrap.is_some() => generate_wrapped(cli, prefer_full, conflict, out, msgs),
+        None if cli.xml => generate_xml(cli, prefer_full, conflict, out, msgs),
+        None if cli.pg_copy || cli.pg_copy_binary => generate_pg_copy(cli, prefer_full, conflict, out, msgs),
+        None => generate(cli, prefer_full, conflict, out, msgs),
+    });
+    let result = result.and_then(|()| out.finish(line_ending(cli.crlf)).map_err(|e| output_error(e, msgs)));
+    match result.and_then(|()| out.flush().map_err(|e| output_error(e, msgs))) {
+        Err(AppError::Closed) => Ok(()),
+        result => result,
+    }
+}
+
+/// One UUID of each version for --all-versions, as "v4 <uuid>" lines or a JSON object keyed by version
+fn generate_all_versions(cli: &Cli, prefer_full: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let name = cli.names.first();
+    if name.is_some() && cli.namespace.is_none() {
+        return Err(AppError::Usage(msgs.missing_namespace(UuidVersion::V5)));
+    }
+    let options = FormatOptions::from_cli(cli, prefer_full);
+    let precision = cli.v7_precision.unwrap_or_default();
+    let mut source = entropy_source(cli.seed);
+
+    let mut ids = vec![("nil".to_string(), format_uuid(&Uuid::nil(), &options))];
+    for &version in UuidVersion::value_variants() {
+        let id = match (version, name, cli.namespace) {
+            (UuidVersion::V2, _, _) => {
+                let domain = cli.domain.unwrap_or_default();
+                let Some(local_id) = cli.local_id.or_else(|| default_local_id(domain)) else {
+                    continue;
+                };
+                new_dce_uuid(domain, local_id, source.as_mut(), msgs).map_err(AppError::Failure)?
+            }
+            (version, Some(name), Some(namespace)) if version.is_name_based() => name_based_uuid(version, &namespace, name),
+            (version, _, _) if version.is_name_based() => continue,
+            (version, _, _) => new_uuid(version, precision, source.as_mut(), msgs).map_err(AppError::Failure)?,
+        };
+        ids.push((version.to_string(), format_uuid(&id, &options)));
+    }
+    ids.push(("max".to_string(), format_uuid(&Uuid::max(), &options)));
+
+    let lines = if cli.json {
+        let object: serde_json::Map<String, serde_json::Value> =
+            ids.into_iter().map(|(version, id)| (version, id.into())).collect();
+        vec![serde_json::to_string(&object).map_err(|e| AppError::Failure(e.to_string()))?]
+    } else {
+        ids.iter().map(|(version, id)| format!("{} {}", version, id)).collect()
+    };
+    print_lines(&lines, line_ending(cli.crlf), out).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate UUIDs as the elements of an XML document for --xml
+/// The root element is written even when there are no UUIDs, so the document is always well-formed
+fn generate_xml(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let eol = line_ending(cli.crlf);
+    if cli.xml_declaration {
+        write!(out, "<?xml version=\"1.0\"?>{}", eol).map_err(|e| output_error(e, msgs))?;
+    }
+    write!(out, "<{}>{}", cli.xml_root, eol).map_err(|e| output_error(e, msgs))?;
+    generate(cli, prefer_full, conflict, out, msgs)?;
+    write!(out, "</{}>{}", cli.xml_root, eol).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate UUIDs for PostgreSQL's COPY FROM STDIN, adding the binary header and trailer
+/// or the optional text end-of-data marker
+fn generate_pg_copy(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    if cli.pg_copy_binary {
+        out.write_all(PG_COPY_HEADER).map_err(|e| output_error(e, msgs))?;
+        generate(cli, prefer_full, conflict, out, msgs)?;
+        return out.write_all(PG_COPY_TRAILER).map_err(|e| output_error(e, msgs));
+    }
+    generate(cli, prefer_full, conflict, out, msgs)?;
+    if cli.with_terminator {
+        write!(out, "\\.{}", line_ending(cli.crlf)).map_err(|e| output_error(e, msgs))?;
+    }
+    Ok(())
+}
+
+/// Keys already assigned in a .env file, ignoring comments and an optional export prefix
+fn env_keys(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("export ").unwrap_or(line).split_once('='))
+        .map(|(key, _)| key.trim())
+        .collect()
+}
+
+/// Generate UUIDs as a JSON array of strings for --json, [] when there are none
+fn generate_json(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut batch = Vec::new();
+    generate(cli, prefer_full, conflict, &mut batch, msgs)?;
+    let batch = String::from_utf8_lossy(&batch);
+    let items = batch
+        .lines()
+        .map(|line| if json_objects(cli) { serde_json::from_str(line) } else { Ok(serde_json::Value::from(line)) })
+        .collect::<Result<Vec<serde_json::Value>, _>>()
+        .map_err(|e| AppError::Failure(e.to_string()))?;
+    let json = serde_json::to_string(&items).map_err(|e| AppError::Failure(e.to_string()))?;
+    print_lines(&[json], line_ending(cli.crlf), out).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate base32/base64 UUIDs as one concatenated stream, wrapped at --wrap columns
+fn generate_wrapped(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    if cli.encoding.rfc4648_alphabet().is_none() {
+        return Err(AppError::Usage(msgs.wrap_not_supported(cli.encoding)));
+    }
+    let mut batch = Vec::new();
+    generate(cli, prefer_full, conflict, &mut batch, msgs)?;
+    let batch = String::from_utf8_lossy(&batch);
+    let items: Vec<&str> = batch.lines().collect();
+    print_lines(&wrap_concatenated(&items, cli.wrap.unwrap_or(0)), line_ending(cli.crlf), out).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate UUIDs and lay them out in columns across the terminal for --columns
+/// Column widths depend on every item, so the whole batch is generated first
+fn generate_columns(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let mut batch = Vec::new();
+    generate(cli, prefer_full, conflict, &mut batch, msgs)?;
+    let batch = String::from_utf8_lossy(&batch);
+    let items: Vec<&str> = batch.lines().collect();
+    let width = terminal_size::terminal_size().map_or(DEFAULT_TERMINAL_WIDTH, |(width, _)| width.0 as usize);
+    print_lines(&columns(&items, width), line_ending(cli.crlf), out).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate UUIDs as configured, writing results to `out`
+/// With --summary or --summary-json, a line about the batch follows on stderr once it is all written
+fn generate(cli: &Cli, prefer_full: bool, conflict: bool, out: &mut dyn Write, msgs: &Messages) -> Result<(), AppError> {
+    let started = std::time::Instant::now();
+    let mut summary = BatchSummary::default();
+    if cli.distribution_check && cli.version != UuidVersion::V4 {
+        return Err(AppError::Usage(msgs.distribution_check_not_supported(&cli.version.to_string())));
+    }
+    generate_batch(cli, prefer_full, conflict, out, &mut summary, msgs)?;
+    if cli.distribution_check {
+        out.flush().map_err(|e| output_error(e, msgs))?;
+        distribution_check(&summary.first_bytes, cli.quiet, msgs);
+    }
+    if cli.quiet || !(cli.summary || cli.summary_json) {
+        return Ok(());
+    }
+
+    let elapsed = started.elapsed();
+    out.flush().map_err(|e| output_error(e, msgs))?;
+    let version = if cli.mix.is_some() { msgs.mixed_versions().to_string() } else { cli.version.to_string() };
+    if cli.summary_json {
+        let report = SummaryReport::new(&summary, &version, elapsed);
+        eprintln!("{}", serde_json::to_string(&report).map_err(|e| AppError::Failure(e.to_string()))?);
+    } else {
+        print_info(&msgs.batch_summary(&summary, &version, elapsed));
+    }
+    Ok(())
+}
+
+/// Report the first-byte chi-square for --distribution-check, warning (W012) when it is skewed
+/// Returns whether the distribution looked skewed
+fn distribution_check(histogram: &ByteHistogram, quiet: bool, msgs: &Messages) -> bool {
+    let chi_square = histogram.chi_square();
+    if !quiet {
+        print_info(&msgs.distribution_summary(histogram, chi_square));
+    }
+    let skewed = chi_square.is_some_and(|chi_square| chi_square > FIRST_BYTE_CHI_SQUARE_LIMIT);
+    if skewed {
+        print_warning(WarningCode::SkewedDistribution, msgs.distribution_skewed());
+    }
+    skewed
+}
+
+/// Generate the batch into `out`, adding each UUID to `summary`
+/// With --audit-log the batch goes through `generate_logged`, so it is all logged before any of it is printed
+fn generate_batch(
+    cli: &Cli,
+    prefer_full: bool,
+    conflict: bool,
+    out: &mut dyn Write,
+    summary: &mut BatchSummary,
+    msgs: &Messages,
+) -> Result<(), AppError> {
+    match &cli.audit_log {
+        Some(path) => {
+            let mut log = AuditLog::open(path, cli.audit_best_effort, msgs)?;
+            generate_logged(cli, prefer_full, conflict, out, summary, &mut log, msgs)
+        }
+        None => emit_batch(cli, prefer_full, conflict, out, summary, None, msgs),
+    }
+}
+
+/// Generate the batch into a buffer, record every UUID in `log`, and only then write the buffer to `out`
+/// A log write failing partway (outside best-effort mode) thus fails the run with nothing printed
+fn generate_logged(
+    cli: &Cli,
+    prefer_full: bool,
+    conflict: bool,
+    out: &mut dyn Write,
+    summary: &mut BatchSummary,
+    log: &mut AuditLog,
+    msgs: &Messages,
+) -> Result<(), AppError> {
+    let mut batch = Vec::new();
+    let mut ids = Vec::new();
+    emit_batch(cli, prefer_full, conflict, &mut batch, summary, Some(&mut ids), msgs)?;
+    for id in &ids {
+        log.record(id, cli.version)?;
+    }
+    out.write_all(&batch).map_err(|e| output_error(e, msgs))
+}
+
+/// Generate the batch into `out`, adding each UUID to `summary` and, when given, to `generated`
+fn emit_batch(
+    cli: &Cli,
+    prefer_full: bool,
+    conflict: bool,
+    out: &mut dyn Write,
+    summary: &mut BatchSummary,
+    mut generated: Option<&mut Vec<Uuid>>,
+    msgs: &Messages,
+) -> Result<(), AppError> {
+    if conflict {
+        print_conflict_warning(prefer_full, msgs);
+    }
+
+    let eol = line_ending(cli.crlf);
+    if cli.entropy_info && !cli.quiet {
+        print_info(&msgs.entropy_info(cli.seed));
+    }
+    let mut source = entropy_source(cli.seed);
+    let mut record = |id: &Uuid| -> Result<(), AppError> {
+        summary.add(*id);
+        if let Some(generated) = generated.as_mut() {
+            generated.push(*id);
+        }
+        Ok(())
+    };
+
+    // Several named C arrays or exports in one run get numbered names so they do not collide
+    // --keys sets the count, one UUID per key
+    let count = if cli.keys.is_empty() { cli.count } else { cli.keys.len() };
+    let numbered_names = cli.per_line || count > 1 || cli.names.len() > 1;
+    let name_index = std::cell::Cell::new(0);
+    let next_name = |name: Option<&str>| {
+        let name = name?;
+        name_index.set(name_index.get() + 1);
+        Some(if numbered_names { format!("{}_{}", name, name_index.get()) } else { name.to_string() })
+    };
+    let env_key = || {
+        name_index.set(name_index.get() + 1);
+        cli.keys.get(name_index.get() - 1).cloned().unwrap_or_else(|| format!("UUID_{}", name_index.get()))
+    };
+
+    let options = FormatOptions::from_cli(cli, prefer_full);
+    let line_index = std::cell::Cell::new(0);
+    let render = |id: Uuid| {
+        let text = if let Some(language) = &cli.snippet {
+            // Every language's parser accepts the hyphenated form, which not all accept without hyphens
+            snippet(language, &format_uuid(&id, &FormatOptions { style: Style::Hyphenated, ..options }))
+        } else if cli.c_array {
+            c_array(&id, options.case, next_name(cli.c_array_name.as_deref()).as_deref())
+        } else if let Some(len) = cli.short {
+            short_id(&id, len, options.case, cli.encoding)
+        } else {
+            match cli.encoding {
+                Encoding::Uuid25 => encode_uuid25(&id, options.case),
+                Encoding::Efi => efi_guid(&id),
+                Encoding::Oid => uuid_oid(&id),
+                Encoding::Base32 | Encoding::Base64 | Encoding::Base64Url => {
+                    let (alphabet, bits) = cli.encoding.rfc4648_alphabet().expect("an RFC 4648 encoding");
+                    encode_rfc4648(&id, alphabet, bits)
+                }
+                _ if cli.check_digit => with_check_digit(&id, &options),
+                _ => format_uuid(&id, &options),
+            }
+        };
+        if json_objects(cli) {
+            return json_record(&id, &text, cli.with_partition, cli.show_variant, cli.with_hash);
+        }
+        let text = match cli.with_partition {
+            Some(partitions) => format!("{}\t{}", text, kafka_partition(&text, partitions)),
+            None => text,
+        };
+        let text = if cli.show_variant { format!("{} {}", text, variant_label(&id)) } else { text };
+        let text = if cli.with_hash { format!("{} {}", text, uuid_sha256(&id)) } else { text };
+        let text = if cli.comment { format!("{} {}", text, record_comment(&cli.comment_prefix, &id)) } else { text };
+        if cli.timestamped {
+            with_timestamp(unix_millis_now(), &text)
+        } else if let Some(name) = next_name(cli.export.as_deref()) {
+            export_statement(&name, &text, cli.shell.unwrap_or_default())
+        } else if cli.env_file {
+            format!("{}={}", env_key(), text)
+        } else if cli.ndjson {
+            let index = cli.with_index.then(|| line_index.replace(line_index.get() + 1));
+            ndjson_record(&id, &text, index)
+        } else if cli.xml {
+            xml_element(&cli.xml_element, id.get_version_num(), &text)
+        } else {
+            text
+        }
+    };
+    let mut pacer = cli.rate.map(Pacer::new);
+    let v7 = cli.version == UuidVersion::V7;
+    let mut rows = 0;
+    let mut print = |id, out: &mut dyn Write| -> Result<(), AppError> {
+        if let Some(pacer) = pacer.as_mut() {
+            pacer.wait();
+        }
+        record(&id)?;
+        if cli.pg_copy_binary {
+            out.write_all(&pg_copy_tuple(id)).map_err(|e| output_error(e, msgs))?;
+        } else if cli.markdown {
+            // Rows are written as they come, so the header goes out with the first one
+            if rows == 0 {
+                print_lines(&markdown_header(v7).map(String::from), eol, out).map_err(|e| output_error(e, msgs))?;
+            }
+            rows += 1;
+            write!(out, "{}{}", markdown_row(rows, &render(id), id, v7), eol).map_err(|e| output_error(e, msgs))?;
+        } else {
+            write!(out, "{}{}", render(id), eol).map_err(|e| output_error(e, msgs))?;
+        }
+        if pacer.is_some() {
+            out.flush().map_err(|e| output_error(e, msgs))?;
+        }
+        if cli.qr {
+            // The text line goes to stdout for pipes; the picture is for the person at the terminal
+            let text = if cli.qr_urn {
+                id.urn().to_string()
+            } else {
+                format_uuid(&id, &options)
+            };
+            out.flush().map_err(|e| output_error(e, msgs))?;
+            eprintln!("{}", render_qr(&qr_code(&text, msgs)?));
+        }
+        Ok(())
+    };
+
+    if let Some(key) = duplicate_names(&cli.keys).first() {
+        return Err(AppError::Invalid(msgs.duplicate_key(key)));
+    }
+    if cli.env_file
+        && cli.append
+        && let Some(path) = &cli.output
+        && let Ok(existing) = std::fs::read_to_string(path)
+    {
+        let existing = env_keys(&existing);
+        let new_keys: Vec<String> =
+            if cli.keys.is_empty() { (1..=count).map(|i| format!("UUID_{}", i)).collect() } else { cli.keys.clone() };
+        for key in new_keys.iter().filter(|key| existing.contains(&key.as_str())) {
+            print_warning(WarningCode::EnvKeyExists, &msgs.env_key_exists(key, path));
+        }
+    }
+
+    if let (Some(target), Some(partitions)) = (cli.target_partition, cli.with_partition)
+        && target >= partitions
+    {
+        return Err(AppError::Usage(msgs.target_partition_out_of_range(target, partitions)));
+    }
+    if let Some(len) = cli.short {
+        match cli.encoding.text_length() {
+            None => return Err(AppError::Usage(msgs.short_not_supported(cli.encoding))),
+            Some(max) if len > max => return Err(AppError::Invalid(msgs.short_too_long(len, cli.encoding, max))),
+            Some(_) => {}
+        }
+    }
+    if cli.qr && (count != 1 || cli.names.len() > 1) {
+        return Err(AppError::Usage(msgs.qr_single_only().to_string()));
+    }
+
+    if let Some(prefix) = &cli.vanity {
+        if cli.version != UuidVersion::V4 {
+            return Err(AppError::Usage(msgs.vanity_requires_v4(cli.version)));
+        }
+        if prefix.len() > VANITY_WARN_LENGTH {
+            print_warning(WarningCode::SlowVanity, &msgs.slow_vanity_search(prefix));
+        }
+
+        // Progress is decoration, so porcelain mode leaves it out
+        let mut reported = false;
+        let id = vanity_search(prefix, cli.jobs, |attempts, elapsed| {
+            if !cli.porcelain {
+                let per_second = (attempts as f64 / elapsed.as_secs_f64()) as u64;
+                eprint!("\r{}", msgs.vanity_progress(attempts, per_second));
+                reported = true;
+            }
+        });
+        if reported {
+            eprintln!();
+        }
+        print(id, out)?;
+        return Ok(());
+    }
+
+    if let Some(phrase) = &cli.from_phrase {
+        if cli.version != UuidVersion::V4 {
+            return Err(AppError::Usage(msgs.phrase_requires_v4(cli.version)));
+        }
+        print_warning(WarningCode::InsecurePhrase, msgs.phrase_not_secure());
+
+        let mut ids: Vec<Uuid> = match count {
+            1 => vec![phrase_uuid(phrase, None)],
+            count => (1..=count).map(|i| phrase_uuid(phrase, Some(i))).collect(),
+        };
+        if cli.sort {
+            ids.sort_unstable();
+        } else if cli.shuffle {
+            ids.shuffle(&mut stream_rng(cli.seed));
+        }
+        for id in ids {
+            print(id, out)?;
+        }
+        return Ok(());
+    }
+
+    let dce = match cli.version {
+        UuidVersion::V2 => {
+            let domain = cli.domain.unwrap_or_default();
+            match cli.local_id.or_else(|| default_local_id(domain)) {
+                Some(local_id) => Some((domain, local_id)),
+                None => return Err(AppError::Usage(msgs.missing_local_id(domain))),
+            }
+        }
+        version if cli.domain.is_some() || cli.local_id.is_some() => {
+            return Err(AppError::Usage(msgs.dce_option_not_supported(version)));
+        }
+        _ => None,
+    };
+
+    if cli.version.is_name_based() {
+        if cli.registry.is_some() {
+            return Err(AppError::Usage(msgs.registry_not_supported(cli.version)));
+        }
+        if cli.per_line {
+            return Err(AppError::Usage(msgs.per_line_not_supported(cli.version)));
+        }
+        let Some(namespace) = cli.namespace else {
+            return Err(AppError::Usage(msgs.missing_namespace(cli.version)));
+        };
+        if cli.names.is_empty() {
+            return Err(AppError::Usage(msgs.missing_name(cli.version)));
+        }
+
+        if cli.warn_dup_names {
+            for name in duplicate_names(&cli.names) {
+                print_warning(WarningCode::DuplicateName, &msgs.duplicate_name(name));
+            }
+        }
+        let names = if cli.unique_names {
+            unique_names(&cli.names)
+        } else {
+            cli.names.iter().map(String::as_str).collect()
+        };
+
+        let mut ids: Vec<Uuid> = names.iter().map(|name| name_based_uuid(cli.version, &namespace, name)).collect();
+        if cli.sort {
+            ids.sort_unstable();
+        } else if cli.shuffle {
+            ids.shuffle(&mut stream_rng(cli.seed));
+        }
+        for id in ids {
+            print(id, out)?;
+        }
+        return Ok(());
+    }
+    if !cli.names.is_empty() {
+        return Err(AppError::Usage(msgs.name_not_supported(cli.version)));
+    }
+    if cli.v7_precision.is_some() && cli.version != UuidVersion::V7 {
+        return Err(AppError::Usage(msgs.v7_precision_not_supported(cli.version)));
+    }
+    if cli.timestamp_ms.is_some() && cli.version != UuidVersion::V7 {
+        return Err(AppError::Usage(msgs.timestamp_ms_not_supported(cli.version)));
+    }
+    let precision = cli.v7_precision.unwrap_or_default();
+    if cli.counter_start.is_some() && precision != V7Precision::SubMillis {
+        return Err(AppError::Usage(msgs.counter_start_needs_sub_ms().to_string()));
+    }
+    let pinned = cli.timestamp_ms.is_some() || cli.counter_start.is_some();
+    let last_pinned = std::cell::Cell::new(None);
+    let mix_plan = match &cli.mix {
+        Some(mix) => {
+            if mix.total() != 100 {
+                print_warning(WarningCode::MixNormalized, &msgs.mix_normalized(mix.total()));
+            }
+            mix.plan(count, &mut stream_rng(cli.seed))
+        }
+        None => Vec::new(),
+    };
+    let mix_index = std::cell::Cell::new(0);
+    let fresh = |source: &mut dyn EntropySource| match dce {
+        Some((domain, local_id)) => new_dce_uuid(domain, local_id, source, msgs),
+        None if !mix_plan.is_empty() => new_uuid(mix_plan[mix_index.replace(mix_index.get() + 1)], precision, source, msgs),
+        None if pinned => {
+            let id = pinned_v7(precision, cli.timestamp_ms, cli.counter_start, last_pinned.get(), source, msgs)?;
+            last_pinned.set(Some(id));
+            Ok(id)
+        }
+        None => new_uuid(cli.version, precision, source, msgs),
+    };
+    let fresh = |source: &mut dyn EntropySource| match (cli.target_partition, cli.with_partition) {
+        (Some(target), Some(partitions)) => find_partition(target, partitions, &options, || fresh(&mut *source), msgs),
+        _ => fresh(source),
+    };
+
+    if cli.per_line {
+        let next = |number, line: &[u8]| {
+            let id = if cli.versions_from_stdin {
+                new_uuid(line_version(number, line, msgs)?, precision, source.as_mut(), msgs)?
+            } else {
+                fresh(source.as_mut())?
+            };
+            record(&id)?;
+            Ok(render(id))
+        };
+        emit_per_line(std::io::stdin().lock(), out, eol, next, msgs)?;
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.registry {
+        let mut ids = Registry::open(path, cli.registry_binary, msgs)?.issue(count, || fresh(source.as_mut()))?;
+        if cli.sort {
+            ids.sort_unstable();
+        } else if cli.shuffle {
+            ids.shuffle(&mut stream_rng(cli.seed));
+        }
+        for id in ids {
+            print(id, out)?;
+        }
+    } else if cli.sort && dce.is_none() && cli.mix.is_none() {
+        for id in generate_sorted(cli.version, precision, count, source.as_mut(), msgs)? {
+            print(id, out)?;
+        }
+    } else if cli.sort || cli.shuffle {
+        let mut ids = (0..count).map(|_| fresh(source.as_mut())).collect::<Result<Vec<_>, _>>()?;
+        if cli.sort {
+            ids.sort_unstable();
+        } else {
+            ids.shuffle(&mut stream_rng(cli.seed));
+        }
+        for id in ids {
+            print(id, out)?;
+        }
+    } else {
+        for _ in 0..count {
+            print(fresh(source.as_mut())?, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> std::process::ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    // Set before parsing so that even usage errors get porcelain diagnostics
+    PORCELAIN.store(args.iter().any(|arg| arg == "--porcelain"), Ordering::Relaxed);
+    let lang = Language::resolve(&args);
+    // Kept for clap's value parsers and the Display impls, which cannot be handed `msgs`
+    Messages::init(lang);
+    let msgs = Messages::new(lang);
+
+    let (prefer_full, conflict) = determine_format_precedence(&args);
+    let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+    let result = parse_cli(&args, &msgs).and_then(|cli| run(&cli, prefer_full, conflict, &mut stdout, &msgs));
+
+    match result {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            report_error(&err);
+            std::process::ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generate a UUID and format it with `options`
+    fn generate_uuid(version: UuidVersion, options: FormatOptions) -> String {
+        format_uuid(&new_uuid(version, V7Precision::Millis, &mut OsEntropy, &ENGLISH).unwrap(), &options)
+    }
+
+    const UPPER: FormatOptions = FormatOptions { style: Style::Hyphenated, case: Case::Upper };
+    const SIMPLE: FormatOptions = FormatOptions { style: Style::Simple, case: Case::Lower };
+    const UPPER_SIMPLE: FormatOptions = FormatOptions { style: Style::Simple, case: Case::Upper };
+
+    /// What `main` hands down when the language resolves to English
+    const ENGLISH: Messages = Messages::new(Language::English);
+
+    #[test]
+    fn test_generate_default_format() {
+        let uuid = generate_uuid(UuidVersion::V4, FormatOptions::default());
+        // Default format: lowercase with hyphens (8-4-4-4-12)
+        assert!(uuid.len() == 36);
+        assert!(uuid.contains('-'));
+        assert!(uuid.chars().filter(|&c| c == '-').count() == 4);
+        // Check that there are no uppercase letters
+        assert!(!uuid.chars().any(|c| c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn test_generate_uppercase_format() {
+        let uuid = generate_uuid(UuidVersion::V4, UPPER);
+        // Uppercase format with hyphens
+        assert!(uuid.len() == 36);
+        assert!(uuid.contains('-'));
+        assert!(uuid.chars().filter(|&c| c == '-').count() == 4);
+        // Check that there are no lowercase letters
+        assert!(!uuid.chars().any(|c| c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_generate_simple_format() {
+        let uuid = generate_uuid(UuidVersion::V4, SIMPLE);
+        // Simple format: lowercase without hyphens
+        assert!(uuid.len() == 32);
+        assert!(!uuid.contains('-'));
+        // Check that there are no uppercase letters
+        assert!(!uuid.chars().any(|c| c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn test_generate_uppercase_simple_format() {
+        let uuid = generate_uuid(UuidVersion::V4, UPPER_SIMPLE);
+        // Uppercase simple format
+        assert!(uuid.len() == 32);
+        assert!(!uuid.contains('-'));
+        // Check that there are no lowercase letters
+        assert!(!uuid.chars().any(|c| c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_uuid_v4_uniqueness() {
+        let uuid1 = generate_uuid(UuidVersion::V4, FormatOptions::default());
+        let uuid2 = generate_uuid(UuidVersion::V4, FormatOptions::default());
+        // Two UUIDs should be different (extremely unlikely to be the same)
+        assert_ne!(uuid1, uuid2);
+    }
+
+    #[test]
+    fn test_uuid_v7_uniqueness() {
+        let uuid1 = generate_uuid(UuidVersion::V7, FormatOptions::default());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let uuid2 = generate_uuid(UuidVersion::V7, FormatOptions::default());
+        // Two V7 UUIDs should be different
+        assert_ne!(uuid1, uuid2);
+    }
+
+    #[test]
+    fn test_uuid_v7_ordered() {
+        let uuid1 = generate_uuid(UuidVersion::V7, FormatOptions::default());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let uuid2 = generate_uuid(UuidVersion::V7, FormatOptions::default());
+        // V7 UUIDs should be time-ordered (uuid2 > uuid1)
+        assert!(uuid2 > uuid1);
+    }
+
+    #[test]
+    fn test_uuid_valid_format() {
+        let uuid = generate_uuid(UuidVersion::V4, FormatOptions::default());
+        // Check standard UUID format: xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx
+        let parts: Vec<&str> = uuid.split('-').collect();
+        assert_eq!(parts.len(), 5);
+        assert_eq!(parts[0].len(), 8);
+        assert_eq!(parts[1].len(), 4);
+        assert_eq!(parts[2].len(), 4);
+        assert_eq!(parts[3].len(), 4);
+        assert_eq!(parts[4].len(), 12);
+    }
+
+    #[test]
+    fn test_uuid_hex_chars() {
+        let uuid = generate_uuid(UuidVersion::V4, SIMPLE);
+        // All characters should be valid hex digits
+        assert!(uuid.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_cli_parse_uppercase_short() {
+        let cli = Cli::try_parse_from(["zuuid", "-U"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_uppercase_short_lower() {
+        let cli = Cli::try_parse_from(["zuuid", "-u"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_uppercase_long() {
+        let cli = Cli::try_parse_from(["zuuid", "--upper"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_simple_short() {
+        let cli = Cli::try_parse_from(["zuuid", "-s"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(!cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_simple_short_upper() {
+        let cli = Cli::try_parse_from(["zuuid", "-S"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(!cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_simple_long() {
+        let cli = Cli::try_parse_from(["zuuid", "--simple"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(!cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined() {
+        let cli = Cli::try_parse_from(["zuuid", "-u", "-s"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_long() {
+        let cli = Cli::try_parse_from(["zuuid", "--upper", "--simple"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_no_args() {
+        let cli = Cli::try_parse_from(["zuuid"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
         assert!(!cli.uppercase);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_version_4() {
+        let cli = Cli::try_parse_from(["zuuid", "-V", "4"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+    }
+
+    #[test]
+    fn test_cli_parse_version_7() {
+        let cli = Cli::try_parse_from(["zuuid", "-V", "7"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+    }
+
+    #[test]
+    fn test_cli_parse_version_lowercase_v_4() {
+        let cli = Cli::try_parse_from(["zuuid", "-v", "4"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+    }
+
+    #[test]
+    fn test_cli_parse_version_lowercase_v_7() {
+        let cli = Cli::try_parse_from(["zuuid", "-v", "7"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+    }
+
+    #[test]
+    fn test_cli_parse_version_long() {
+        let cli = Cli::try_parse_from(["zuuid", "--uuid-version", "7"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+    }
+
+    #[test]
+    fn test_cli_parse_version_with_other_options() {
+        let cli = Cli::try_parse_from(["zuuid", "-V", "7", "-U", "-s"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_version_lowercase_v_with_other_options() {
+        let cli = Cli::try_parse_from(["zuuid", "-v", "7", "-U", "-s"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_uuid_version_from_str() {
+        assert_eq!("4".parse::<UuidVersion>().unwrap(), UuidVersion::V4);
+        assert_eq!("7".parse::<UuidVersion>().unwrap(), UuidVersion::V7);
+        assert_eq!("v4".parse::<UuidVersion>().unwrap(), UuidVersion::V4);
+        assert_eq!("v7".parse::<UuidVersion>().unwrap(), UuidVersion::V7);
+        assert_eq!("V4".parse::<UuidVersion>().unwrap(), UuidVersion::V4);
+        assert_eq!("V7".parse::<UuidVersion>().unwrap(), UuidVersion::V7);
+        assert!("6".parse::<UuidVersion>().is_err());
+        assert!("invalid".parse::<UuidVersion>().is_err());
+    }
+
+    #[test]
+    fn test_language_selection_and_exact_messages() {
+        assert_eq!(Language::parse("zh_CN.UTF-8"), Some(Language::Chinese));
+        assert_eq!(Language::parse("EN"), Some(Language::English));
+        assert_eq!(Language::parse("fr"), None);
+        // --lang wins over the environment, and the last one given counts
+        assert_eq!(Language::resolve(&to_args(&["zuuid", "--lang", "zh"])), Language::Chinese);
+        assert_eq!(Language::resolve(&to_args(&["zuuid", "--lang=zh", "-n", "2", "--lang=en"])), Language::English);
+        assert_eq!(Cli::try_parse_from(["zuuid", "--lang", "zh"]).unwrap().lang, Some(Language::Chinese));
+        assert!(Cli::try_parse_from(["zuuid", "--lang", "fr"]).is_err());
+
+        let err = "6".parse::<UuidVersion>().unwrap_err();
+        assert_eq!(err, InvalidVersion("6".to_string()));
+        // Unit tests never call Messages::init, so the shared messages behind Display stay English
+        assert_eq!(err.to_string(), Messages::new(Language::English).invalid_version("6"));
+        // Everything run reports comes from the messages it is handed
+        let chinese = Messages::new(Language::Chinese);
+        let cli = Cli::try_parse_from(["zuuid", "-n", "0"]).unwrap();
+        let err = run(&cli, false, false, &mut Vec::new(), &chinese).unwrap_err();
+        assert_eq!(err.message(), chinese.count_zero());
+        assert_eq!(
+            Messages::new(Language::Chinese).invalid_version("6"),
+            "无效的 UUID 版本：6。有效值：2、3、4、5、7，也可写作 v7、uuid7、uuidv7，或 dce（2）、random（4）、time（7）"
+        );
+        assert_eq!(Messages::new(Language::English).invalid_language("fr"), "Invalid language: fr. Valid values: en, zh");
+        assert_eq!(Messages::new(Language::Chinese).conflict_warning(), "同时指定了 -f（完整）和 -s（简单）格式标志。");
+    }
+
+    #[test]
+    fn test_uuid_version_aliases() {
+        let aliases = [
+            ("uuid3", UuidVersion::V3),
+            ("uuidv3", UuidVersion::V3),
+            ("uuid4", UuidVersion::V4),
+            ("UUIDv4", UuidVersion::V4),
+            ("random", UuidVersion::V4),
+            ("uuid5", UuidVersion::V5),
+            ("uuidv5", UuidVersion::V5),
+            ("uuid7", UuidVersion::V7),
+            ("uuidv7", UuidVersion::V7),
+            ("Time", UuidVersion::V7),
+            ("timestamp", UuidVersion::V7),
+        ];
+        for (alias, version) in aliases {
+            assert_eq!(alias.parse::<UuidVersion>().unwrap(), version, "{}", alias);
+            assert_eq!(Cli::try_parse_from(["zuuid", "-V", alias]).unwrap().version, version, "{}", alias);
+        }
+        assert!("uuid6".parse::<UuidVersion>().is_err());
+        assert!("uuid".parse::<UuidVersion>().is_err());
+        assert!(Messages::new(Language::English).invalid_version("x").contains("random (4) and time (7)"));
+    }
+
+    #[test]
+    fn test_cli_parse_combined_flags_us() {
+        let cli = Cli::try_parse_from(["zuuid", "-us"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_flags_u_upper_s() {
+        let cli = Cli::try_parse_from(["zuuid", "-uS"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_flags_upper_u_s() {
+        let cli = Cli::try_parse_from(["zuuid", "-Us"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_flags_upper_u_upper_s() {
+        let cli = Cli::try_parse_from(["zuuid", "-US"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_with_version() {
+        let cli = Cli::try_parse_from(["zuuid", "-V7", "-us"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_all_flags() {
+        let cli = Cli::try_parse_from(["zuuid", "-V7", "-U", "-s"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+    }
+
+    #[test]
+    fn test_generate_full_format() {
+        let uuid = generate_uuid(UuidVersion::V4, FormatOptions::from_flags(false, false, true, true));
+        // Full format: lowercase with hyphens (36 chars)
+        assert!(uuid.len() == 36);
+        assert!(uuid.contains('-'));
+        assert!(uuid.chars().filter(|&c| c == '-').count() == 4);
+    }
+
+    #[test]
+    fn test_generate_full_uppercase_format() {
+        let uuid = generate_uuid(UuidVersion::V4, FormatOptions::from_flags(true, false, true, true));
+        // Full uppercase format
+        assert!(uuid.len() == 36);
+        assert!(uuid.contains('-'));
+        assert!(!uuid.chars().any(|c| c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_conflict_simple_wins() {
+        let uuid = generate_uuid(UuidVersion::V4, FormatOptions::from_flags(false, true, true, false));
+        // When prefer_full is false, simple wins
+        assert!(uuid.len() == 32);
+        assert!(!uuid.contains('-'));
+    }
+
+    #[test]
+    fn test_conflict_full_wins() {
+        let uuid = generate_uuid(UuidVersion::V4, FormatOptions::from_flags(false, true, true, true));
+        // When prefer_full is true, full wins
+        assert!(uuid.len() == 36);
+        assert!(uuid.contains('-'));
+    }
+
+    #[test]
+    fn test_format_options_from_cli() {
+        let options = |args: &[&str]| {
+            let args = to_args(args);
+            let (prefer_full, _) = determine_format_precedence(&args);
+            FormatOptions::from_cli(&Cli::try_parse_from(&args).unwrap(), prefer_full)
+        };
+        assert_eq!(options(&["zuuid"]), FormatOptions::default());
+        assert_eq!(options(&["zuuid", "-U", "-s"]), UPPER_SIMPLE);
+        assert_eq!(options(&["zuuid", "-u"]), UPPER);
+        // With both -f and -s, the first one given wins
+        assert_eq!(options(&["zuuid", "-f", "-s"]), FormatOptions::default());
+        assert_eq!(options(&["zuuid", "-s", "-f"]), SIMPLE);
+        assert_eq!(options(&["zuuid", "-sfU"]), UPPER_SIMPLE);
+
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        assert_eq!(TextFormat::Full.render(&id), "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6");
+        assert_eq!(TextFormat::Simple.render(&id), "018f3a2e1b2c7d3e8f40a1b2c3d4e5f6");
+        assert_eq!(TextFormat::Base64.render(&id), "AY86LhssfT6PQKGyw9Tl9g");
+        assert_eq!(format_uuid(&id, &UPPER), "018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6");
+        assert_eq!(format_uuid(&id, &SIMPLE), "018f3a2e1b2c7d3e8f40a1b2c3d4e5f6");
+    }
+
+    #[test]
+    fn test_cli_parse_full_short() {
+        let cli = Cli::try_parse_from(["zuuid", "-f"]).unwrap();
+        assert!(cli.full);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_full_short_upper() {
+        let cli = Cli::try_parse_from(["zuuid", "-F"]).unwrap();
+        assert!(cli.full);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_full_long() {
+        let cli = Cli::try_parse_from(["zuuid", "--full"]).unwrap();
+        assert!(cli.full);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_cli_parse_full_with_uppercase() {
+        let cli = Cli::try_parse_from(["zuuid", "-f", "-U"]).unwrap();
+        assert!(cli.full);
+        assert!(cli.uppercase);
+    }
+
+    #[test]
+    fn test_cli_parse_combined_full_flags() {
+        let cli = Cli::try_parse_from(["zuuid", "-fU"]).unwrap();
+        assert!(cli.full);
+        assert!(cli.uppercase);
+    }
+
+    #[test]
+    fn test_cli_parse_full_uppercase_u_with_full() {
+        let cli = Cli::try_parse_from(["zuuid", "-Uf"]).unwrap();
+        assert!(cli.full);
+        assert!(cli.uppercase);
+    }
+
+    #[test]
+    fn test_cli_parse_default_count() {
+        let cli = Cli::try_parse_from(["zuuid"]).unwrap();
+        assert_eq!(cli.count, 1);
+    }
+
+    #[test]
+    fn test_cli_parse_count_short() {
+        let cli = Cli::try_parse_from(["zuuid", "-n", "5"]).unwrap();
+        assert_eq!(cli.count, 5);
+    }
+
+    #[test]
+    fn test_cli_parse_count_long() {
+        let cli = Cli::try_parse_from(["zuuid", "--count", "10"]).unwrap();
+        assert_eq!(cli.count, 10);
+    }
+
+    #[test]
+    fn test_cli_parse_count_with_other_options() {
+        let cli = Cli::try_parse_from(["zuuid", "-n", "3", "-U", "-s", "-V", "7"]).unwrap();
+        assert_eq!(cli.count, 3);
+        assert!(cli.uppercase);
+        assert!(cli.simple);
+        assert_eq!(cli.version, UuidVersion::V7);
+    }
+
+    #[test]
+    fn test_numeric_options_accept_every_spelling() {
+        let parse = |args: &[&str]| Cli::try_parse_from(std::iter::once("zuuid").chain(args.iter().copied())).map_err(|e| e.kind());
+        for spelling in [&["-n5"][..], &["-n", "5"], &["-n=5"], &["--count", "5"], &["--count=5"]] {
+            assert_eq!(parse(spelling).ok().map(|cli| cli.count), Some(5), "{:?}", spelling);
+        }
+        for spelling in [&["-j3"][..], &["-j", "3"], &["-j=3"], &["--jobs", "3"], &["--jobs=3"]] {
+            assert_eq!(parse(spelling).ok().map(|cli| cli.jobs), Some(3), "{:?}", spelling);
+        }
+        for spelling in [&["--timestamp-ms", "42"][..], &["--timestamp-ms=42"]] {
+            assert_eq!(parse(spelling).ok().and_then(|cli| cli.timestamp_ms), Some(42), "{:?}", spelling);
+        }
+        for spelling in [&["-V7"][..], &["-V", "7"], &["-V=7"], &["--uuid-version=7"]] {
+            assert_eq!(parse(spelling).ok().map(|cli| cli.version), Some(UuidVersion::V7), "{:?}", spelling);
+        }
+
+        // A negative number is the option's value, rejected by its parser rather than read as an unknown flag
+        for spelling in [&["-n", "-1"][..], &["-n-1"], &["--count=-1"], &["-j", "-2"], &["--timestamp-ms", "-5"]] {
+            assert_eq!(parse(spelling).err(), Some(ErrorKind::ValueValidation), "{:?}", spelling);
+        }
+        let err = Cli::try_parse_from(["zuuid", "-n", "-1"]).err().unwrap().to_string();
+        assert!(err.contains(&Messages::new(Language::English).invalid_count("-1")), "{}", err);
+        assert!(matches!(
+            Cli::try_parse_from(["zuuid", "sample", "--stdin", "-n", "-1"]).err().map(|e| e.kind()),
+            Some(ErrorKind::ValueValidation)
+        ));
+    }
+
+    fn numbered_input(n: usize) -> std::io::Cursor<String> {
+        let text: String = (0..n).map(|i| format!("{}\n", i)).collect();
+        std::io::Cursor::new(text)
+    }
+
+    #[test]
+    fn test_cli_parse_sample() {
+        let cli = Cli::try_parse_from(["zuuid", "sample", "--stdin", "-n", "100", "--seed", "42"]).unwrap();
+        match cli.command {
+            Some(Command::Sample(args)) => {
+                assert!(args.stdin);
+                assert_eq!(args.count, 100);
+                assert_eq!(args.seed, Some(42));
+            }
+            _ => panic!("expected sample subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_shuffle_defaults() {
+        let cli = Cli::try_parse_from(["zuuid", "shuffle", "--stdin"]).unwrap();
+        match cli.command {
+            Some(Command::Shuffle(args)) => {
+                assert!(args.stdin);
+                assert_eq!(args.max_lines, DEFAULT_SHUFFLE_MAX_LINES);
+                assert_eq!(args.seed, None);
+            }
+            _ => panic!("expected shuffle subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_sample_seeded_is_deterministic() {
+        let first = sample_lines(numbered_input(1000), 10, &mut stream_rng(Some(7)), &ENGLISH).unwrap();
+        let second = sample_lines(numbered_input(1000), 10, &mut stream_rng(Some(7)), &ENGLISH).unwrap();
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, second);
+        // Every sampled line comes from the input
+        assert!(first.iter().all(|line| line.parse::<usize>().unwrap() < 1000));
+    }
+
+    #[test]
+    fn test_sample_fewer_lines_than_count() {
+        let sample = sample_lines(numbered_input(3), 10, &mut stream_rng(Some(1)), &ENGLISH).unwrap();
+        assert_eq!(sample, vec!["0", "1", "2"]);
+    }
+
+    #[test]
+    fn test_sample_is_uniform() {
+        // Each of 200 lines should be picked with probability 20/200 per trial
+        let trials = 2000;
+        let mut counts = vec![0usize; 200];
+        for seed in 0..trials {
+            for line in sample_lines(numbered_input(200), 20, &mut stream_rng(Some(seed)), &ENGLISH).unwrap() {
+                counts[line.parse::<usize>().unwrap()] += 1;
+            }
+        }
+        // Expected 200 hits per line, standard deviation about 13.4
+        for (line, &count) in counts.iter().enumerate() {
+            assert!((130..=270).contains(&count), "line {} picked {} times", line, count);
+        }
+    }
+
+    #[test]
+    fn test_shuffle_seeded_is_deterministic_permutation() {
+        let first = shuffle_lines(numbered_input(100), 1000, &mut stream_rng(Some(3)), &ENGLISH).unwrap();
+        let second = shuffle_lines(numbered_input(100), 1000, &mut stream_rng(Some(3)), &ENGLISH).unwrap();
+        assert_eq!(first, second);
+
+        let mut sorted: Vec<usize> = first.iter().map(|line| line.parse().unwrap()).collect();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_shuffle_max_lines_exceeded() {
+        assert!(shuffle_lines(numbered_input(10), 10, &mut stream_rng(Some(0)), &ENGLISH).is_ok());
+        assert!(shuffle_lines(numbered_input(11), 10, &mut stream_rng(Some(0)), &ENGLISH).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_sort_and_seed() {
+        let cli = Cli::try_parse_from(["zuuid", "--sort", "--seed", "9", "-n", "3"]).unwrap();
+        assert!(cli.sort);
+        assert_eq!(cli.seed, Some(9));
+        assert_eq!(cli.count, 3);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_seeded_v4_is_deterministic() {
+        let first = new_uuid(UuidVersion::V4, V7Precision::Millis, &mut StdRng::seed_from_u64(5), &ENGLISH).unwrap();
+        let second = new_uuid(UuidVersion::V4, V7Precision::Millis, &mut StdRng::seed_from_u64(5), &ENGLISH).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_seeded_v7_keeps_version() {
+        let id = new_uuid(UuidVersion::V7, V7Precision::Millis, &mut StdRng::seed_from_u64(5), &ENGLISH).unwrap();
+        assert_eq!(id.get_version_num(), 7);
+    }
+
+    #[test]
+    fn test_generate_sorted_ascending() {
+        for version in [UuidVersion::V4, UuidVersion::V7] {
+            let lines: Vec<String> = generate_sorted(version, V7Precision::Millis, 50, &mut OsEntropy, &ENGLISH)
+                .unwrap()
+                .into_iter()
+                .map(|id| format_uuid(&id, &UPPER_SIMPLE))
+                .collect();
+            // Line count is preserved and the formatted output is ascending
+            assert_eq!(lines.len(), 50);
+            assert!(lines.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+
+    #[test]
+    fn test_generate_sorted_seeded_is_stable() {
+        let first = generate_sorted(UuidVersion::V4, V7Precision::Millis, 10, &mut StdRng::seed_from_u64(1), &ENGLISH).unwrap();
+        let second = generate_sorted(UuidVersion::V4, V7Precision::Millis, 10, &mut StdRng::seed_from_u64(1), &ENGLISH).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_cli_parse_per_line() {
+        let cli = Cli::try_parse_from(["zuuid", "--per-line", "-V", "7"]).unwrap();
+        assert!(cli.per_line);
+        assert_eq!(cli.version, UuidVersion::V7);
+        assert!(Cli::try_parse_from(["zuuid", "--per-line", "-n", "3"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--per-line", "--sort"]).is_err());
+    }
+
+    #[test]
+    fn test_per_line_emits_one_uuid_per_line() {
+        let input = std::io::Cursor::new("first\nsecond\n\nlast without newline");
+        let mut out = Vec::new();
+        let emitted =
+            emit_per_line(input, &mut out, "\n", |_, _| Ok(generate_uuid(UuidVersion::V7, FormatOptions::default())), &ENGLISH).unwrap();
+        assert_eq!(emitted, 4);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|line| Uuid::parse_str(line).is_ok()));
+    }
+
+    #[test]
+    fn test_per_line_empty_input() {
+        let mut out = Vec::new();
+        let emitted = emit_per_line(std::io::empty(), &mut out, "\n", |_, _| unreachable!(), &ENGLISH).unwrap();
+        assert_eq!(emitted, 0);
+        assert!(out.is_empty());
+    }
+
+    /// Writer that fails with a broken pipe, like stdout after `head` exits
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_versions_from_stdin_drives_each_line() {
+        let next = |number, line: &[u8]| {
+            let version = line_version(number, line, &ENGLISH)?;
+            Ok(generate_uuid(version, FormatOptions::default()))
+        };
+        let mut out = Vec::new();
+        let emitted = emit_per_line(&b"v4\nv7\n 7 \nuuidv4\n"[..], &mut out, "\n", next, &ENGLISH).unwrap();
+        assert_eq!(emitted, 4);
+        let versions: Vec<usize> =
+            String::from_utf8(out).unwrap().lines().map(|line| Uuid::parse_str(line).unwrap().get_version_num()).collect();
+        assert_eq!(versions, [4, 7, 7, 4]);
+
+        // Lines before the bad one are already written; the error names its line number
+        let mut out = Vec::new();
+        let Err(AppError::Invalid(message)) = emit_per_line(&b"v7\nv5\n"[..], &mut out, "\n", next, &ENGLISH) else {
+            panic!("expected an invalid line error");
+        };
+        assert_eq!(message, Messages::new(Language::English).invalid_version_line(2, "v5"));
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 1);
+        assert!(matches!(line_version(1, b"\n", &ENGLISH), Err(AppError::Invalid(_))));
+
+        assert!(Cli::try_parse_from(["zuuid", "--versions-from-stdin"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--per-line", "--versions-from-stdin", "-V", "7"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--per-line", "--versions-from-stdin"]).unwrap().versions_from_stdin);
+    }
+
+    #[test]
+    fn test_per_line_stops_on_broken_pipe() {
+        let input = std::io::Cursor::new("a\nb\n");
+        let emitted = emit_per_line(input, &mut ClosedPipe, "\n", |_, _| Ok("x".to_string()), &ENGLISH).unwrap();
+        assert_eq!(emitted, 0);
+    }
+
+    /// Reader fed one chunk at a time through a channel, blocking until the next arrives
+    struct ChannelReader {
+        rx: std::sync::mpsc::Receiver<Vec<u8>>,
+        pending: Vec<u8>,
+    }
+
+    impl std::io::Read for ChannelReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() {
+                match self.rx.recv() {
+                    Ok(chunk) => self.pending = chunk,
+                    Err(_) => return Ok(0), // Sender dropped: end of input
+                }
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    /// Writer that forwards everything written so far on each flush
+    struct ChannelWriter {
+        tx: std::sync::mpsc::Sender<String>,
+        buffer: Vec<u8>,
+    }
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.buffer.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            let text = String::from_utf8(std::mem::take(&mut self.buffer)).unwrap();
+            self.tx.send(text).map_err(|_| std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_per_line_emits_immediately() {
+        let (in_tx, in_rx) = std::sync::mpsc::channel();
+        let (out_tx, out_rx) = std::sync::mpsc::channel();
+
+        let worker = std::thread::spawn(move || {
+            let reader = std::io::BufReader::new(ChannelReader { rx: in_rx, pending: Vec::new() });
+            let mut writer = ChannelWriter { tx: out_tx, buffer: Vec::new() };
+            emit_per_line(reader, &mut writer, "\n", |_, _| Ok(generate_uuid(UuidVersion::V4, FormatOptions::default())), &ENGLISH)
+        });
+
+        // Each UUID must arrive before the next input line is sent
+        for event in ["event 1\n", "event 2\n", "event 3\n"] {
+            in_tx.send(event.as_bytes().to_vec()).unwrap();
+            let line = out_rx.recv_timeout(std::time::Duration::from_secs(5)).expect("UUID was not emitted immediately");
+            assert!(Uuid::parse_str(line.trim_end()).is_ok());
+        }
+
+        drop(in_tx);
+        assert_eq!(worker.join().unwrap().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_uuid_version_name_based_from_str() {
+        assert_eq!("3".parse::<UuidVersion>().unwrap(), UuidVersion::V3);
+        assert_eq!("v5".parse::<UuidVersion>().unwrap(), UuidVersion::V5);
+        assert!(UuidVersion::V3.is_name_based());
+        assert!(UuidVersion::V5.is_name_based());
+        assert!(!UuidVersion::V4.is_name_based());
+        assert!(!UuidVersion::V7.is_name_based());
+    }
+
+    #[test]
+    fn test_parse_namespace() {
+        assert_eq!(parse_namespace("dns").unwrap(), Uuid::NAMESPACE_DNS);
+        assert_eq!(parse_namespace("URL").unwrap(), Uuid::NAMESPACE_URL);
+        assert_eq!(parse_namespace("oid").unwrap(), Uuid::NAMESPACE_OID);
+        assert_eq!(parse_namespace("x500").unwrap(), Uuid::NAMESPACE_X500);
+        assert_eq!(parse_namespace("6ba7b810-9dad-11d1-80b4-00c04fd430c8").unwrap(), Uuid::NAMESPACE_DNS);
+        assert!(parse_namespace("example").is_err());
+    }
+
+    #[test]
+    fn test_well_known_uuids_match_published_forms() {
+        let published = [
+            ("dns", "6ba7b810-9dad-11d1-80b4-00c04fd430c8"),
+            ("url", "6ba7b811-9dad-11d1-80b4-00c04fd430c8"),
+            ("oid", "6ba7b812-9dad-11d1-80b4-00c04fd430c8"),
+            ("x500", "6ba7b814-9dad-11d1-80b4-00c04fd430c8"),
+            ("nil", "00000000-0000-0000-0000-000000000000"),
+            ("max", "ffffffff-ffff-ffff-ffff-ffffffffffff"),
+            ("bluetooth", "00000000-0000-1000-8000-00805f9b34fb"),
+        ];
+        assert_eq!(WELL_KNOWN_UUIDS.len(), published.len());
+        for ((name, id), (published_name, text)) in WELL_KNOWN_UUIDS.iter().zip(published) {
+            assert_eq!(*name, published_name);
+            assert_eq!(id.to_string(), text, "{}", name);
+            assert_eq!(lookup(&name.to_uppercase()), Some(*id));
+        }
+        assert_eq!(lookup("example"), None);
+    }
+
+    #[test]
+    fn test_namespaces_lists_well_known() {
+        let output = run_to_string(&["zuuid", "namespaces"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), WELL_KNOWN_UUIDS.len());
+        for (line, (_, id)) in lines.iter().zip(WELL_KNOWN_UUIDS) {
+            assert!(line.contains(&id.to_string()), "{}", line);
+            // Every listed name is accepted back by --namespace
+            assert_eq!(parse_namespace(line.split_whitespace().next().unwrap()).unwrap(), id);
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&run_to_string(&["zuuid", "namespaces", "--json"]).unwrap()).unwrap();
+        assert_eq!(json[3]["name"], "x500");
+        assert_eq!(json[3]["uuid"], "6ba7b814-9dad-11d1-80b4-00c04fd430c8");
+        assert_eq!(json[0]["description"], Messages::new(Language::English).namespace_description("dns"));
+    }
+
+    #[test]
+    fn test_cli_parse_v5_names() {
+        let cli = Cli::try_parse_from(["zuuid", "-V", "5", "--namespace", "dns", "--name", "a.com", "--name", "b.com"]).unwrap();
+        assert_eq!(cli.version, UuidVersion::V5);
+        assert_eq!(cli.namespace, Some(Uuid::NAMESPACE_DNS));
+        assert_eq!(cli.names, vec!["a.com", "b.com"]);
+        assert!(Cli::try_parse_from(["zuuid", "--warn-dup-names", "--unique-names"]).is_err());
+    }
+
+    #[test]
+    fn test_name_based_uuid_known_values() {
+        // Reference values from RFC 9562 appendix A
+        let v3 = name_based_uuid(UuidVersion::V3, &Uuid::NAMESPACE_DNS, "www.example.com");
+        assert_eq!(v3.to_string(), "5df41881-3aed-3515-88a7-2f4a814cf09e");
+        let v5 = name_based_uuid(UuidVersion::V5, &Uuid::NAMESPACE_DNS, "www.example.com");
+        assert_eq!(v5.to_string(), "2ed6657d-e927-568b-95e1-2665a8aea6a2");
+    }
+
+    #[test]
+    fn test_duplicate_names_detected() {
+        let names: Vec<String> = ["a", "b", "a", "c", "a", "b"].iter().map(|s| s.to_string()).collect();
+        // Each repeated name is reported once
+        assert_eq!(duplicate_names(&names), vec!["a", "b"]);
+        // Identical names really do produce identical UUIDs
+        let first = name_based_uuid(UuidVersion::V5, &Uuid::NAMESPACE_DNS, &names[0]);
+        let repeat = name_based_uuid(UuidVersion::V5, &Uuid::NAMESPACE_DNS, &names[2]);
+        assert_eq!(first, repeat);
+    }
+
+    #[test]
+    fn test_unique_names_skips_duplicates() {
+        let names: Vec<String> = ["a", "b", "a", "c", "b"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(unique_names(&names), vec!["a", "b", "c"]);
+        assert!(duplicate_names(&["x".to_string(), "y".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_name_warning_localized() {
+        assert!(Messages::new(Language::English).duplicate_name("a.com").contains("\"a.com\""));
+        assert!(Messages::new(Language::Chinese).duplicate_name("a.com").contains("“a.com”"));
+    }
+
+    #[test]
+    fn test_parse_vanity_prefix() {
+        assert_eq!(parse_vanity_prefix("CaFe").unwrap(), "cafe");
+        assert_eq!(parse_vanity_prefix("12345678").unwrap(), "12345678");
+        assert!(parse_vanity_prefix("").is_err());
+        assert!(parse_vanity_prefix("123456789").is_err());
+        assert!(parse_vanity_prefix("beer").is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_vanity() {
+        let cli = Cli::try_parse_from(["zuuid", "--vanity", "dead", "-j", "4", "-U"]).unwrap();
+        assert_eq!(cli.vanity.as_deref(), Some("dead"));
+        assert_eq!(cli.jobs, 4);
+        assert!(Cli::try_parse_from(["zuuid", "--vanity", "dead", "-n", "2"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--vanity", "dead", "--seed", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_vanity_search_single_job() {
+        let id = vanity_search("c", 1, |_, _| {});
+        assert!(id.as_simple().to_string().starts_with('c'));
+        assert_eq!(id.get_version_num(), 4);
+        assert_eq!(id.get_variant(), uuid::Variant::RFC4122);
+    }
+
+    #[test]
+    fn test_vanity_search_multiple_jobs() {
+        let prefix = parse_vanity_prefix("Ab").unwrap();
+        let id = vanity_search(&prefix, 4, |_, _| {});
+        // The match is case-insensitive and still formats normally
+        assert!(format_uuid(&id, &UPPER_SIMPLE).starts_with("AB"));
+        assert_eq!(id.get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_slow_vanity_search_warning() {
+        let msg = Messages::new(Language::English).slow_vanity_search("1234567");
+        assert!(msg.contains("268435456"));
+    }
+
+    #[test]
+    fn test_parse_short_length() {
+        assert_eq!(parse_short_length("1").unwrap(), 1);
+        assert_eq!(parse_short_length("32").unwrap(), 32);
+        assert!(parse_short_length("0").is_err());
+        assert!(parse_short_length("33").is_err());
+        assert!(parse_short_length("eight").is_err());
+    }
+
+    #[test]
+    fn test_short_id_length_and_hex() {
+        for len in [1, 8, 12, 32] {
+            let short = short_id(&Uuid::new_v4(), len, Case::Lower, Encoding::Hex);
+            assert_eq!(short.len(), len);
+            assert!(short.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    fn test_short_id_takes_trailing_digits() {
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        assert_eq!(short_id(&id, 8, Case::Lower, Encoding::Hex), "c3d4e5f6");
+        assert_eq!(short_id(&id, 8, Case::Upper, Encoding::Hex), "C3D4E5F6");
+        assert_eq!(short_id(&id, 32, Case::Lower, Encoding::Hex), "018f3a2e1b2c7d3e8f40a1b2c3d4e5f6");
+    }
+
+    #[test]
+    fn test_short_over_uuid25() {
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        let full = encode_uuid25(&id, Case::Lower);
+        assert_eq!(short_id(&id, 6, Case::Lower, Encoding::Uuid25), full[19..]);
+        assert_eq!(short_id(&id, 25, Case::Upper, Encoding::Uuid25), full.to_uppercase());
+
+        let output = run_to_string(&["zuuid", "--encoding", "uuid25", "--short", "25", "-n", "2"]).unwrap();
+        assert!(output.lines().all(|line| decode_uuid25(line).is_some()));
+        let err = run_to_string(&["zuuid", "--encoding", "uuid25", "--short", "26"]).unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.message(), Messages::new(Language::English).short_too_long(26, Encoding::Uuid25, 25));
+        assert_eq!(run_to_string(&["zuuid", "--encoding", "oid", "--short", "4"]).unwrap_err().exit_code(), 2);
+
+        // base32 is 26 characters and base64 22, whole or cut down
+        let base32 = encode_rfc4648(&id, BASE32_ALPHABET, 5);
+        let base64 = encode_rfc4648(&id, BASE64_ALPHABET, 6);
+        assert_eq!(short_id(&id, 10, Case::Lower, Encoding::Base32), base32[16..]);
+        assert_eq!(short_id(&id, 26, Case::Lower, Encoding::Base32), base32);
+        assert_eq!(short_id(&id, 10, Case::Upper, Encoding::Base64), base64[12..]);
+        assert_eq!(short_id(&id, 22, Case::Lower, Encoding::Base64), base64);
+        assert_eq!(Encoding::Base32.text_length(), Some(26));
+        assert_eq!(Encoding::Base64Url.text_length(), Some(22));
+        let output = run_to_string(&["zuuid", "--encoding", "base32", "--short", "26", "-n", "2"]).unwrap();
+        assert!(output.lines().all(|line| decode_rfc4648(line, BASE32_ALPHABET, 5).is_some()));
+        assert!(run_to_string(&["zuuid", "--encoding", "base64", "--short", "22"]).unwrap().trim_end().len() == 22);
+        for (encoding, max) in [("base32", 26), ("base64", 22), ("base64url", 22)] {
+            let err = run_to_string(&["zuuid", "--encoding", encoding, "--short", &(max + 1).to_string()]).unwrap_err();
+            assert_eq!(err.exit_code(), 4);
+            let encoding: Encoding = ValueEnum::from_str(encoding, false).unwrap();
+            assert_eq!(err.message(), Messages::new(Language::English).short_too_long(max + 1, encoding, max));
+        }
+        assert_eq!(run_to_string(&["zuuid", "--short", "32"]).unwrap().trim_end().len(), 32);
+    }
+
+    #[test]
+    fn test_cli_parse_short() {
+        let cli = Cli::try_parse_from(["zuuid", "--short", "8", "-n", "3"]).unwrap();
+        assert_eq!(cli.short, Some(8));
+        assert!(Cli::try_parse_from(["zuuid", "--short", "0"]).is_err());
+    }
+
+    /// Fresh path under the system temp directory, unique to this test process
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("zuuid-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_format_rfc3339_millis() {
+        assert_eq!(format_rfc3339_millis(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_rfc3339_millis(1_704_067_200_123), "2024-01-01T00:00:00.123Z");
+        // Leap day and end-of-day rollover
+        assert_eq!(format_rfc3339_millis(1_709_251_199_999), "2024-02-29T23:59:59.999Z");
+        assert_eq!(format_rfc3339_millis(951_782_400_000), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_audit_line_format() {
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        let line = audit_line(1_704_067_200_123, &id, UuidVersion::V7, "alice");
+        assert_eq!(line, "2024-01-01T00:00:00.123Z\t018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6\tv7\talice\n");
+    }
+
+    #[test]
+    fn test_audit_log_appends_across_runs() {
+        let path = temp_path("audit-append.log");
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+
+        AuditLog::open(&path, false, &ENGLISH).unwrap().record(&first, UuidVersion::V4).unwrap();
+        AuditLog::open(&path, false, &ENGLISH).unwrap().record(&second, UuidVersion::V4).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, id) in lines.iter().zip([first, second]) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            assert_eq!(fields.len(), 4);
+            assert!(fields[0].ends_with('Z'));
+            assert_eq!(fields[1], id.to_string());
+            assert_eq!(fields[2], "v4");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_fails_closed() {
+        // A regular file as the parent directory makes the log unwritable, even for root
+        let parent = temp_path("audit-not-a-dir");
+        std::fs::write(&parent, "").unwrap();
+        let path = parent.join("audit.log");
+
+        assert!(AuditLog::open(&path, false, &ENGLISH).is_err());
+
+        // Best-effort mode keeps going without a log
+        let mut log = AuditLog::open(&path, true, &ENGLISH).unwrap();
+        assert!(log.record(&Uuid::new_v4(), UuidVersion::V4).is_ok());
+        std::fs::remove_file(&parent).unwrap();
+    }
+
+    #[test]
+    fn test_cli_parse_audit_log() {
+        let cli = Cli::try_parse_from(["zuuid", "--audit-log", "ids.log", "--audit-best-effort"]).unwrap();
+        assert_eq!(cli.audit_log, Some(std::path::PathBuf::from("ids.log")));
+        assert!(cli.audit_best_effort);
+        assert!(Cli::try_parse_from(["zuuid", "--audit-best-effort"]).is_err());
+    }
+
+    /// Check the shape YYYY-MM-DDTHH:MM:SS.mmmZ, the same as the regex
+    /// ^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d{3}Z$
+    fn is_iso8601_millis(s: &str) -> bool {
+        let pattern = "dddd-dd-ddTdd:dd:dd.dddZ";
+        s.len() == pattern.len()
+            && s.chars().zip(pattern.chars()).all(|(c, p)| match p {
+                'd' => c.is_ascii_digit(),
+                _ => c == p,
+            })
+    }
+
+    #[test]
+    fn test_with_timestamp_prefix() {
+        let id = Uuid::new_v4().to_string();
+        let line = with_timestamp(unix_millis_now(), &id);
+        let (prefix, rest) = line.split_once(' ').unwrap();
+        assert!(is_iso8601_millis(prefix), "bad timestamp prefix: {}", prefix);
+        assert_eq!(rest, id);
+        assert_eq!(with_timestamp(1_704_067_200_123, "x"), "2024-01-01T00:00:00.123Z x");
+    }
+
+    #[test]
+    fn test_cli_parse_timestamped() {
+        let cli = Cli::try_parse_from(["zuuid", "--timestamped", "-V", "7"]).unwrap();
+        assert!(cli.timestamped);
+    }
+
+    const PROFILE_CONFIG: &str = r#"
+        format = "full"
+
+        [profiles.db]
+        version = "7"
+        format = "simple"
+
+        [profiles.docs]
+        upper = true
+    "#;
+
+    /// Parse arguments and apply the given config text, as `parse_cli` does
+    fn cli_with_config(args: &[&str], config: &str) -> Result<Cli, String> {
+        let matches = Cli::command().try_get_matches_from(args).unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        let settings = Config::parse(config)?.settings(cli.profile.as_deref(), &ENGLISH)?;
+        apply_settings(&mut cli, &matches, settings)?;
+        Ok(cli)
+    }
+
+    #[test]
+    fn test_config_profile_selection() {
+        let settings = Config::parse(PROFILE_CONFIG).unwrap().settings(Some("db"), &ENGLISH).unwrap();
+        assert_eq!(settings.version, Some(UuidVersion::V7));
+        assert_eq!(settings.format, Some(Style::Simple));
+        assert_eq!(settings.upper, None);
+
+        // Profile values are layered over the top-level defaults
+        let settings = Config::parse(PROFILE_CONFIG).unwrap().settings(Some("docs"), &ENGLISH).unwrap();
+        assert_eq!(settings.format, Some(Style::Hyphenated));
+        assert_eq!(settings.upper, Some(true));
+    }
+
+    #[test]
+    fn test_format_options_serde_round_trip() {
+        for version in UuidVersion::value_variants() {
+            let json = serde_json::to_string(version).unwrap();
+            assert_eq!(json, format!("\"{}\"", version));
+            assert_eq!(serde_json::from_str::<UuidVersion>(&json).unwrap(), *version);
+        }
+
+        for encoding in Encoding::value_variants() {
+            let json = serde_json::to_string(encoding).unwrap();
+            assert_eq!(json, format!("\"{}\"", encoding.to_possible_value().unwrap().get_name()));
+            assert_eq!(json, format!("\"{}\"", encoding));
+            assert_eq!(serde_json::from_str::<Encoding>(&json).unwrap(), *encoding);
+        }
+        assert!(serde_json::from_str::<Encoding>("\"base64_url\"").is_err());
+
+        let options = FormatOptions { style: Style::Simple, case: Case::Upper };
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(json, r#"{"style":"simple","case":"upper"}"#);
+        assert_eq!(serde_json::from_str::<FormatOptions>(&json).unwrap(), options);
+        assert_eq!(serde_json::from_str::<FormatOptions>("{}").unwrap(), FormatOptions::default());
+        assert!(serde_json::from_str::<FormatOptions>(r#"{"style":"dotted"}"#).is_err());
+        assert!(serde_json::from_str::<FormatOptions>(r#"{"colour":"red"}"#).is_err());
+    }
+
+    #[test]
+    fn test_config_and_serde_accept_the_same_versions() {
+        // Every spelling clap accepts for -V must mean the same version in the config file and in JSON
+        for version in UuidVersion::value_variants() {
+            let value = version.to_possible_value().unwrap();
+            for name in value.get_name_and_aliases() {
+                let settings = Config::parse(&format!("version = \"{}\"", name)).unwrap().settings(None, &ENGLISH).unwrap();
+                assert_eq!(settings.version, Some(*version), "{}", name);
+                assert_eq!(serde_json::from_str::<UuidVersion>(&format!("\"{}\"", name)).unwrap(), *version, "{}", name);
+            }
+        }
+
+        // Rejections carry the same localized message either way
+        let expected = Messages::new(Language::English).invalid_version("6");
+        assert!(Config::parse("version = \"6\"").unwrap_err().contains(&expected));
+        assert!(serde_json::from_str::<UuidVersion>("\"6\"").unwrap_err().to_string().contains(&expected));
+        assert!(Config::parse("format = \"dotted\"").unwrap_err().contains("Valid values: full, simple"));
+    }
+
+    #[test]
+    fn test_config_unknown_profile_lists_available() {
+        let err = Config::parse(PROFILE_CONFIG).unwrap().settings(Some("prod"), &ENGLISH).unwrap_err();
+        assert!(err.contains("prod"));
+        assert!(err.contains("db, docs"));
+
+        let err = Config::default().settings(Some("db"), &ENGLISH).unwrap_err();
+        assert!(err.contains("No profiles"));
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_keys() {
+        assert!(Config::parse("colour = true").is_err());
+        assert!(Config::parse("[profiles.db]\nversoin = \"7\"").is_err());
+    }
+
+    #[test]
+    fn test_profile_applies_without_cli_flags() {
+        let cli = cli_with_config(&["zuuid", "--profile", "db"], PROFILE_CONFIG).unwrap();
+        assert_eq!(cli.version, UuidVersion::V7);
         assert!(cli.simple);
+        assert!(!cli.uppercase);
+
+        let cli = cli_with_config(&["zuuid", "--profile", "docs"], PROFILE_CONFIG).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.uppercase);
+        assert!(cli.full);
+    }
+
+    #[test]
+    fn test_cli_flags_override_profile() {
+        let cli = cli_with_config(&["zuuid", "--profile", "db", "-V", "4", "-f"], PROFILE_CONFIG).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.full);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_config_defaults_without_profile() {
+        let cli = cli_with_config(&["zuuid"], PROFILE_CONFIG).unwrap();
+        assert_eq!(cli.version, UuidVersion::V4);
+        assert!(cli.full);
+        assert!(!cli.simple);
+    }
+
+    #[test]
+    fn test_config_invalid_values() {
+        assert!(cli_with_config(&["zuuid"], "version = \"6\"").is_err());
+        assert!(cli_with_config(&["zuuid"], "format = \"dotted\"").is_err());
+    }
+
+    /// Entropy source that fails a set number of times before delegating to the OS RNG
+    struct FlakyEntropy {
+        failures_left: u32,
+        calls: u32,
+    }
+
+    impl EntropySource for FlakyEntropy {
+        fn fill_random(&mut self, buf: &mut [u8]) -> Result<(), String> {
+            self.calls += 1;
+            if self.failures_left > 0 {
+                self.failures_left -= 1;
+                return Err("entropy source not ready".to_string());
+            }
+            OsEntropy.fill_random(buf)
+        }
+    }
+
+    #[test]
+    fn test_rng_failure_retried() {
+        let mut source = FlakyEntropy { failures_left: 1, calls: 0 };
+        let id = new_uuid(UuidVersion::V4, V7Precision::Millis, &mut source, &ENGLISH).unwrap();
+        assert_eq!(id.get_version_num(), 4);
+        assert_eq!(source.calls, 2);
+    }
+
+    #[test]
+    fn test_rng_failure_gives_up_with_error() {
+        let mut source = FlakyEntropy { failures_left: RNG_ATTEMPTS, calls: 0 };
+        let err = new_uuid(UuidVersion::V7, V7Precision::Millis, &mut source, &ENGLISH).unwrap_err();
+        assert!(err.contains("entropy source not ready"));
+        assert_eq!(source.calls, RNG_ATTEMPTS);
+    }
+
+    /// Entropy source that fills every buffer from a counter, so every UUID differs but its digits are skewed
+    struct CountingEntropy(u128);
+
+    impl EntropySource for CountingEntropy {
+        fn fill_random(&mut self, buf: &mut [u8]) -> Result<(), String> {
+            self.0 = self.0.wrapping_add(1);
+            buf.copy_from_slice(&self.0.to_be_bytes()[16 - buf.len()..]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_doctor_checks_fail_independently() {
+        const NOW: u64 = 1_760_000_000_000;
+        let statuses = |checks: Vec<DoctorCheck>| -> Vec<(&'static str, bool)> {
+            checks.into_iter().map(|check| (check.name, check.ok)).collect()
+        };
+        let all_ok = vec![
+            ("rng", true),
+            ("uniqueness", true),
+            ("distribution", true),
+            ("clock", true),
+            ("locale", true),
+            ("config", true),
+        ];
+        let with = |failing: &[&str]| -> Vec<(&'static str, bool)> {
+            all_ok.iter().map(|&(name, ok)| (name, ok && !failing.contains(&name))).collect()
+        };
+
+        assert_eq!(statuses(doctor_checks(&mut StdRng::seed_from_u64(7), NOW, None, &ENGLISH)), all_ok);
+        let mut unreachable = FlakyEntropy { failures_left: u32::MAX, calls: 0 };
+        assert_eq!(statuses(doctor_checks(&mut unreachable, NOW, None, &ENGLISH)), with(&["rng", "uniqueness", "distribution"]));
+        // The RNG is probed once, with no retries and no sample
+        assert_eq!(unreachable.calls, 1);
+        assert_eq!(statuses(doctor_checks(&mut CountingEntropy(0), NOW, None, &ENGLISH)), with(&["distribution"]));
+        assert_eq!(statuses(doctor_checks(&mut CountingEntropy(u128::MAX - 1), NOW, None, &ENGLISH)), with(&["distribution"]));
+
+        let mut source = StdRng::seed_from_u64(7);
+        assert_eq!(statuses(doctor_checks(&mut source, 1_500_000_000_000, None, &ENGLISH)), with(&["clock"]));
+        assert_eq!(statuses(doctor_checks(&mut source, DOCTOR_CLOCK_RANGE.end, None, &ENGLISH)), with(&["clock"]));
+
+        let path = temp_path("doctor.toml");
+        assert_eq!(statuses(doctor_checks(&mut source, NOW, Some(&path), &ENGLISH)), all_ok);
+        std::fs::write(&path, "version = \"7\"\n").unwrap();
+        assert_eq!(statuses(doctor_checks(&mut source, NOW, Some(&path), &ENGLISH)), all_ok);
+        std::fs::write(&path, "version = [").unwrap();
+        assert_eq!(statuses(doctor_checks(&mut source, NOW, Some(&path), &ENGLISH)), with(&["config"]));
+
+        // The report lists every check and still fails the run
+        let mut out = Vec::new();
+        let Err(AppError::Failure(message)) = doctor(&mut source, NOW, Some(&path), "\n", &mut out, &ENGLISH) else {
+            panic!("expected doctor to fail");
+        };
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(message, Messages::new(Language::English).doctor_failed(1, 6));
+        let report = String::from_utf8(out).unwrap();
+        assert_eq!(report.lines().count(), 6);
+        assert!(report.lines().last().unwrap().starts_with("FAIL  config"), "{}", report);
+    }
+
+    /// Entropy source whose first byte is only ever 0 to 15, the rest from the OS RNG
+    struct LowFirstByteEntropy;
+
+    impl EntropySource for LowFirstByteEntropy {
+        fn fill_random(&mut self, buf: &mut [u8]) -> Result<(), String> {
+            OsEntropy.fill_random(buf)?;
+            buf[0] &= 0x0f;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_distribution_check_flags_biased_rng() {
+        let histogram = |source: &mut dyn EntropySource, count| {
+            let mut summary = BatchSummary::default();
+            for _ in 0..count {
+                summary.add(new_uuid(UuidVersion::V4, V7Precision::Millis, source, &ENGLISH).unwrap());
+            }
+            summary.first_bytes
+        };
+
+        let fair = histogram(&mut StdRng::seed_from_u64(3), 20_000);
+        assert_eq!(fair.total(), 20_000);
+        assert!(fair.chi_square().unwrap() < FIRST_BYTE_CHI_SQUARE_LIMIT);
+        assert!(!distribution_check(&fair, true, &ENGLISH));
+
+        let biased = histogram(&mut LowFirstByteEntropy, 20_000);
+        assert!(biased.chi_square().unwrap() > FIRST_BYTE_CHI_SQUARE_LIMIT);
+        assert!(distribution_check(&biased, true, &ENGLISH));
+
+        // Too few UUIDs are reported, never flagged
+        let small = histogram(&mut LowFirstByteEntropy, FIRST_BYTE_MIN_SAMPLE as usize - 1);
+        assert_eq!(small.chi_square(), None);
+        assert!(!distribution_check(&small, true, &ENGLISH));
+        assert!(Messages::new(Language::English).distribution_summary(&small, None).contains("at least 1280"));
+
+        let err = run_to_string(&["zuuid", "-V", "7", "--distribution-check"]).unwrap_err();
+        assert!(matches!(err, AppError::Usage(_)));
+        assert!(Cli::try_parse_from(["zuuid", "--distribution-check", "--mix", "v4:1,v7:1"]).is_err());
+        assert_eq!(run_to_string(&["zuuid", "-n", "3", "--distribution-check", "-q"]).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_rng_unavailable_localized() {
+        assert!(Messages::new(Language::English).rng_unavailable(4, "x").contains("4 attempts"));
+        assert!(Messages::new(Language::Chinese).rng_unavailable(4, "x").contains("4 次"));
+    }
+
+    #[test]
+    fn test_v7_ordered_within_millisecond() {
+        // The same timestamp and random bits twice still yield increasing UUIDs
+        let millis = unix_millis_now();
+        let first = next_v7(millis, &[0xff; 10]);
+        let second = next_v7(millis, &[0xff; 10]);
+        let third = next_v7(millis, &[0; 10]);
+        assert!(first < second && second < third);
+        for id in [first, second, third] {
+            assert_eq!(id.get_version_num(), 7);
+            assert_eq!(id.get_variant(), uuid::Variant::RFC4122);
+        }
+    }
+
+    #[test]
+    fn test_v7_successor() {
+        let successor = |last: &str, fresh: &str| {
+            let last = Uuid::parse_str(last).unwrap().as_u128();
+            Uuid::from_u128(v7_successor(last, Uuid::parse_str(fresh).unwrap().as_u128())).to_string()
+        };
+        let fresh = "018f3a2e-1b2c-7000-8000-0000deadbeef";
+
+        // Counter bumped, low 32 bits taken from the fresh value
+        assert_eq!(successor("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", fresh), "018f3a2e-1b2c-7d3e-8f40-a1b3deadbeef");
+
+        // Counter overflow from rand_b into rand_a skips the variant bits
+        assert_eq!(successor("018f3a2e-1b2c-7d3e-bfff-ffff00000000", fresh), "018f3a2e-1b2c-7d3f-8000-0000deadbeef");
+
+        // Counter exhausted: move to the next millisecond
+        assert_eq!(successor("018f3a2e-1b2c-7fff-bfff-ffff00000000", fresh), "018f3a2e-1b2d-7000-8000-0000deadbeef");
+    }
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    /// Parse `args` and run generation into a buffer, as `main` does with stdout
+    fn run_to_string(args: &[&str]) -> Result<String, AppError> {
+        let args = to_args(args);
+        let (prefer_full, conflict) = determine_format_precedence(&args);
+        let cli = Cli::try_parse_from(&args).unwrap();
+        let mut out = Vec::new();
+        run(&cli, prefer_full, conflict, &mut out, &ENGLISH)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_resolve_output_style_truth_table() {
+        let table = [
+            (false, false, false, Style::Hyphenated),
+            (false, false, true, Style::Hyphenated),
+            (true, false, false, Style::Simple),
+            (true, false, true, Style::Simple),
+            (false, true, false, Style::Hyphenated),
+            (false, true, true, Style::Hyphenated),
+            (true, true, false, Style::Simple),
+            (true, true, true, Style::Hyphenated),
+        ];
+        for (simple, full, prefer_full, expected) in table {
+            assert_eq!(resolve_output_style(simple, full, prefer_full), expected, "-s={simple} -f={full} prefer_full={prefer_full}");
+        }
+    }
+
+    #[test]
+    fn test_determine_format_precedence() {
+        assert_eq!(determine_format_precedence(&to_args(&["zuuid", "-fs"])), (true, true));
+        assert_eq!(determine_format_precedence(&to_args(&["zuuid", "-s", "-F"])), (false, true));
+        assert_eq!(determine_format_precedence(&to_args(&["zuuid", "--simple", "--full"])), (false, true));
+        // No conflict from a single flag or from long options that merely contain s/f
+        assert_eq!(determine_format_precedence(&to_args(&["zuuid", "-s"])), (true, false));
+        assert_eq!(determine_format_precedence(&to_args(&["zuuid", "-f", "--sort", "--seed", "1"])), (true, false));
+    }
+
+    #[test]
+    fn test_porcelain_stdout_pure_with_conflict() {
+        let output = run_to_string(&["zuuid", "--porcelain", "-f", "-s", "-n", "3"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        // The conflict warning goes to stderr; stdout holds exactly the UUIDs in the winning format
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() == 36 && Uuid::parse_str(line).is_ok()));
+    }
+
+    #[test]
+    fn test_porcelain_stdout_pure_with_duplicate_name_warning() {
+        let args = ["zuuid", "--porcelain", "-V", "5", "--namespace", "dns", "--name", "a", "--name", "a", "--warn-dup-names", "-s"];
+        let output = run_to_string(&args).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() == 32 && Uuid::parse_str(line).is_ok()));
+    }
+
+    #[test]
+    fn test_porcelain_rejects_decorations() {
+        assert!(Cli::try_parse_from(["zuuid", "--porcelain", "--timestamped"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--porcelain", "--short", "8"]).is_err());
+    }
+
+    #[test]
+    fn test_format_diagnostic() {
+        assert_eq!(format_diagnostic("warning", "33", "careful", DiagnosticStyle::Porcelain), "zuuid: warning: careful");
+        assert_eq!(format_diagnostic("error", "31", "failed", DiagnosticStyle::Color), "\x1b[31mfailed\x1b[0m");
+    }
+
+    #[test]
+    fn test_redirected_stderr_gets_no_escapes() {
+        assert_eq!(diagnostic_style(false, true, false), DiagnosticStyle::Color);
+        assert_eq!(diagnostic_style(false, false, false), DiagnosticStyle::Plain);
+        assert_eq!(diagnostic_style(false, true, true), DiagnosticStyle::Plain);
+        assert_eq!(diagnostic_style(true, true, false), DiagnosticStyle::Porcelain);
+
+        let msgs = Messages::new(Language::English);
+        let style = diagnostic_style(false, false, false);
+        for msg in [msgs.conflict_warning(), msgs.using_full(), msgs.using_simple()] {
+            let line = format_warning(WarningCode::FormatConflict, msg, style);
+            assert_eq!(line, format!("zuuid: warning[W001]: {}", msg));
+            assert!(!line.as_bytes().contains(&0x1b));
+        }
+    }
+
+    #[test]
+    fn test_warning_codes() {
+        // The code prefix stays the same in every language and style; only the message is translated
+        let chinese = Messages::new(Language::Chinese);
+        let line = format_warning(WarningCode::FormatConflict, chinese.conflict_warning(), DiagnosticStyle::Porcelain);
+        assert!(line.starts_with("zuuid: warning[W001]: "));
+
+        // The rendered line names the warning once: messages carry no "Warning:" prefix of their own
+        let english = Messages::new(Language::English);
+        assert_eq!(
+            format_warning(WarningCode::FormatConflict, english.conflict_warning(), DiagnosticStyle::Plain),
+            "zuuid: warning[W001]: Both -f (full) and -s (simple) format flags specified."
+        );
+        assert_eq!(line, "zuuid: warning[W001]: 同时指定了 -f（完整）和 -s（简单）格式标志。");
+        assert_eq!(
+            format_warning(WarningCode::DuplicateName, &english.duplicate_name("a.com"), DiagnosticStyle::Plain),
+            "zuuid: warning[W009]: Name \"a.com\" is listed more than once and yields the same UUID."
+        );
+        let colored = format_warning(WarningCode::DuplicateName, "dup", DiagnosticStyle::Color);
+        assert_eq!(colored, "\x1b[33mzuuid: warning[W009]: dup\x1b[0m");
+
+        // The reset follows every message: empty, wide CJK text, or one carrying its own reset
+        for msg in ["", chinese.conflict_warning(), "bad line: \x1b[1mx\x1b[0m y"] {
+            let colored = format_warning(WarningCode::InvalidLine, msg, DiagnosticStyle::Color);
+            assert!(colored.starts_with("\x1b[33mzuuid: warning[W005]: "), "{:?}", colored);
+            assert!(colored.ends_with(&format!("{}\x1b[0m", msg.replace("\x1b[0m", "\x1b[0m\x1b[33m"))), "{:?}", colored);
+        }
+        assert_eq!(format_warning(WarningCode::InvalidLine, "", DiagnosticStyle::Color), "\x1b[33mzuuid: warning[W005]: \x1b[0m");
+
+        // Codes and names are unique, and both look the warning up
+        for (i, code) in WARNING_CODES.into_iter().enumerate() {
+            assert_eq!(code.to_string(), format!("W{:03}", i + 1));
+            assert_eq!(WarningCode::lookup(code.name()), Some(code));
+            assert_eq!(WarningCode::lookup(&code.to_string().to_lowercase()), Some(code));
+        }
+
+        let entries = ["W001", "duplicate-name", " w007 ", "W999", "loud"].map(String::from);
+        let (mask, unknown) = suppressed_warnings(&entries);
+        assert_eq!(mask, WarningCode::FormatConflict.bit() | WarningCode::DuplicateName.bit() | WarningCode::SlowVanity.bit());
+        assert_eq!(unknown, ["W999", "loud"]);
+        assert_eq!(mask & WarningCode::InsecurePhrase.bit(), 0);
+
+        let cli = Cli::try_parse_from(["zuuid", "--no-warn", "W001,W009", "-fs"]).unwrap();
+        assert_eq!(cli.no_warn, ["W001", "W009"]);
+        assert_eq!(suppressed_warnings(&cli.no_warn).0 & WarningCode::FormatConflict.bit(), WarningCode::FormatConflict.bit());
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--no-warn", "W004", "--stats"]).is_ok());
+    }
+
+    #[test]
+    fn test_app_error_exit_codes() {
+        let usage = run_to_string(&["zuuid", "-V", "5", "--name", "a"]).unwrap_err();
+        assert_eq!(usage.exit_code(), 2);
+        assert_eq!(usage.code(), "usage");
+
+        let failure = AppError::from("generation failed".to_string());
+        assert_eq!(failure.exit_code(), 1);
+        assert_eq!(failure.code(), "error");
+        assert_eq!(failure.message(), "generation failed");
+    }
+
+    /// A reader whose every read fails
+    struct BrokenReader;
+
+    impl std::io::Read for BrokenReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("device gone"))
+        }
+    }
+
+    #[test]
+    fn test_error_classes_carry_code_and_localized_message() {
+        let msgs = Messages::new(Language::English);
+
+        // Usage: option combination that cannot work
+        let usage = run_to_string(&["zuuid", "-V", "5", "--name", "a"]).unwrap_err();
+        assert_eq!(usage.exit_code(), 2);
+        assert_eq!(usage.message(), msgs.missing_namespace(UuidVersion::V5));
+
+        // I/O: the input stream fails
+        let io = sample_lines(std::io::BufReader::new(BrokenReader), 3, &mut stream_rng(Some(1)), &ENGLISH).unwrap_err();
+        assert_eq!(io.exit_code(), 74);
+        assert_eq!(io.code(), "io");
+        assert_eq!(io.message(), msgs.read_error(&std::io::Error::other("device gone")));
+
+        let mut out = Vec::new();
+        let io =
+            emit_per_line(std::io::BufReader::new(BrokenReader), &mut out, "\n", |_, _| Ok("x".to_string()), &ENGLISH).unwrap_err();
+        assert_eq!(io.exit_code(), 74);
+
+        // Validation: input over the shuffle limit, or a bad config value
+        let invalid = shuffle_lines(numbered_input(11), 10, &mut stream_rng(Some(0)), &ENGLISH).unwrap_err();
+        assert_eq!(invalid.exit_code(), 4);
+        assert_eq!(invalid.code(), "invalid");
+        assert_eq!(invalid.message(), msgs.too_many_lines(10));
+    }
+
+    #[test]
+    fn test_audit_log_open_failure_is_io_error() {
+        // A regular file cannot be a parent directory, even for root
+        let parent = temp_path("audit-parent-file");
+        std::fs::write(&parent, "").unwrap();
+        let path = parent.join("audit.log");
+
+        let err = run_to_string(&["zuuid", "--audit-log", path.to_str().unwrap()]).unwrap_err();
+        assert_eq!(err.exit_code(), 74);
+        assert!(err.message().contains(path.to_str().unwrap()));
+        std::fs::remove_file(&parent).unwrap();
+    }
+
+    /// Writer that accepts `writes` writes and then fails like a full disk
+    struct FullAfter {
+        writes: usize,
+    }
+
+    impl Write for FullAfter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.writes == 0 {
+                return Err(std::io::ErrorKind::StorageFull.into());
+            }
+            self.writes -= 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_audit_log_failing_partway_prints_nothing() {
+        let cli = Cli::try_parse_from(["zuuid", "-n", "5", "--audit-log", "ids.log"]).unwrap();
+        let log = |best_effort| AuditLog {
+            path: std::path::PathBuf::from("ids.log"),
+            file: Some(Box::new(FullAfter { writes: 2 })),
+            best_effort,
+            user: "test".to_string(),
+            msgs: ENGLISH,
+        };
+        let mut out = Vec::new();
+        let err = generate_logged(&cli, false, false, &mut out, &mut BatchSummary::default(), &mut log(false), &ENGLISH).unwrap_err();
+        assert_eq!(err.exit_code(), 74);
+        assert!(out.is_empty());
+
+        // Best-effort mode stops logging at the failure and still prints the batch
+        generate_logged(&cli, false, false, &mut out, &mut BatchSummary::default(), &mut log(true), &ENGLISH).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 5);
+        assert!(Cli::try_parse_from(["zuuid", "--audit-log", "ids.log", "--per-line"]).is_err());
+    }
+
+    #[test]
+    fn test_c_array_has_sixteen_hex_bytes() {
+        let literal = c_array(&Uuid::new_v4(), Case::Lower, None);
+        assert!(literal.starts_with('{') && literal.ends_with('}'));
+        let tokens: Vec<&str> = literal.trim_matches(|c| c == '{' || c == '}').split(", ").collect();
+        assert_eq!(tokens.len(), 16);
+        assert!(tokens.iter().all(|t| t.len() == 4 && t.starts_with("0x") && t[2..].chars().all(|c| c.is_ascii_hexdigit())));
+    }
+
+    #[test]
+    fn test_c_array_exact_output() {
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        assert_eq!(
+            c_array(&id, Case::Lower, None),
+            "{0x01, 0x8f, 0x3a, 0x2e, 0x1b, 0x2c, 0x7d, 0x3e, 0x8f, 0x40, 0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6}"
+        );
+        assert_eq!(
+            c_array(&id, Case::Upper, Some("device_id")),
+            "const uint8_t device_id[16] = {0x01, 0x8F, 0x3A, 0x2E, 0x1B, 0x2C, 0x7D, 0x3E, 0x8F, 0x40, 0xA1, 0xB2, 0xC3, 0xD4, 0xE5, 0xF6};"
+        );
+    }
+
+    #[test]
+    fn test_upper_keeps_hex_prefixes_lowercase() {
+        // -U reaches the digits of every 0x-prefixed output, never the prefix itself
+        for args in [&["zuuid", "--c-array", "-U", "-n", "20"][..], &["zuuid", "--c-array", "--upper", "--c-array-name", "id"]] {
+            let output = run_to_string(args).unwrap();
+            assert!(output.contains("0x") && !output.contains("0X"), "{}", output);
+            let digits: String = output.split("0x").skip(1).map(|token| &token[..2]).collect();
+            assert!(digits.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()), "{}", digits);
+        }
+    }
+
+    #[test]
+    fn test_parse_c_identifier() {
+        assert_eq!(parse_c_identifier("device_id").unwrap(), "device_id");
+        assert_eq!(parse_c_identifier("_GUID2").unwrap(), "_GUID2");
+        assert!(parse_c_identifier("").is_err());
+        assert!(parse_c_identifier("2fast").is_err());
+        assert!(parse_c_identifier("my-id").is_err());
+        assert!(parse_c_identifier("static").is_err());
+    }
+
+    #[test]
+    fn test_c_array_names_numbered_for_batches() {
+        let output = run_to_string(&["zuuid", "--c-array", "--c-array-name", "key", "-n", "2"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("const uint8_t key_1[16] = {0x"));
+        assert!(lines[1].starts_with("const uint8_t key_2[16] = {0x"));
+
+        let output = run_to_string(&["zuuid", "--c-array", "--c-array-name", "key"]).unwrap();
+        assert!(output.starts_with("const uint8_t key[16] = {0x"));
+        assert!(Cli::try_parse_from(["zuuid", "--c-array-name", "key"]).is_err());
+    }
+
+    #[test]
+    fn test_sub_ms_v7_strictly_ordered_within_one_tick() {
+        let context = uuid::ContextV7::new().with_additional_precision();
+        // Same clock reading every time, as when many UUIDs land in one millisecond
+        let ids: Vec<Uuid> = (0..1000u32)
+            .map(|i| sub_ms_v7(&context, 1_704_067_200, 123_456_789, i.wrapping_mul(0x9e37_79b9)))
+            .collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        for id in &ids {
+            assert_eq!(id.get_version_num(), 7);
+            assert_eq!(id.get_variant(), uuid::Variant::RFC4122);
+            assert!(id.to_string().starts_with("018cc251-f47b-7"));
+        }
+    }
+
+    #[test]
+    fn test_sub_ms_v7_run() {
+        let output = run_to_string(&["zuuid", "-V", "7", "--v7-precision", "sub-ms", "-n", "500"]).unwrap();
+        let ids: Vec<Uuid> = output.lines().map(|line| Uuid::parse_str(line).unwrap()).collect();
+        assert_eq!(ids.len(), 500);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let err = run_to_string(&["zuuid", "--v7-precision", "sub-ms"]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert_eq!("SUB-MS".parse::<V7Precision>().unwrap(), V7Precision::SubMillis);
+        assert!("us".parse::<V7Precision>().is_err());
+    }
+
+    #[test]
+    fn test_counter_start_seeds_sub_ms_counter() {
+        let counter = |id: &Uuid| {
+            let value = id.as_u128();
+            (((value >> 64) & 0xfff) << V7_COUNTER_BITS) | ((value & RAND_B_MASK) >> 32)
+        };
+        let start = V7_COUNTER_MAX - 1;
+        let args = ["zuuid", "-V", "7", "--v7-precision", "sub-ms", "--timestamp-ms", "1714668051244", "--seed", "3", "-n", "3"];
+        let output = run_to_string(&[&args[..], &["--counter-start", &start.to_string()]].concat()).unwrap();
+        assert_eq!(output, run_to_string(&[&args[..], &["--counter-start", &start.to_string()]].concat()).unwrap());
+
+        let ids: Vec<Uuid> = output.lines().map(|line| Uuid::parse_str(line).unwrap()).collect();
+        assert_eq!(ids[0].as_u128() >> 80, 1_714_668_051_244);
+        assert_eq!(counter(&ids[0]), start as u128);
+        // Rollover carries out of the 30 counter bits into the sub-millisecond bits above them
+        assert_eq!(counter(&ids[1]), V7_COUNTER_MAX as u128);
+        assert_eq!(counter(&ids[2]), 1 << V7_COUNTER_BITS);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let max = (V7_COUNTER_MAX + 1).to_string();
+        assert!(Cli::try_parse_from(["zuuid", "-V", "7", "--v7-precision", "sub-ms", "--counter-start", &max]).is_err());
+        let err = run_to_string(&["zuuid", "-V", "7", "--counter-start", "0"]).unwrap_err();
+        assert_eq!(err.message(), Messages::new(Language::English).counter_start_needs_sub_ms());
+        assert_eq!(run_to_string(&["zuuid", "--timestamp-ms", "0"]).unwrap_err().exit_code(), 2);
+    }
+
+    /// Writer that accepts `limit` bytes and then reports a closed pipe, like `head -c`
+    struct PipeClosingAfter {
+        received: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for PipeClosingAfter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let room = self.limit - self.received.len();
+            if room == 0 {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            let taken = buf.len().min(room);
+            self.received.extend_from_slice(&buf[..taken]);
+            Ok(taken)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_run_succeeds_when_pipe_closes() {
+        for args in [
+            &["zuuid", "-n", "1000"][..],
+            &["zuuid", "-n", "1000", "--sort"],
+            &["zuuid", "-V", "5", "--namespace", "dns", "--name", "a", "--name", "b"],
+        ] {
+            let args = to_args(args);
+            let cli = Cli::try_parse_from(&args).unwrap();
+            let mut out = PipeClosingAfter { received: Vec::new(), limit: 40 };
+            assert!(run(&cli, false, false, &mut out, &ENGLISH).is_ok());
+            assert_eq!(out.received.len(), 40);
+        }
+    }
+
+    #[test]
+    fn test_output_error_classification() {
+        assert!(matches!(output_error(std::io::ErrorKind::BrokenPipe.into(), &ENGLISH), AppError::Closed));
+        let err = output_error(std::io::Error::other("disk full"), &ENGLISH);
+        assert_eq!(err.exit_code(), 74);
+    }
+
+    #[test]
+    fn test_entropy_info() {
+        let en = Messages::new(Language::English);
+        assert!(en.entropy_info(Some(42)).contains("seed 42"));
+        assert!(en.entropy_info(None).contains("operating system"));
+        assert!(!en.entropy_info(None).contains("seed"));
+
+        let zh = Messages::new(Language::Chinese);
+        assert!(zh.entropy_info(Some(42)).contains("种子为 42"));
+        assert!(zh.entropy_info(None).contains("操作系统"));
+
+        // Informational only: generation still happens
+        let output = run_to_string(&["zuuid", "--entropy-info", "--seed", "1"]).unwrap();
+        assert_eq!(output.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_all_versions_labels_each_version() {
+        let labels = |output: &str| -> Vec<String> {
+            output.lines().map(|line| line.split_once(' ').unwrap().0.to_string()).collect()
+        };
+
+        // --count is ignored; name-based versions need --name, and v1/v6 are never generated
+        let output = run_to_string(&["zuuid", "--all-versions", "-n", "5", "--local-id", "1000"]).unwrap();
+        assert_eq!(labels(&output), ["nil", "v2", "v4", "v7", "max"]);
+        for line in output.lines() {
+            let (label, id) = line.split_once(' ').unwrap();
+            let id = Uuid::parse_str(id).unwrap();
+            match label {
+                "nil" => assert!(id.is_nil()),
+                "max" => assert!(id.is_max()),
+                _ => assert_eq!(format!("v{}", id.get_version_num()), label),
+            }
+        }
+
+        // The org domain has no default local ID, so v2 is left out
+        let output =
+            run_to_string(&["zuuid", "--all-versions", "--domain", "org", "--namespace", "dns", "--name", "example.com", "-Us"]).unwrap();
+        assert_eq!(labels(&output), ["nil", "v3", "v4", "v5", "v7", "max"]);
+        assert!(output.ends_with("max FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF\n"));
+        let v5 = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"example.com").simple().to_string().to_uppercase();
+        assert!(output.contains(&format!("v5 {}\n", v5)));
+
+        let output = run_to_string(&["zuuid", "--all-versions", "--domain", "org", "--json"]).unwrap();
+        let json: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&output).unwrap();
+        assert_eq!(json.keys().collect::<Vec<_>>(), ["nil", "v4", "v7", "max"]);
+        assert_eq!(json["nil"], Uuid::nil().to_string());
+        assert_eq!(json["max"], Uuid::max().to_string());
+
+        assert!(run_to_string(&["zuuid", "--all-versions", "--name", "example.com"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--json", "--xml"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--all-versions", "--qr"]).is_err());
+    }
+
+    #[test]
+    fn test_verbose_logs_resolved_format() {
+        let log = |args: &[&str]| {
+            let args = to_args(args);
+            let (prefer_full, conflict) = determine_format_precedence(&args);
+            let cli = Cli::try_parse_from(&args).unwrap();
+            let mut err = Vec::new();
+            log_verbose(&cli, prefer_full, conflict, &mut err, &ENGLISH).unwrap();
+            String::from_utf8(err).unwrap()
+        };
+
+        let stderr = log(&["zuuid", "--verbose", "-V", "7", "-s", "-f", "-U", "-n", "3", "--seed", "1"]);
+        assert!(stderr.contains("Resolved version: v7"), "{}", stderr);
+        assert!(stderr.contains("Resolved format: simple, uppercase (both -f and -s given, -s came first)"), "{}", stderr);
+        assert!(stderr.contains("seeded mode") && stderr.contains("Count: 3"), "{}", stderr);
+        assert_eq!(stderr.lines().count(), 4);
+
+        let stderr = log(&["zuuid", "--verbose"]);
+        assert!(stderr.contains("Resolved format: full, lowercase") && !stderr.contains("came first"), "{}", stderr);
+        assert_eq!(log(&["zuuid", "--verbose", "--quiet"]), "");
+        assert_eq!(log(&["zuuid", "-s"]), "");
+    }
+
+    #[test]
+    fn test_batch_summary_matches_output() {
+        let cli = Cli::try_parse_from(["zuuid", "-V", "7", "-n", "50", "--seed", "9", "--shuffle", "--summary"]).unwrap();
+        let mut out = Vec::new();
+        let mut summary = BatchSummary::default();
+        generate_batch(&cli, false, false, &mut out, &mut summary, &ENGLISH).unwrap();
+        let ids: Vec<Uuid> = String::from_utf8(out).unwrap().lines().map(|line| Uuid::parse_str(line).unwrap()).collect();
+        assert_eq!(summary.count, 50);
+        assert_eq!(summary.min, ids.iter().min().copied());
+        assert_eq!(summary.max, ids.iter().max().copied());
+
+        let elapsed = std::time::Duration::from_millis(500);
+        let (min, max) = (summary.min.unwrap().to_string(), summary.max.unwrap().to_string());
+        let line = Messages::new(Language::English).batch_summary(&summary, "v7", elapsed);
+        assert!(line.starts_with("generated 50 v7 UUIDs in 0.50s (100/s), range "), "{}", line);
+        assert!(line.contains(&format!("{} .. {}", min, max)) && line.contains(", timestamps "));
+
+        let report = serde_json::to_value(SummaryReport::new(&summary, "v7", elapsed)).unwrap();
+        assert_eq!((report["count"].as_u64(), report["min"].as_str(), report["max"].as_str()), (Some(50), Some(&*min), Some(&*max)));
+        assert!(report["min_timestamp"].is_string());
+
+        // v4 has no timestamps, an empty batch no range
+        let v4 = SummaryReport::new(&BatchSummary::default(), "v4", elapsed);
+        assert_eq!(serde_json::to_value(v4).unwrap(), serde_json::json!({ "count": 0, "version": "v4", "seconds": 0.5, "per_second": 0.0 }));
+        assert_eq!(human_rate(238_400.0), "238k/s");
+        assert_eq!(human_rate(1_520_000.0), "1.5M/s");
+
+        // The summary goes to stderr only
+        let output = run_to_string(&["zuuid", "-n", "3", "--summary", "--quiet"]).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert!(Cli::try_parse_from(["zuuid", "--summary", "--summary-json"]).is_err());
+    }
+
+    #[test]
+    fn test_mix_counts_and_validation() {
+        let mix = parse_mix("v4:30,v7:70").unwrap();
+        assert_eq!(mix, VersionMix(vec![(UuidVersion::V4, 30), (UuidVersion::V7, 70)]));
+        assert_eq!(mix.counts(10), [(UuidVersion::V4, 3), (UuidVersion::V7, 7)]);
+        // Leftovers go to the largest remainders: 7 * 0.3 = 2.1 and 7 * 0.7 = 4.9
+        assert_eq!(mix.counts(7), [(UuidVersion::V4, 2), (UuidVersion::V7, 5)]);
+        assert_eq!(parse_mix("random:1,time:1").unwrap().counts(3), [(UuidVersion::V4, 2), (UuidVersion::V7, 1)]);
+        for bad in ["", "v4", "v4:30,v4:70", "v4:0,v7:100", "v5:50,v4:50", "v4:-1", "v4:30;v7:70", "v9:100"] {
+            assert_eq!(parse_mix(bad), Err(Messages::global().mix_reason().to_string()), "{:?}", bad);
+        }
+
+        // A seeded run has exactly the planned counts, in the same interleaving every time
+        let cli = Cli::try_parse_from(["zuuid", "--mix", "v4:30,v7:70", "-n", "100", "--seed", "5"]).unwrap();
+        let mut out = Vec::new();
+        let mut summary = BatchSummary::default();
+        generate_batch(&cli, false, false, &mut out, &mut summary, &ENGLISH).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        let versions: Vec<usize> = output.lines().map(|line| Uuid::parse_str(line).unwrap().get_version_num()).collect();
+        assert_eq!(versions.iter().filter(|&&v| v == 4).count(), 30);
+        assert_eq!(versions.iter().filter(|&&v| v == 7).count(), 70);
+        assert!(versions.windows(2).any(|pair| pair[0] != pair[1]) && versions[..30].contains(&7));
+        let again = run_to_string(&["zuuid", "--mix", "v4:30,v7:70", "-n", "100", "--seed", "5"]).unwrap();
+        let again: Vec<usize> = again.lines().map(|line| Uuid::parse_str(line).unwrap().get_version_num()).collect();
+        assert_eq!(again, versions);
+
+        // The summary reports the count of each version
+        let elapsed = std::time::Duration::from_secs(1);
+        let line = Messages::new(Language::English).batch_summary(&summary, "mixed", elapsed);
+        assert!(line.starts_with("generated 100 mixed UUIDs in 1.00s (100/s), versions v4 30, v7 70, range "), "{}", line);
+        let report = serde_json::to_value(SummaryReport::new(&summary, "mixed", elapsed)).unwrap();
+        assert_eq!(report["versions"], serde_json::json!({ "v4": 30, "v7": 70 }));
+
+        // Weights need not add up to 100
+        let output = run_to_string(&["zuuid", "--mix", "v4:1,v7:3", "-n", "8", "--seed", "5"]).unwrap();
+        assert_eq!(output.lines().filter(|line| line.as_bytes()[14] == b'7').count(), 6);
+        assert!(Cli::try_parse_from(["zuuid", "--mix", "v4:50,v7:50", "-V", "7"]).is_err());
+    }
+
+    #[test]
+    fn test_crlf_and_bom_bytes() {
+        let lf = run_to_string(&["zuuid", "--seed", "7", "-n", "2"]).unwrap();
+        let (first, second) = (&lf[..36], &lf[37..73]);
+        assert_eq!(lf, format!("{}\n{}\n", first, second));
+
+        let crlf = run_to_string(&["zuuid", "--seed", "7", "-n", "2", "--crlf"]).unwrap();
+        assert_eq!(crlf.as_bytes(), format!("{}\r\n{}\r\n", first, second).as_bytes());
+
+        // The BOM only marks files; stdout never gets one
+        let path = temp_path("crlf-bom.txt");
+        let path_arg = path.display().to_string();
+        let file = |args: &[&str]| {
+            run(&Cli::try_parse_from([&["zuuid", "--seed", "7", "-n", "2", "-o", &path_arg][..], args].concat()).unwrap(), false, false, &mut Vec::new(), &ENGLISH)
+                .unwrap();
+            std::fs::read(&path).unwrap()
+        };
+        assert_eq!(file(&["--bom"]), [&[0xef, 0xbb, 0xbf][..], lf.as_bytes()].concat());
+        assert_eq!(file(&["--bom", "--crlf"]), [&[0xef, 0xbb, 0xbf][..], crlf.as_bytes()].concat());
+        std::fs::remove_file(&path).unwrap();
+        assert!(Cli::try_parse_from(["zuuid", "-n", "2", "--bom"]).is_err());
+    }
+
+    #[test]
+    fn test_crlf_ends_every_line() {
+        for args in [
+            &["zuuid", "-n", "3", "--comment"][..],
+            &["zuuid", "--all-versions", "--local-id", "1"],
+            &["zuuid", "-n", "2", "--markdown"],
+            &["zuuid", "-n", "2", "--xml"],
+            &["zuuid", "-n", "2", "--c-array"],
+            &["zuuid", "-n", "2", "--env-file"],
+            &["zuuid", "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"],
+            &["zuuid", "namespaces"],
+            &["zuuid", "decode", "c232ab00-9414-11ec-b3c8-9f6bdeced846"],
+        ] {
+            let output = run_to_string(&[args, &["--crlf"]].concat()).unwrap();
+            assert!(output.ends_with("\r\n"), "{:?}", args);
+            assert_eq!(output.matches('\n').count(), output.matches("\r\n").count(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_print_lines_crlf() {
+        let mut out = Vec::new();
+        print_lines(&["a".to_string(), "b".to_string()], line_ending(true), &mut out).unwrap();
+        assert_eq!(out, b"a\r\nb\r\n");
+
+        // Global flag, so it is accepted after the subcommand too
+        let cli = Cli::try_parse_from(["zuuid", "shuffle", "--stdin", "--crlf"]).unwrap();
+        assert!(cli.crlf);
+    }
+
+    #[test]
+    fn test_parse_count_power_of_two() {
+        assert_eq!(parse_count("2^10").unwrap(), 1024);
+        assert_eq!(parse_count("2^0").unwrap(), 1);
+        assert_eq!(parse_count("1500").unwrap(), 1500);
+        assert_eq!(Cli::try_parse_from(["zuuid", "-n", "2^4"]).unwrap().count, 16);
+
+        let overflow = format!("2^{}", usize::BITS);
+        assert_eq!(parse_count(&overflow).unwrap_err(), Messages::new(Language::English).count_too_large(&overflow));
+        assert!(parse_count("3^2").is_err());
+        assert!(parse_count("2^").is_err());
+        assert!(parse_count("2^-1").is_err());
+        assert!(Cli::try_parse_from(["zuuid", "-n", "2^99"]).is_err());
+    }
+
+    #[test]
+    fn test_count_zero_and_max_count() {
+        let msgs = Messages::new(Language::English);
+        let err = run_to_string(&["zuuid", "-n", "0"]).unwrap_err();
+        assert_eq!((err.exit_code(), err.message()), (4, msgs.count_zero()));
+        assert_eq!(run_to_string(&["zuuid", "-n", "0", "--allow-empty"]).unwrap(), "");
+
+        assert_eq!(Cli::try_parse_from(["zuuid"]).unwrap().max_count, DEFAULT_MAX_COUNT);
+        assert!(check_count(DEFAULT_MAX_COUNT, false, DEFAULT_MAX_COUNT, &ENGLISH).is_ok());
+        assert_eq!(run_to_string(&["zuuid", "-n", "3", "--max-count", "3"]).unwrap().lines().count(), 3);
+        let err = run_to_string(&["zuuid", "-n", "4", "--max-count", "3"]).unwrap_err();
+        assert_eq!((err.exit_code(), err.message()), (4, msgs.count_over_max(4, 3).as_str()));
+        let err = run_to_string(&["zuuid", "-n", &(DEFAULT_MAX_COUNT + 1).to_string()]).unwrap_err();
+        assert_eq!(err.message(), msgs.count_over_max(DEFAULT_MAX_COUNT + 1, DEFAULT_MAX_COUNT));
+
+        // --keys sets the count, so it is held to the limit too
+        let err = run_to_string(&["zuuid", "--env-file", "--keys", "A,B", "--max-count", "1"]).unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+
+        assert_eq!(cli_with_config(&["zuuid"], "max_count = 10").unwrap().max_count, 10);
+        let cli = cli_with_config(&["zuuid", "--max-count", "3"], "[profiles.big]\nmax_count = 10").unwrap();
+        assert_eq!(cli.max_count, 3);
+    }
+
+    /// Module matrix of a generated QR code, for decoding with rqrr
+    struct QrModules(qrcode::QrCode);
+
+    impl rqrr::BitGrid for QrModules {
+        fn size(&self) -> usize {
+            self.0.width()
+        }
+
+        fn bit(&self, y: usize, x: usize) -> bool {
+            self.0[(x, y)] == qrcode::Color::Dark
+        }
+    }
+
+    #[test]
+    fn test_qr_code_decodes_to_text() {
+        for text in [
+            "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6",
+            "018F3A2E1B2C7D3E8F40A1B2C3D4E5F6",
+            "urn:uuid:018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6",
+        ] {
+            let grid = rqrr::Grid::new(QrModules(qr_code(text, &ENGLISH).unwrap()));
+            let (_, decoded) = grid.decode().unwrap();
+            assert_eq!(decoded, text);
+        }
+    }
+
+    /// render_qr output for 018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6
+    const QR_SNAPSHOT: &str = concat!(
+        "█████████████████████████████████████\n",
+        "█████████████████████████████████████\n",
+        "████ ▄▄▄▄▄ █ ▄▀▀▄█▄▄▄▄▀▀ █ ▄▄▄▄▄ ████\n",
+        "████ █   █ █▄▀▀  ████▀ ▄▄█ █   █ ████\n",
+        "████ █▄▄▄█ █▄  ▀ █▄█▀▀▄▄▄█ █▄▄▄█ ████\n",
+        "████▄▄▄▄▄▄▄█▄▀▄█ ▀▄▀ █▄█▄█▄▄▄▄▄▄▄████\n",
+        "████▄▀█▄ ▄▄  ▄█▄▀ █ ▄▄▀ █▄█▀ ▀ ▄▄████\n",
+        "████ ▀ ▄▀▄▄▀▀ ▀██ █▄▄ ▄█▄ █ ▀███▀████\n",
+        "████ ▀▀▀ ▄▄▀▀█  ▀ ▀ ██▄█▀▄ █▀▀▀ ▀████\n",
+        "████▀█ ▀█ ▄ █▀ ▀▄▄█▄██▄▄▄▀▀█▀▀   ████\n",
+        "████▀▄█ ▄█▄  ▄▀▀▀▀  █▀▀ ▄ █▄▀██ █████\n",
+        "████   ▄▀▄▄█▀▄▀▀ █▀▄ █  █▀ █ ▀ ▄▄████\n",
+        "████▄▄█▄▄█▄█   ▀▀▄  ▀▄▄▄ ▄▄▄ █  ▄████\n",
+        "████ ▄▄▄▄▄ █ █▀▀ █ ▄█▀   █▄█ ▀█▄▀████\n",
+        "████ █   █ █ ▀▄▄█▄▄  █▀▄▄ ▄ ▄▄▀█ ████\n",
+        "████ █▄▄▄█ ██▀▄█▀█  ▀▄▄ █ ▀▀ █▀▄ ████\n",
+        "████▄▄▄▄▄▄▄█▄█▄███▄▄▄█▄█▄▄███████████\n",
+        "█████████████████████████████████████\n",
+        "▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀",
+    );
+
+    #[test]
+    fn test_render_qr_snapshot() {
+        let rendered = render_qr(&qr_code("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", &ENGLISH).unwrap());
+        assert_eq!(rendered, QR_SNAPSHOT);
+    }
+
+    #[test]
+    fn test_qr_requires_single_uuid() {
+        let err = run_to_string(&["zuuid", "--qr", "-n", "2"]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(Cli::try_parse_from(["zuuid", "--qr-urn"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--qr", "--short", "8"]).is_err());
+
+        // stdout still carries just the plain line
+        let output = run_to_string(&["zuuid", "--qr", "--seed", "3"]).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert!(Uuid::parse_str(output.trim_end()).is_ok());
+    }
+
+    fn decode_args(uuids: &[&str], canonical_only: bool) -> DecodeArgs {
+        DecodeArgs {
+            uuids: uuids.iter().map(|id| id.to_string()).collect(),
+            canonical_only,
+            expect_check_digit: false,
+            stats: false,
+            dump: false,
+            stdin: false,
+            require_version: None,
+            json: false,
+            json_pretty: false,
+        }
+    }
+
+    #[test]
+    fn test_decode_normalizes_braced_uppercase() {
+        let args = decode_args(&["{018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}"], true);
+        assert_eq!(decode_lines(&args, false, &ENGLISH).unwrap(), ["018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"]);
+
+        let args = decode_args(&["urn:uuid:018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6", "6BA7B8109DAD11D180B400C04FD430C8"], true);
+        assert_eq!(
+            decode_lines(&args, false, &ENGLISH).unwrap(),
+            ["018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", "6ba7b810-9dad-11d1-80b4-00c04fd430c8"]
+        );
+    }
+
+    #[test]
+    fn test_dce_uuid_embeds_domain_and_local_id() {
+        // 2024-05-02T16:40:51.244Z in 100 ns ticks since the Gregorian epoch
+        let timestamp = 1_714_668_051_244 * 10_000 + GREGORIAN_OFFSET;
+        let id = dce_uuid(DceDomain::Person, 1000, timestamp, &[0xff, 0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(id.as_bytes()[..4], [0x00, 0x00, 0x03, 0xe8]);
+        assert_eq!(id.as_bytes()[4..6], ((timestamp >> 32) as u16).to_be_bytes());
+        assert_eq!(id.as_bytes()[6..8], ((timestamp >> 48) as u16 | 0x2000).to_be_bytes());
+        assert_eq!(id.as_bytes()[8..], [0xbf, 0x00, 0x03, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(id.get_version_num(), 2);
+        assert_eq!(id.get_variant(), uuid::Variant::RFC4122);
+        assert_eq!(dce_fields(&id), Some((0, 1000)));
+
+        let group = dce_uuid(DceDomain::Group, 0xdead_beef, timestamp, &[0; 7]);
+        assert_eq!(dce_fields(&group), Some((1, 0xdead_beef)));
+        let lines = Messages::new(Language::English).decoded(&group);
+        assert_eq!(lines[3..], ["domain: group", "local_id: 3735928559"]);
+        assert_eq!(dce_domain_label(9), "9");
+
+        let output = run_to_string(&["zuuid", "-V", "dce", "--domain", "org", "--local-id", "42", "-n", "3", "--sort"]).unwrap();
+        for line in output.lines() {
+            assert_eq!(dce_fields(&Uuid::parse_str(line).unwrap()), Some((2, 42)));
+        }
+        assert!(matches!(run_to_string(&["zuuid", "-V", "2", "--domain", "org"]), Err(AppError::Usage(_))));
+        assert!(matches!(run_to_string(&["zuuid", "-V", "4", "--local-id", "1"]), Err(AppError::Usage(_))));
+    }
+
+    #[test]
+    fn test_decode_v1_clock_seq_and_node() {
+        // The RFC 9562 v1 example and its v6 reordering share clock sequence and node
+        let v1 = Uuid::parse_str("c232ab00-9414-11ec-b3c8-9f6bdeced846").unwrap();
+        let v6 = Uuid::parse_str("1ec9414c-232a-6b00-b3c8-9f6bdeced846").unwrap();
+        for id in [v1, v6] {
+            let node: Vec<String> = id.as_bytes()[10..].iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(time_mac_fields(&id), Some((0x33c8, node.join(":"))));
+        }
+        assert_eq!(time_mac_fields(&v1).unwrap().1, "9f:6b:de:ce:d8:46");
+        assert_eq!(time_mac_fields(&Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap()), None);
+
+        let lines = Messages::new(Language::English).decoded(&v1);
+        assert_eq!(lines[3..], ["clock_seq: 13256", "node: 9f:6b:de:ce:d8:46"]);
+
+        let json = serde_json::to_value(InspectRow::new("c232ab00-9414-11ec-b3c8-9f6bdeced846")).unwrap();
+        assert_eq!((json["clock_seq"].as_u64(), json["node"].as_str()), (Some(0x33c8), Some("9f:6b:de:ce:d8:46")));
+        let json = serde_json::to_value(InspectRow::new("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6")).unwrap();
+        assert!(json.get("node").is_none());
+    }
+
+    #[test]
+    fn test_decode_reports_version_and_variant() {
+        let en = Messages::new(Language::English);
+        let id = Uuid::parse_str("018F3A2E1B2C7D3E8F40A1B2C3D4E5F6").unwrap();
+        assert_eq!(
+            en.decoded(&id),
+            ["canonical: 018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", "version: 7 (Unix time-ordered)", "variant: RFC 9562"]
+        );
+        assert_eq!(en.version_description(&Uuid::nil()), "nil");
+        assert_eq!(Messages::new(Language::Chinese).decoded(&id)[1], "版本：7 (Unix 时间有序)");
+
+        let args = decode_args(&["{018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}", &Uuid::new_v4().to_string()], false);
+        let lines = decode_lines(&args, false, &ENGLISH).unwrap();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], Messages::new(Language::English).decoded(&id)[0]);
+        assert_eq!(lines[3], "");
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        let err = decode_lines(&decode_args(&["not-a-uuid"], true), false, &ENGLISH).unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.message(), "Invalid value #1 for UUID: \"not-a-uuid\": not a UUID");
+        assert!(Cli::try_parse_from(["zuuid", "decode"]).is_err());
+    }
+
+    #[test]
+    fn test_input_errors_name_argument_position_and_value() {
+        let english = Messages::new(Language::English);
+        let chinese = Messages::new(Language::Chinese);
+        let err = InputError::new("--versions", 2, "6", english.version_reason());
+        assert_eq!(
+            english.input_error(&err),
+            "Invalid value #2 for --versions: \"6\": use 2, 3, 4, 5 or 7, also written v7, uuid7 or uuidv7, or dce (2), random (4) and time (7)"
+        );
+        let err = InputError::new("--after", 1, "2024-13-01", chinese.time_reason());
+        assert_eq!(
+            chinese.input_error(&err),
+            "--after 的第 1 个值无效：\"2024-13-01\"：请使用 YYYY-MM-DD、UTC 时间戳（如 2024-01-01T12:00:00Z）或 now"
+        );
+        let err = InputError::new("UUID", 3, "zz", chinese.not_encoded(Encoding::Uuid25));
+        assert_eq!(chinese.input_error(&err), "UUID 的第 3 个值无效：\"zz\"：不是有效的 uuid25 UUID");
+
+        // Clap's rejections are located among every value given to the option, delimited lists item by item
+        let clap_error = |args: &[&str]| clap_input_error(&Cli::try_parse_from(args).err().unwrap(), &to_args(args), &ENGLISH).unwrap();
+        let err = clap_error(&["zuuid", "bench", "--versions", "4", "--versions=7,6"]);
+        assert_eq!(err, InputError::new("--versions", 3, "6", english.version_reason()));
+        assert_eq!(clap_error(&["zuuid", "-V9"]), InputError::new("--uuid-version", 1, "9", english.version_reason()));
+        let err = clap_error(&["zuuid", "filter", "--after", "now", "--before", "2024-02-30"]);
+        assert_eq!(err, InputError::new("--before", 1, "2024-02-30", english.time_reason()));
+        let err = clap_error(&["zuuid", "-V", "5", "--namespace", "example", "--name", "a"]);
+        assert_eq!(err, InputError::new("--namespace", 1, "example", english.namespace_reason()));
+        assert!(clap_input_error(&Cli::try_parse_from(["zuuid", "--no-such-flag"]).err().unwrap(), &[], &ENGLISH).is_none());
+
+        let err = run_to_string(&["zuuid", "convert", "--from", "uuid25", "0000000000000000000000000", "zz"]).unwrap_err();
+        assert_eq!(err.message(), "Invalid value #2 for UUID: \"zz\": not a valid uuid25 UUID");
+    }
+
+    #[test]
+    fn test_phrase_uuid_is_deterministic_v4() {
+        let first = phrase_uuid("fixture-users", None);
+        assert_eq!(first, phrase_uuid("fixture-users", None));
+        assert_ne!(first, phrase_uuid("fixture-orders", None));
+        assert_eq!(first.get_version_num(), 4);
+        assert_eq!(first.get_variant(), uuid::Variant::RFC4122);
+        // SHA-256("abc") starts ba7816bf8f01cfea414140de5dae2223; version and variant bits are then forced
+        assert_eq!(phrase_uuid("abc", None).to_string(), "ba7816bf-8f01-4fea-8141-40de5dae2223");
+    }
+
+    #[test]
+    fn test_from_phrase_batches_are_indexed() {
+        let once = run_to_string(&["zuuid", "--from-phrase", "demo", "-n", "3"]).unwrap();
+        let again = run_to_string(&["zuuid", "--from-phrase", "demo", "-n", "3"]).unwrap();
+        assert_eq!(once, again);
+        let lines: Vec<&str> = once.lines().collect();
+        assert_eq!(lines[0], phrase_uuid("demo", Some(1)).to_string());
+        assert_eq!(lines[2], phrase_uuid("demo", Some(3)).to_string());
+
+        let err = run_to_string(&["zuuid", "--from-phrase", "demo", "-V", "7"]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    fn bench_args(json: bool) -> BenchArgs {
+        BenchArgs {
+            duration: std::time::Duration::from_millis(20),
+            versions: vec![UuidVersion::V4, UuidVersion::V7],
+            formats: vec![TextFormat::Full, TextFormat::Simple, TextFormat::Base64],
+            warmup: None,
+            json,
+            json_pretty: false,
+        }
+    }
+
+    #[test]
+    fn test_bench_table() {
+        let lines = bench_report(&bench_args(false), &ENGLISH).unwrap();
+        assert_eq!(lines.len(), 7);
+        let combos: Vec<(&str, &str)> = lines[1..]
+            .iter()
+            .map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                assert_eq!(fields.len(), 5);
+                assert!(fields[4].parse::<f64>().unwrap() > 0.0);
+                (fields[0], fields[1])
+            })
+            .collect();
+        assert_eq!(
+            combos,
+            [("v4", "full"), ("v4", "simple"), ("v4", "base64"), ("v7", "full"), ("v7", "simple"), ("v7", "base64")]
+        );
+    }
+
+    #[test]
+    fn test_bench_json() {
+        let lines = bench_report(&bench_args(true), &ENGLISH).unwrap();
+        assert_eq!(lines.len(), 1);
+        let results: Vec<serde_json::Value> = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(results.len(), 6);
+        for result in &results {
+            assert!(result["uuids"].as_u64().unwrap() > 0);
+            assert!(result["seconds"].as_f64().unwrap() > 0.0);
+            assert!(result["per_second"].as_f64().unwrap() > 0.0);
+        }
+        assert_eq!(results[5]["version"], "v7");
+        assert_eq!(results[5]["format"], "base64");
+    }
+
+    #[test]
+    fn test_bench_arguments() {
+        assert_eq!(parse_duration("500ms").unwrap(), std::time::Duration::from_millis(500));
+        assert_eq!(parse_duration("3s").unwrap(), std::time::Duration::from_secs(3));
+        assert_eq!(parse_duration("1.5").unwrap(), std::time::Duration::from_millis(1500));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("fast").is_err());
+        assert!(parse_duration("1e30s").is_err());
+
+        let cli = Cli::try_parse_from(["zuuid", "bench", "--versions", "7", "--formats", "simple,full"]).unwrap();
+        let Some(Command::Bench(args)) = cli.command else { panic!("expected bench") };
+        assert_eq!(args.versions, [UuidVersion::V7]);
+        assert_eq!(args.formats, [TextFormat::Simple, TextFormat::Full]);
+        let cli = Cli::try_parse_from(["zuuid", "bench", "--formats", "base64,simple"]).unwrap();
+        let Some(Command::Bench(args)) = cli.command else { panic!("expected bench") };
+        assert_eq!(args.formats, [TextFormat::Base64, TextFormat::Simple]);
+        assert_eq!(args.duration, std::time::Duration::from_secs(3));
+        assert!(Cli::try_parse_from(["zuuid", "bench", "--formats", "base32"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "bench", "--duration", "1e30s"]).is_err());
+
+        let mut args = bench_args(false);
+        args.versions = vec![UuidVersion::V5];
+        assert_eq!(run_bench(&args, &ENGLISH).unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_bench_warmup_is_not_measured() {
+        let cli = Cli::try_parse_from(["zuuid", "bench", "--warmup", "1000", "--duration", "20ms", "--versions", "4", "--formats", "simple"]).unwrap();
+        let Some(Command::Bench(args)) = cli.command else { panic!("expected bench") };
+        let results = run_bench(&args, &ENGLISH).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].warmup, 1000);
+        // Timed runs count whole batches of 256, which 1000 warmup UUIDs would not add up to
+        assert!(results[0].uuids > 0 && results[0].uuids.is_multiple_of(256), "{}", results[0].uuids);
+        assert_eq!(results[0].per_second, results[0].uuids as f64 / results[0].seconds);
+
+        let json = bench_report(&BenchArgs { json: true, ..args }, &ENGLISH).unwrap();
+        let json: Vec<serde_json::Value> = serde_json::from_str(&json[0]).unwrap();
+        assert_eq!(json[0]["warmup"], 1000);
+    }
+
+    #[test]
+    fn test_uuid_stats_counts_mixed_input() {
+        let input = [
+            "d9428888-122b-41b1-8b04-1e1b5a1c6f2e",
+            "6BA7B8109DAD41D180B400C04FD430C8",
+            "{7d444840-9dc0-41a2-b1f5-7f5c9e4d0f0b}",
+            "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6",
+            "018f3a2e-1b2d-7000-8000-0000deadbeef",
+            "",
+            "00000000-0000-0000-0000-000000000000",
+            "not a uuid",
+            "018f3a2e-1b2c-7d3e-cf40-a1b2c3d4e5f6",
+        ]
+        .join("\n");
+        let stats = uuid_stats(std::io::Cursor::new(input), None, &ENGLISH).unwrap();
+
+        assert_eq!(stats.total, 8);
+        assert_eq!(stats.invalid, 1);
+        assert_eq!(stats.versions["4"], 3);
+        assert_eq!(stats.versions["7"], 3);
+        assert_eq!(stats.versions["nil"], 1);
+        assert_eq!(stats.versions["1"], 0);
+        assert_eq!(stats.variants["rfc9562"], 5);
+        assert_eq!(stats.variants["microsoft"], 1);
+        assert_eq!(stats.variants["ncs"], 1);
+
+        let json: serde_json::Value = serde_json::from_str(&stats_lines(&stats, Some(JsonLayout::Compact), &ENGLISH).unwrap()[0]).unwrap();
+        assert_eq!(json["versions"]["7"], 3);
+        assert_eq!(json["invalid"], 1);
+
+        let lines = stats_lines(&stats, None, &ENGLISH).unwrap();
+        assert_eq!(lines.len(), 2 + STATS_VERSIONS.len() + STATS_VARIANTS.len());
+        let en = Messages::new(Language::English);
+        assert_eq!(en.stats_line(StatsLabel::Version("4"), 3), "version 4: 3");
+        assert_eq!(en.stats_line(StatsLabel::Variant(uuid::Variant::RFC4122), 5), "variant RFC 9562: 5");
+    }
+
+    #[test]
+    fn test_decode_stats_arguments() {
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--stats", "--json"]).is_ok());
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--stats", "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--json", "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"]).is_err());
+    }
+
+    #[test]
+    fn test_repl_scripted_transcript() {
+        let script = [
+            "gen",
+            "format simple",
+            "upper on",
+            "v7",
+            "gen 2",
+            "",
+            "inspect {018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}",
+            "bogus",
+            "quit",
+            "gen",
+        ]
+        .join("\n");
+        let mut out = Vec::new();
+        repl_lines(std::io::Cursor::new(script), DEFAULT_MAX_COUNT, "\n", &mut out, &ENGLISH).unwrap();
+        let transcript = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = transcript.lines().collect();
+
+        // One default v4, two uppercase simple v7s, the inspect report, and nothing after quit
+        assert_eq!(lines.len(), 6);
+        let first = Uuid::parse_str(lines[0]).unwrap();
+        assert_eq!((first.get_version_num(), lines[0].len()), (4, 36));
+        assert_eq!(lines[0], lines[0].to_lowercase());
+        for line in &lines[1..3] {
+            assert_eq!(line.len(), 32);
+            assert_eq!(*line, line.to_uppercase());
+            assert_eq!(Uuid::parse_str(line).unwrap().get_version_num(), 7);
+        }
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        assert_eq!(lines[3..], Messages::new(Language::English).decoded(&id));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_time("2024-05-02").unwrap(), 1_714_608_000_000);
+        assert_eq!(parse_time("2024-05-02T16:40:51.244Z").unwrap(), 1_714_668_051_244);
+        assert_eq!(parse_time("2024-05-02t16:40:51.2z").unwrap(), 1_714_668_051_200);
+        assert_eq!(format_rfc3339_millis(parse_time("2000-02-29T23:59:59Z").unwrap() as i64), "2000-02-29T23:59:59.000Z");
+        assert!(parse_time("now").unwrap() >= parse_time("2024-01-01").unwrap());
+        for bad in ["2023-02-29", "2024-13-01", "24-01-01", "2024-01-01T12:00:00", "2024-01-01T24:00:00Z", "1969-12-31", "yesterday"] {
+            assert!(parse_time(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_filter_predicates() {
+        let filter = |args: &[&str], input: &str| -> Result<String, AppError> {
+            let cli = Cli::try_parse_from([&["zuuid", "filter", "--stdin"][..], args].concat()).unwrap();
+            let Some(Command::Filter(args)) = cli.command else { panic!("not filter") };
+            let mut out = Vec::new();
+            filter_lines(&args, std::io::Cursor::new(input), "\n", &mut out, &ENGLISH)?;
+            Ok(String::from_utf8(out).unwrap())
+        };
+        // v7 from 2024-05-02, v7 from 2023-11-14, v4, and a braced uppercase v1 from 2022-02-22
+        let input = "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6\n\
+                     018bcfe5-6800-7000-8000-000000000000\n\
+                     \n\
+                     cafe0000-0000-4000-8000-000000000000\n\
+                     {C232AB00-9414-11EC-B3C8-9F6BDECED846}\n";
+        let lines = |output: String| output.lines().map(str::to_string).collect::<Vec<_>>();
+
+        assert_eq!(lines(filter(&["--version", "7"], input).unwrap()).len(), 2);
+        assert_eq!(filter(&["--prefix", "CAFE-0"], input).unwrap(), "cafe0000-0000-4000-8000-000000000000\n");
+        assert_eq!(filter(&["--after", "2024-01-01"], input).unwrap(), "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6\n");
+        // Lines pass through unchanged, braces and case included
+        assert_eq!(filter(&["--before", "2023-01-01"], input).unwrap(), "{C232AB00-9414-11EC-B3C8-9F6BDECED846}\n");
+        assert_eq!(
+            lines(filter(&["--after", "2022-01-01", "--before", "now"], input).unwrap()),
+            ["018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", "018bcfe5-6800-7000-8000-000000000000", "{C232AB00-9414-11EC-B3C8-9F6BDECED846}"]
+        );
+        assert_eq!(filter(&["--version", "7", "--before", "2024-01-01"], input).unwrap(), "018bcfe5-6800-7000-8000-000000000000\n");
+
+        // --invert keeps the valid lines that fail, including those without a timestamp
+        assert_eq!(
+            lines(filter(&["--after", "2023-01-01", "--invert"], input).unwrap()),
+            ["cafe0000-0000-4000-8000-000000000000", "{C232AB00-9414-11EC-B3C8-9F6BDECED846}"]
+        );
+
+        // A v1 from 1582 is before 1970, not wrapped past every date
+        let gregorian = "00000000-0000-1000-8000-000000000000\n";
+        assert_eq!(filter(&["--before", "2000-01-01T00:00:00Z"], gregorian).unwrap(), gregorian);
+        assert_eq!(filter(&["--after", "1970-01-01"], gregorian).unwrap(), "");
+
+        let bad = "cafe0000-0000-4000-8000-000000000000\nnot-a-uuid\n";
+        assert!(matches!(filter(&[], bad), Err(AppError::Invalid(_))));
+        assert_eq!(filter(&["--skip-invalid"], bad).unwrap(), "cafe0000-0000-4000-8000-000000000000\n");
+        assert!(Cli::try_parse_from(["zuuid", "filter", "--prefix", "xyz"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "filter", "--after", "last week"]).is_err());
+    }
+
+    #[test]
+    fn test_extract_uuids() {
+        let a = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        let line = "id={018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}, urn:uuid:018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6;";
+        assert_eq!(extract_uuids(line), [a, a]);
+        // Glued to more hex or letters, or missing hyphens: not a match
+        assert!(extract_uuids("0018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6 018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6x").is_empty());
+        assert!(extract_uuids("018f3a2e1b2c7d3e8f40a1b2c3d4e5f6").is_empty());
+    }
+
+    #[test]
+    fn test_scan_counts_files_and_unique_uuids() {
+        const A: &str = "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6";
+        const B: &str = "6ba7b810-9dad-11d1-80b4-00c04fd430c8";
+        const C: &str = "00000000-0000-4000-8000-000000000001";
+        let root = temp_path("scan");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("sub/deep")).unwrap();
+        std::fs::write(root.join("a.txt"), format!("first {A}\nnone\nagain {A} and {B}\n")).unwrap();
+        std::fs::write(root.join("empty.txt"), "no ids here\n").unwrap();
+        std::fs::write(root.join("sub/b.cfg"), format!("{B}\n")).unwrap();
+        std::fs::write(root.join("sub/deep/c.json"), format!("{{\"id\": \"{C}\"}}\n")).unwrap();
+        std::fs::write(root.join("blob.bin"), format!("\0{C}\n")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("sub/loop")).unwrap();
+
+        let scan = |extra: &[&str]| {
+            let mut args = vec!["zuuid", "scan", root.to_str().unwrap()];
+            args.extend(extra);
+            let cli = Cli::try_parse_from(&args).unwrap();
+            let Some(Command::Scan(args)) = cli.command else { panic!("not a scan") };
+            let report = scan_paths(&args, &ENGLISH).unwrap();
+            (report, args)
+        };
+
+        let (report, args) = scan(&[]);
+        let counts: Vec<(String, usize)> = report.files.iter().map(|f| (f.path.clone(), f.count)).collect();
+        let path = |name: &str| root.join(name).display().to_string();
+        assert_eq!(counts, [(path("a.txt"), 3), (path("sub/b.cfg"), 1), (path("sub/deep/c.json"), 1)]);
+        assert_eq!((report.files_scanned, report.skipped_binary, report.total, report.unique), (4, 1, 5, 3));
+        let lines = scan_lines(&report, &args, &ENGLISH).unwrap();
+        assert_eq!(lines[0], format!("{}: 3", path("a.txt")));
+        assert_eq!(lines.last().unwrap(), "5 UUIDs (3 unique) in 3 of 4 files scanned, 1 binary files skipped");
+
+        // The binary file holds C too, which makes up for the too-deep c.json
+        let (report, _) = scan(&["--max-depth", "1", "--binary"]);
+        assert_eq!((report.files_scanned, report.skipped_binary, report.total, report.unique), (4, 0, 5, 3));
+        assert!(report.files.iter().all(|f| !f.path.contains("deep")));
+
+        let (report, args) = scan(&["--list"]);
+        let lines = scan_lines(&report, &args, &ENGLISH).unwrap();
+        assert_eq!(&lines[..3], [format!("{}:1:{A}", path("a.txt")), format!("{}:3:{A}", path("a.txt")), format!("{}:3:{B}", path("a.txt"))]);
+
+        let (report, args) = scan(&["--format", "json", "--list"]);
+        let json: serde_json::Value = serde_json::from_str(&scan_lines(&report, &args, &ENGLISH).unwrap()[0]).unwrap();
+        assert_eq!((json["total"].as_u64(), json["unique"].as_u64(), json["files_scanned"].as_u64()), (Some(5), Some(3), Some(4)));
+        assert_eq!(json["files"][0]["count"], 3);
+        assert_eq!(json["files"][2]["matches"], serde_json::json!([{ "line": 1, "uuid": C }]));
+
+        assert!(matches!(scan_paths(&ScanArgs { paths: vec![root.join("missing")], ..args }, &ENGLISH), Err(AppError::Io(_))));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_positional_uuids_are_inspected() {
+        const V7: &str = "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6";
+
+        // Without positionals the default command still generates
+        let output = run_to_string(&["zuuid"]).unwrap();
+        assert!(Uuid::parse_str(output.trim_end()).is_ok());
+
+        let output = run_to_string(&["zuuid", V7]).unwrap();
+        assert_eq!(output, format!("7\trfc9562\t2024-05-02T16:40:51.244Z\t{}\n", V7));
+
+        // Any accepted form, one line per argument, in order
+        let output = run_to_string(&["zuuid", "{018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}", "urn:uuid:00000000-0000-0000-0000-000000000000"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(V7));
+        assert!(lines[1].ends_with("00000000-0000-0000-0000-000000000000"));
+
+        // An invalid input fails the run, but only after every input is shown
+        let cli = Cli::try_parse_from(["zuuid", V7, "not-a-uuid", V7]).unwrap();
+        let mut out = Vec::new();
+        let err = run(&cli, false, false, &mut out, &ENGLISH).unwrap_err();
+        assert!(matches!(err, AppError::Invalid(ref msg) if msg == "1 of 3 inputs are not valid UUIDs"));
+        let output = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "invalid\t-\t-\tnot-a-uuid");
+
+        // Subcommand names still win over positionals
+        assert!(matches!(Cli::try_parse_from(["zuuid", "namespaces"]).unwrap().command, Some(Command::Namespaces(_))));
+        assert!(Cli::try_parse_from(["zuuid", "--repl", V7]).is_err());
+    }
+
+    #[test]
+    fn test_repl_shortcuts() {
+        let script = ["v7", "simple", "gen", "full", "gen", "decode 018f3a2e1b2c7d3e8f40a1b2c3d4e5f6", "exit"].join("\n");
+        let mut out = Vec::new();
+        repl_lines(std::io::Cursor::new(script), DEFAULT_MAX_COUNT, "\n", &mut out, &ENGLISH).unwrap();
+        let transcript = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = transcript.lines().collect();
+
+        assert_eq!((lines[0].len(), lines[1].len()), (32, 36));
+        for line in &lines[..2] {
+            assert_eq!(Uuid::parse_str(line).unwrap().get_version_num(), 7);
+        }
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        assert_eq!(lines[2..], Messages::new(Language::English).decoded(&id));
+
+        assert!(Cli::try_parse_from(["zuuid", "--repl"]).unwrap().repl);
+        assert_eq!(Messages::new(Language::English).repl_prompt(UuidVersion::V7), "zuuid (v7)> ");
+        assert_eq!(Messages::new(Language::Chinese).repl_prompt(UuidVersion::V4), "zuuid（v4）> ");
+    }
+
+    #[test]
+    fn test_repl_rejects_bad_commands() {
+        let mut session = ReplSession::new(DEFAULT_MAX_COUNT, &ENGLISH);
+        let msgs = Messages::new(Language::English);
+        assert_eq!(session.handle("format base64"), Err(msgs.repl_usage("format simple|full")));
+        assert_eq!(session.handle("launch"), Err(msgs.unknown_repl_command("launch")));
+        assert!(session.handle("gen lots").is_err());
+        assert!(session.handle("inspect nope").is_err());
+        assert_eq!(session.handle("decode"), Err(msgs.repl_usage("decode <uuid>")));
+        assert_eq!(session.handle("simple extra"), Err(msgs.unknown_repl_command("simple")));
+        assert_eq!(session.handle("  "), Ok(ReplReply::Lines(Vec::new())));
+        assert_eq!(session.handle("QUIT"), Ok(ReplReply::Quit));
+        assert_eq!(session.handle("gen 2^2"), Ok(ReplReply::Generate(4)));
+    }
+
+    #[test]
+    fn test_repl_gen_respects_max_count() {
+        let mut session = ReplSession::new(10, &ENGLISH);
+        let msgs = Messages::new(Language::English);
+        assert_eq!(session.handle("gen 11"), Err(msgs.count_over_max(11, 10)));
+        assert_eq!(session.handle("gen 999999999999"), Err(msgs.count_over_max(999_999_999_999, 10)));
+        assert_eq!(session.handle("gen 0"), Err(msgs.count_zero().to_string()));
+        assert_eq!(session.handle("gen 10"), Ok(ReplReply::Generate(10)));
+
+        // The rejected command leaves the session running
+        let mut out = Vec::new();
+        repl_lines(std::io::Cursor::new("gen 11\ngen 3"), 10, "\n", &mut out, &ENGLISH).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+    }
+
+    /// Send one raw HTTP/1.1 request and return (status line, body)
+    fn http_get(addr: std::net::SocketAddr, target: &str, accept: &str) -> (String, String) {
+        use std::io::Read;
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nAccept: {}\r\nConnection: close\r\n\r\n", target, accept)
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    #[test]
+    fn test_serve_endpoints_on_ephemeral_port() {
+        let server = std::sync::Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+        let addr = server.server_addr().to_ip().unwrap();
+        let worker = server.clone();
+        std::thread::spawn(move || serve_requests(&worker, 2, 100, &ENGLISH));
+
+        let (status, body) = http_get(addr, "/healthz", "*/*");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body, "ok\n");
+
+        let (status, body) = http_get(addr, "/uuid?version=7&count=5&format=simple", "text/plain");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let ids: Vec<&str> = body.lines().collect();
+        assert_eq!(ids.len(), 5);
+        assert!(ids.iter().all(|id| id.len() == 32 && Uuid::parse_str(id).unwrap().get_version_num() == 7));
+
+        let (status, body) = http_get(addr, "/uuid?count=3", "application/json");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["uuids"].as_array().unwrap().len(), 3);
+
+        let (status, _) = http_get(addr, "/uuid?count=101", "text/plain");
+        assert!(status.starts_with("HTTP/1.1 400"));
+        let (status, _) = http_get(addr, "/nope", "text/plain");
+        assert!(status.starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_handle_http_errors() {
+        let msgs = Messages::new(Language::English);
+        let get = tiny_http::Method::Get;
+        let over = handle_http(&get, "/uuid?count=11", false, 10, &ENGLISH);
+        assert_eq!(over, HttpReply::error(400, msgs.count_over_limit(11, 10), false));
+        assert_eq!(handle_http(&get, "/uuid?version=5", false, 10, &ENGLISH).status, 400);
+        assert_eq!(handle_http(&get, "/uuid?format=base32", false, 10, &ENGLISH).status, 400);
+        assert_eq!(handle_http(&get, "/uuid?colour=red", false, 10, &ENGLISH).status, 400);
+        assert_eq!(handle_http(&tiny_http::Method::Post, "/uuid", false, 10, &ENGLISH).status, 405);
+
+        let reply = handle_http(&get, "/uuid?version=v4&count=2^1", true, 10, &ENGLISH);
+        assert_eq!((reply.status, reply.json), (200, true));
+        let reply = handle_http(&get, "/uuid?format=base64", false, 10, &ENGLISH);
+        assert_eq!((reply.status, reply.body.trim_end().len()), (200, 22));
+
+        let error = handle_http(&get, "/missing", true, 10, &ENGLISH);
+        let json: serde_json::Value = serde_json::from_str(&error.body).unwrap();
+        assert_eq!(json["error"], msgs.not_found("/missing"));
+
+        let cli = Cli::try_parse_from(["zuuid", "serve"]).unwrap();
+        let Some(Command::Serve(args)) = cli.command else { panic!("expected serve") };
+        assert_eq!((args.port, args.bind.to_string(), args.max_count), (8080, "127.0.0.1".to_string(), 10_000));
+    }
+
+    #[test]
+    fn test_pacer_spaces_emissions() {
+        let mut pacer = Pacer::new(50.0);
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            pacer.wait();
+        }
+        // The first emission is immediate, then nine 20ms intervals
+        let elapsed = start.elapsed();
+        assert!(elapsed >= std::time::Duration::from_millis(175), "{:?}", elapsed);
+        assert!(elapsed < std::time::Duration::from_millis(400), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_pacer_does_not_burst_after_stall() {
+        let mut pacer = Pacer::new(100.0);
+        pacer.wait();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let start = std::time::Instant::now();
+        pacer.wait();
+        pacer.wait();
+        pacer.wait();
+        // Missed slots are not made up: two full intervals still separate the last three
+        assert!(start.elapsed() >= std::time::Duration::from_millis(18));
+    }
+
+    #[test]
+    fn test_rate_limits_generation() {
+        let start = std::time::Instant::now();
+        let output = run_to_string(&["zuuid", "--rate", "100", "-n", "6"]).unwrap();
+        assert_eq!(output.lines().count(), 6);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(48));
+
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("-5").is_err());
+        assert!(parse_rate("inf").is_err());
+        assert!(parse_rate("1e-300").is_err());
+        assert_eq!(parse_rate("0.5").unwrap(), 0.5);
+    }
+
+    #[test]
+    fn test_parse_mint_request() {
+        assert_eq!(parse_mint_request("7 100 simple\n", 1000, &ENGLISH).unwrap(), (UuidVersion::V7, 100, TextFormat::Simple));
+        assert_eq!(parse_mint_request("v4", 1000, &ENGLISH).unwrap(), (UuidVersion::V4, 1, TextFormat::Full));
+        assert_eq!(parse_mint_request("4 2^3", 1000, &ENGLISH).unwrap(), (UuidVersion::V4, 8, TextFormat::Full));
+        assert!(parse_mint_request("", 1000, &ENGLISH).is_err());
+        assert!(parse_mint_request("5 1", 1000, &ENGLISH).is_err());
+        assert!(parse_mint_request("7 1001", 1000, &ENGLISH).is_err());
+        assert!(parse_mint_request("7 1 full extra", 1000, &ENGLISH).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_orders_v7_across_clients() {
+        use std::io::Read;
+        use std::os::unix::net::UnixStream;
+
+        let path = temp_path("mint.sock");
+        let (listener, _socket_file) = bind_unix_socket(&path, false, &ENGLISH).unwrap();
+        assert_eq!(bind_unix_socket(&path, false, &ENGLISH).err().unwrap().exit_code(), 2);
+        std::thread::spawn(move || serve_unix_connections(&listener, 10_000, &ENGLISH));
+
+        let mint = |request: &str| -> Vec<Uuid> {
+            let mut stream = UnixStream::connect(&path).unwrap();
+            writeln!(stream, "{}", request).unwrap();
+            let mut reply = String::new();
+            stream.read_to_string(&mut reply).unwrap();
+            reply.lines().map(|line| Uuid::parse_str(line).unwrap()).collect()
+        };
+
+        let (first, second) = std::thread::scope(|scope| {
+            let first = scope.spawn(|| mint("7 500 simple"));
+            let second = scope.spawn(|| mint("7 500"));
+            (first.join().unwrap(), second.join().unwrap())
+        });
+        for ids in [&first, &second] {
+            assert_eq!(ids.len(), 500);
+            assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        // One shared counter: no duplicates, and later requests sort after both
+        let mut all: Vec<Uuid> = first.iter().chain(&second).copied().collect();
+        all.sort_unstable();
+        all.dedup();
+        assert_eq!(all.len(), 1000);
+        assert!(mint("7")[0] > *all.last().unwrap());
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        writeln!(stream, "9").unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        assert!(reply.starts_with("error: "));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_file_cleanup_and_force() {
+        let path = temp_path("force.sock");
+        std::fs::write(&path, "stale").unwrap();
+        assert!(bind_unix_socket(&path, false, &ENGLISH).is_err());
+        let (_listener, socket_file) = bind_unix_socket(&path, true, &ENGLISH).unwrap();
+        assert!(path.exists());
+        drop(socket_file);
+        assert!(!path.exists());
+        assert!(Cli::try_parse_from(["zuuid", "serve", "--force"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "serve", "--unix", "/tmp/x.sock", "--port", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_split_files_start_with_header() {
+        let prefix = temp_path("split-").display().to_string();
+        let cli = Cli::try_parse_from([
+            "zuuid", "-n", "5", "--split", "2", "--split-prefix", &prefix, "--split-header", "# part {index}: {count} ids",
+        ])
+        .unwrap();
+        run(&cli, false, false, &mut Vec::new(), &ENGLISH).unwrap();
+
+        let mut ids = Vec::new();
+        for (index, count) in [(1, 2), (2, 2), (3, 1)] {
+            let path = format!("{}{}.txt", prefix, index);
+            let content = std::fs::read_to_string(&path).unwrap();
+            let mut lines = content.lines();
+            assert_eq!(lines.next().unwrap(), format!("# part {}: {} ids", index, count));
+            let data: Vec<&str> = lines.collect();
+            assert_eq!(data.len(), count);
+            ids.extend(data.iter().map(|line| Uuid::parse_str(line).unwrap()));
+            std::fs::remove_file(&path).unwrap();
+        }
+        assert!(!std::path::Path::new(&format!("{}4.txt", prefix)).exists());
+        assert_eq!(ids.len(), 5);
+
+        assert!(Cli::try_parse_from(["zuuid", "--split", "0"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--split-header", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_export_dialects() {
+        let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        assert_eq!(export_statement("REQUEST_ID", id, ShellDialect::Sh), format!("export REQUEST_ID='{}'", id));
+        assert_eq!(export_statement("REQUEST_ID", id, ShellDialect::Fish), format!("set -gx REQUEST_ID '{}'", id));
+        assert_eq!(export_statement("REQUEST_ID", id, ShellDialect::PowerShell), format!("$env:REQUEST_ID = '{}'", id));
+
+        // Values that need escaping, as base64 or other encodings could produce
+        assert_eq!(shell_quote(r"a'b\c+", ShellDialect::Sh), r"'a'\''b\c+'");
+        assert_eq!(shell_quote(r"a'b\c+", ShellDialect::Fish), r"'a\'b\\c+'");
+        assert_eq!(shell_quote(r"a'b\c+", ShellDialect::PowerShell), r"'a''b\c+'");
+
+        assert!(parse_variable_name("REQUEST_ID").is_ok());
+        assert!(parse_variable_name("1ID").is_err());
+        assert!(parse_variable_name("ID;rm").is_err());
+        assert!("pwsh".parse::<ShellDialect>().is_ok());
+        assert!("csh".parse::<ShellDialect>().is_err());
+    }
+
+    #[test]
+    fn test_export_numbered_batch() {
+        for (shell, prefix) in [("sh", "export ID_"), ("fish", "set -gx ID_"), ("powershell", "$env:ID_")] {
+            let cli = Cli::try_parse_from(["zuuid", "-n", "3", "--export", "ID", "--shell", shell]).unwrap();
+            let mut out = Vec::new();
+            run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+            let output = String::from_utf8(out).unwrap();
+            let lines: Vec<&str> = output.lines().collect();
+            assert_eq!(lines.len(), 3);
+            for (i, line) in lines.iter().enumerate() {
+                assert!(line.starts_with(&format!("{}{}", prefix, i + 1)), "{}", line);
+                let value = line.rsplit(' ').next().unwrap().rsplit('=').next().unwrap();
+                assert!(Uuid::parse_str(value.trim_matches('\'')).is_ok());
+            }
+        }
+
+        let cli = Cli::try_parse_from(["zuuid", "--export", "ID"]).unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("export ID='"));
+        assert!(Cli::try_parse_from(["zuuid", "--shell", "fish"]).is_err());
+    }
+
+    #[test]
+    fn test_column_count() {
+        assert_eq!(column_count(80, 36), 2);
+        assert_eq!(column_count(80, 32), 2);
+        assert_eq!(column_count(120, 36), 3);
+        assert_eq!(column_count(112, 36), 3);
+        assert_eq!(column_count(111, 36), 2);
+        assert_eq!(column_count(80, 8), 8);
+        assert_eq!(column_count(20, 36), 1);
+
+        let items = ["aaaa", "bbbb", "cccc", "dddd", "eeee"];
+        assert_eq!(columns(&items, 16), ["aaaa  cccc  eeee", "bbbb  dddd"]);
+        assert_eq!(columns(&items, 3), items);
+        assert!(columns(&[], 80).is_empty());
+    }
+
+    #[test]
+    fn test_env_file_keys() {
+        let cli = Cli::try_parse_from(["zuuid", "--env-file", "--keys", "API_KEY,SESSION_SEED"]).unwrap();
+        assert_eq!(cli.keys, ["API_KEY", "SESSION_SEED"]);
+        assert!(Cli::try_parse_from(["zuuid", "--env-file", "--keys", "API_KEY,2FA"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--keys", "API_KEY"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--env-file", "--keys", "A", "-n", "2"]).is_err());
+
+        let cli = Cli::try_parse_from(["zuuid", "--env-file", "--keys", "A,B,A"]).unwrap();
+        assert_eq!(run(&cli, false, false, &mut Vec::new(), &ENGLISH).err().unwrap().exit_code(), 4);
+
+        let cli = Cli::try_parse_from(["zuuid", "--env-file", "--keys", "A,B,C"]).unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert_eq!(env_keys(&output), ["A", "B", "C"]);
+
+        let cli = Cli::try_parse_from(["zuuid", "--env-file", "-n", "2"]).unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+        assert_eq!(env_keys(&String::from_utf8(out).unwrap()), ["UUID_1", "UUID_2"]);
+    }
+
+    #[test]
+    fn test_env_keys_in_existing_file() {
+        let content = "# secrets\nAPI_KEY=abc\n\nexport SESSION_SEED = def\nnot a pair\n";
+        assert_eq!(env_keys(content), ["API_KEY", "SESSION_SEED"]);
+    }
+
+    #[test]
+    fn test_env_file_output_append() {
+        let path = temp_path("uuids.env");
+        let path_arg = path.display().to_string();
+        let args = ["zuuid", "-V", "5", "--namespace", "dns", "--name", "a.example", "--name", "b.example", "--env-file"];
+        let a = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"a.example");
+        let b = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"b.example");
+
+        let cli = Cli::try_parse_from(args.iter().copied().chain(["--keys", "API_KEY,SEED", "-o", &path_arg])).unwrap();
+        run(&cli, false, false, &mut Vec::new(), &ENGLISH).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("API_KEY={}\nSEED={}\n", a, b));
+
+        // Appending keeps the existing lines; the repeated key is only warned about
+        let cli = Cli::try_parse_from(args.iter().copied().chain(["--keys", "SEED,OTHER", "-o", &path_arg, "--append"]))
+            .unwrap();
+        run(&cli, false, false, &mut Vec::new(), &ENGLISH).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("API_KEY={}\nSEED={}\nSEED={}\nOTHER={}\n", a, b, a, b)
+        );
+
+        // Without --append the file is replaced
+        let cli = Cli::try_parse_from(args.iter().copied().chain(["-o", &path_arg])).unwrap();
+        run(&cli, false, false, &mut Vec::new(), &ENGLISH).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("UUID_1={}\nUUID_2={}\n", a, b));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bom_starts_the_output_file() {
+        let path = temp_path("bom.txt");
+        let path_arg = path.display().to_string();
+        for bom in [true, false] {
+            let args = [&["zuuid", "-n", "3", "-o", &path_arg][..], if bom { &["--bom"] } else { &[] }].concat();
+            run(&Cli::try_parse_from(args).unwrap(), false, false, &mut Vec::new(), &ENGLISH).unwrap();
+            let content = std::fs::read(&path).unwrap();
+            assert_eq!(content.starts_with(&[0xef, 0xbb, 0xbf]), bom);
+            // Only the start of the file is marked, not each line
+            assert_eq!(String::from_utf8(content).unwrap().matches('\u{feff}').count(), usize::from(bom));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_map_reuses_and_appends_keys() {
+        let path = temp_path("map.csv");
+        let args = MapArgs { stdin: true, map_file: path.clone() };
+        let map = |input: &str| -> Result<Vec<String>, AppError> {
+            let mut out = Vec::new();
+            map_keys(&args, std::io::Cursor::new(input), "\n", &mut out, &ENGLISH)?;
+            Ok(String::from_utf8(out).unwrap().lines().map(str::to_string).collect())
+        };
+
+        // A repeated key gets the same UUID within a run, and the file holds each key once
+        let first = map("alice\nbob\nalice\n").unwrap();
+        assert_eq!(first[0], first[2]);
+        assert_ne!(first[0], first[1]);
+        assert_eq!(Uuid::parse_str(&first[0]).unwrap().get_version_num(), 4);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("alice,{}\nbob,{}\n", first[0], first[1]));
+
+        // A later run reuses them and appends only the new keys, quoted when needed
+        let second = map("bob\n\"doe, jane\"\n\nalice\n").unwrap();
+        assert_eq!((&second[0], &second[3]), (&first[1], &first[0]));
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 4);
+        assert!(content.contains(&format!("\"\"\"doe, jane\"\"\",{}\n,{}\n", second[1], second[2])));
+        assert_eq!(map("\"doe, jane\"\n").unwrap(), [second[1].clone()]);
+
+        // Runs sharing the file take turns on its lock and agree on every key
+        let keys: String = (0..20).map(|i| format!("user{}\n", i)).collect();
+        let runs: Vec<Vec<String>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| map(&keys).unwrap())).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert!(runs.iter().all(|run| *run == runs[0]));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 24);
+
+        for corrupt in ["alice\n", "alice,not-a-uuid\n", "\"alice,00000000-0000-0000-0000-000000000000\n"] {
+            std::fs::write(&path, corrupt).unwrap();
+            assert!(matches!(map("alice\n"), Err(AppError::Invalid(_))), "{:?}", corrupt);
+        }
+        let repeated = "a,00000000-0000-0000-0000-000000000000\na,ffffffff-ffff-ffff-ffff-ffffffffffff\n";
+        std::fs::write(&path, repeated).unwrap();
+        assert!(matches!(map("b\n"), Err(AppError::Invalid(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_registry_never_reissues() {
+        for binary in [false, true] {
+            let path = temp_path(if binary { "registry.bin" } else { "registry.txt" });
+            let path_arg = path.to_str().unwrap();
+            let generate = |extra: &[&str]| -> Result<Vec<Uuid>, AppError> {
+                let mut args = vec!["zuuid", "--registry", path_arg];
+                if binary {
+                    args.push("--registry-binary");
+                }
+                let output = run_to_string(&[&args[..], extra].concat())?;
+                Ok(output.lines().map(|line| Uuid::parse_str(line).unwrap()).collect())
+            };
+
+            // The same seed replays the same stream, so a second run must skip past what the first issued
+            let first = generate(&["-n", "5", "--seed", "7"]).unwrap();
+            let second = generate(&["-n", "5", "--seed", "7"]).unwrap();
+            assert!(second.iter().all(|id| !first.contains(id)));
+            let content = std::fs::read(&path).unwrap();
+            let expected: Vec<u8> = if binary {
+                first.iter().chain(&second).flat_map(|id| *id.as_bytes()).collect()
+            } else {
+                first.iter().chain(&second).map(|id| format!("{}\n", id)).collect::<String>().into_bytes()
+            };
+            assert_eq!(content, expected);
+
+            // Runs sharing the registry take turns on its lock and never hand out the same UUID
+            let runs: Vec<Vec<Uuid>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..2).map(|_| scope.spawn(|| generate(&["-n", "50", "--seed", "9"]).unwrap())).collect();
+                handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+            });
+            let all: std::collections::HashSet<Uuid> = first.iter().chain(&second).chain(runs.concat().iter()).copied().collect();
+            assert_eq!(all.len(), 110);
+            assert_eq!(std::fs::read(&path).unwrap().len(), if binary { 110 * 16 } else { 110 * 37 });
+
+            let corrupt: &[u8] = if binary { &[0; 17] } else { b"not-a-uuid\n" };
+            std::fs::write(&path, corrupt).unwrap();
+            assert_eq!(generate(&[]).unwrap_err().exit_code(), 4);
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        // A text registry accepts any form decode does, and a cut-off last line is terminated first
+        let path = temp_path("registry-forms.txt");
+        std::fs::write(&path, "{67E55044-10B1-426F-9247-BB680E5FE0C8}").unwrap();
+        run_to_string(&["zuuid", "--registry", path.to_str().unwrap()]).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("{67E55044-10B1-426F-9247-BB680E5FE0C8}\n"));
+        assert_eq!(content.lines().count(), 2);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = run_to_string(&["zuuid", "-V", "5", "--namespace", "dns", "--name", "a", "--registry", "ids.txt"]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_ndjson_one_object_per_line() {
+        let output = run_to_string(&["zuuid", "-V", "7", "-n", "3", "--ndjson", "-U"]).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        for line in output.lines() {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            let uuid = record["uuid"].as_str().unwrap();
+            assert_eq!(uuid, uuid.to_uppercase());
+            assert_eq!(Uuid::parse_str(uuid).unwrap().get_version_num(), 7);
+            assert_eq!(record["version"], 7);
+            assert_eq!(record["variant"], "RFC4122");
+        }
+        let record = ndjson_record(&Uuid::nil(), "00000000000000000000000000000000", None);
+        assert_eq!(record, r#"{"uuid":"00000000000000000000000000000000","version":0,"variant":"NCS"}"#);
+        assert!(Cli::try_parse_from(["zuuid", "--ndjson", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_ndjson_with_index_follows_line_order() {
+        let output = run_to_string(&["zuuid", "-n", "5", "--ndjson", "--with-index", "--sort"]).unwrap();
+        let records: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        for (i, (line, record)) in output.lines().zip(&records).enumerate() {
+            assert_eq!(record["index"], i);
+            assert!(line.starts_with(&format!("{{\"index\":{},\"uuid\":", i)));
+        }
+        let ids: Vec<&str> = records.iter().map(|record| record["uuid"].as_str().unwrap()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let output = run_to_string(&["zuuid", "-n", "2", "--ndjson"]).unwrap();
+        assert!(!output.contains("index"));
+        assert!(Cli::try_parse_from(["zuuid", "--with-index"]).is_err());
+    }
+
+    #[test]
+    fn test_humanize_seconds_boundaries() {
+        let cases = [
+            (0, "0s"),
+            (59, "59s"),
+            (60, "1m"),
+            (61, "1m 1s"),
+            (3_599, "59m 59s"),
+            (3_600, "1h"),
+            (3_660, "1h 1m"),
+            (86_399, "23h 59m"),
+            (86_400, "1d"),
+            (273_600, "3d 4h"),
+            (90_061, "1d 1h"),
+        ];
+        for (seconds, text) in cases {
+            assert_eq!(humanize_seconds(seconds), text, "{}", seconds);
+        }
+    }
+
+    #[test]
+    fn test_age_past_future_and_now() {
+        // 2024-05-02T16:40:51.244Z
+        const V7: &str = "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6";
+        const MILLIS: u64 = 1_714_668_051_244;
+        let age = |extra: &[&str], input: &str, now: u64| -> Result<String, AppError> {
+            let cli = Cli::try_parse_from([&["zuuid", "age"][..], extra].concat()).unwrap();
+            let Some(Command::Age(args)) = cli.command else { panic!("not age") };
+            let mut out = Vec::new();
+            age_report(&args, std::io::Cursor::new(input), now, "\n", &mut out, &ENGLISH)?;
+            Ok(String::from_utf8(out).unwrap())
+        };
+
+        assert_eq!(age(&[V7], "", MILLIS + 273_600_000).unwrap(), format!("{}\t3d 4h ago\n", V7));
+        assert_eq!(age(&[V7], "", MILLIS + 999).unwrap(), format!("{}\tnow\n", V7));
+        assert_eq!(age(&[V7], "", MILLIS).unwrap(), format!("{}\tnow\n", V7));
+        assert_eq!(age(&[V7], "", MILLIS - 62_000).unwrap(), format!("{}\tin 1m 2s (in the future)\n", V7));
+        assert_eq!(age(&[V7, "--seconds"], "", MILLIS + 273_600_000).unwrap(), format!("{}\t273600\n", V7));
+        assert_eq!(age(&[V7, "--seconds"], "", MILLIS - 5_000).unwrap(), format!("{}\t-5\n", V7));
+        let chinese = Messages::new(Language::Chinese);
+        assert_eq!(chinese.age_ago("3d 4h"), "3d 4h前");
+        assert_eq!(chinese.age_ahead("5s"), "5s后（位于未来）");
+
+        // Versions without a timestamp fail, or read n/a with --lenient
+        let v1 = "c232ab00-9414-11ec-b3c8-9f6bdeced846";
+        let output = age(&["--stdin", "--seconds"], &format!("{}\n\n{}\n", v1, V7), 1_645_557_742_000).unwrap();
+        assert_eq!(output.lines().next().unwrap(), format!("{}\t0", v1));
+        let v4 = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let err = age(&["--stdin"], &format!("{}\n\n{}\n", V7, v4), MILLIS).unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(
+            err.message(),
+            format!("Invalid value #3 for --stdin: \"{}\": version 4 UUIDs carry no timestamp; only v1, v6 and v7 do", v4)
+        );
+        assert_eq!(age(&[v4, "--lenient"], "", MILLIS).unwrap(), format!("{}\tn/a\n", v4));
+
+        // A v1 timestamp before 1970 (here the Gregorian epoch, 1582-10-15) is in the past, not wrapped into the future
+        let gregorian = "00000000-0000-1000-8000-000000000000";
+        assert_eq!(signed_unix_millis(&Uuid::parse_str(gregorian).unwrap()), Some(-12_219_292_800_000));
+        assert_eq!(age(&[gregorian, "--seconds"], "", MILLIS).unwrap(), format!("{}\t13933960851\n", gregorian));
+        assert!(age(&[gregorian], "", MILLIS).unwrap().ends_with(" ago\n"));
+        assert_eq!(age(&["nope", "--lenient"], "", MILLIS).unwrap_err().exit_code(), 4);
+        assert!(Cli::try_parse_from(["zuuid", "age"]).is_err());
+    }
+
+    #[test]
+    fn test_rotate_matches_versions_and_repeats() {
+        let rotate = |extra: &[&str], input: &str| -> Result<Vec<(String, Uuid)>, AppError> {
+            let cli = Cli::try_parse_from([&["zuuid", "rotate", "--stdin"][..], extra].concat()).unwrap();
+            let Some(Command::Rotate(args)) = cli.command else { panic!("not rotate") };
+            let mut out = Vec::new();
+            rotate_lines(&args, std::io::Cursor::new(input), "\n", &mut out, &ENGLISH)?;
+            let output = String::from_utf8(out).unwrap();
+            Ok(output.lines().map(|line| {
+                let (old, new) = line.split_once('\t').unwrap();
+                (old.to_string(), Uuid::parse_str(new).unwrap())
+            }).collect())
+        };
+        let v4 = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let v7 = "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6";
+        let millis = |id: &Uuid| id.as_u128() >> 80;
+
+        let input = format!("{}\n\n{}\n{}\n", v4, v7, v4.to_uppercase());
+        let pairs = rotate(&[], &input).unwrap();
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0].0, v4);
+        assert_eq!(pairs[0].1.get_version_num(), 4);
+        assert_eq!(pairs[1].1.get_version_num(), 7);
+        assert!(millis(&pairs[1].1) > millis(&Uuid::parse_str(v7).unwrap()));
+        // The same UUID in another spelling keeps its first replacement
+        assert_eq!(pairs[2].0, v4.to_uppercase());
+        assert_eq!(pairs[2].1, pairs[0].1);
+        assert_ne!(pairs[0].1.to_string(), v4);
+
+        let pairs = rotate(&["--preserve-timestamp"], &format!("{}\n{}\n", v7, v7)).unwrap();
+        assert_eq!(millis(&pairs[0].1), 0x018f3a2e1b2c);
+        assert_eq!(pairs[0].1.get_version_num(), 7);
+        assert_ne!(pairs[0].1.to_string(), v7);
+        assert_eq!(pairs[1].1, pairs[0].1);
+
+        let v5 = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"x").to_string();
+        let err = rotate(&[], &format!("{}\n{}\n", v4, v5)).unwrap_err();
+        assert_eq!(err.message(), Messages::new(Language::English).rotate_version_not_supported(2, 5));
+        assert_eq!(rotate(&[], "nope\n").unwrap_err().exit_code(), 4);
+    }
+
+    #[test]
+    fn test_json_array_output() {
+        assert_eq!(run_to_string(&["zuuid", "-n", "0", "--json"]).unwrap(), "[]\n");
+        assert_eq!(run_to_string(&["zuuid", "-n", "0"]).unwrap_err().exit_code(), 4);
+
+        let lines = run_to_string(&["zuuid", "-n", "3", "--seed", "4", "-Us"]).unwrap();
+        let json = run_to_string(&["zuuid", "-n", "3", "--seed", "4", "-Us", "--json"]).unwrap();
+        let ids: Vec<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(ids, lines.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_json_elements_are_uuids() {
+        let json = run_to_string(&["zuuid", "-n", "3", "--json", "-s"]).unwrap();
+        let ids: Vec<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(ids.len(), 3);
+        for id in &ids {
+            Uuid::parse_str(id).unwrap();
+        }
+
+        let json = run_to_string(&["zuuid", "-n", "3", "--json", "--with-hash", "--with-partition", "4", "--show-variant"])
+            .unwrap();
+        let records: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records.len(), 3);
+        for record in &records {
+            let id = Uuid::parse_str(record["uuid"].as_str().unwrap()).unwrap();
+            assert_eq!(record["hash"], uuid_sha256(&id));
+            assert_eq!(record["partition"], kafka_partition(&id.to_string(), 4));
+            assert_eq!(record["variant"], "RFC4122");
+        }
+
+        let json = run_to_string(&["zuuid", "--json", "--with-hash"]).unwrap();
+        let records: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(records[0].as_object().unwrap().keys().collect::<Vec<_>>(), ["uuid", "hash"]);
+
+        assert!(Cli::try_parse_from(["zuuid", "--json", "--timestamped"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--json", "--check-digit"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_simple_short_upper() {
-        let cli = Cli::try_parse_from(["zuuid", "-S"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(!cli.uppercase);
-        assert!(cli.simple);
+    fn test_json_fill() {
+        let fill = |args: &[&str], input: &str| -> Result<serde_json::Value, AppError> {
+            let cli = Cli::try_parse_from([&["zuuid", "json-fill"][..], args].concat()).unwrap();
+            let Some(Command::JsonFill(args)) = cli.command else { panic!("not json-fill") };
+            let mut out = Vec::new();
+            json_fill(&args, input.as_bytes(), "\n", &mut out, &ENGLISH)?;
+            Ok(serde_json::from_slice(&out).unwrap())
+        };
+        let is_v4 = |value: &serde_json::Value| Uuid::parse_str(value.as_str().unwrap()).unwrap().get_version_num() == 4;
+
+        // Nested objects and arrays; other values and the key order are kept
+        let input = r#"{"z": "$uuid", "a": {"id": "$uuid", "n": 1, "tags": ["$uuid", "$uuid", "x"]}, "s": "$uuid:u1"}"#;
+        let json = fill(&[], input).unwrap();
+        assert_eq!(json.as_object().unwrap().keys().collect::<Vec<_>>(), ["z", "a", "s"]);
+        let ids = [&json["z"], &json["a"]["id"], &json["a"]["tags"][0], &json["a"]["tags"][1]];
+        assert!(ids.iter().all(|id| is_v4(id)));
+        assert_eq!(ids.iter().collect::<std::collections::HashSet<_>>().len(), 4);
+        assert_eq!((&json["a"]["n"], &json["a"]["tags"][2], &json["s"]), (&1.into(), &"x".into(), &"$uuid:u1".into()));
+
+        // --shared-key: the same key gets the same UUID, different keys and bare placeholders do not
+        let input = r#"[{"owner": "@id:u1", "id": "@id"}, {"owner": "@id:u1", "id": "@id"}, {"owner": "@id:u2"}]"#;
+        let json = fill(&["--shared-key", "--placeholder", "@id"], input).unwrap();
+        assert!(is_v4(&json[0]["owner"]));
+        assert_eq!(json[0]["owner"], json[1]["owner"]);
+        assert_ne!(json[0]["owner"], json[2]["owner"]);
+        assert_ne!(json[0]["id"], json[1]["id"]);
+
+        assert!(matches!(fill(&[], "{\"a\": "), Err(AppError::Invalid(_))));
+        assert!(matches!(fill(&[], "$uuid"), Err(AppError::Invalid(_))));
     }
 
     #[test]
-    fn test_cli_parse_simple_long() {
-        let cli = Cli::try_parse_from(["zuuid", "--simple"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(!cli.uppercase);
-        assert!(cli.simple);
+    fn test_checksum_round_trip() {
+        let path = temp_path("checksum.txt");
+        let path_arg = path.display().to_string();
+        let cli = Cli::try_parse_from(["zuuid", "-n", "5", "--seed", "3", "--bom", "--crlf", "--checksum", "-o", &path_arg]).unwrap();
+        run(&cli, false, false, &mut Vec::new(), &ENGLISH).unwrap();
+        let content = std::fs::read(&path).unwrap();
+        let (body, hex) = split_checksum(&content).unwrap();
+        assert!(body.starts_with(UTF8_BOM));
+        assert_eq!(String::from_utf8_lossy(body).lines().count(), 5);
+        assert_eq!(hex.len(), 64);
+
+        let verify = |path: &str| run_to_string(&["zuuid", "--verify-checksum", path]);
+        assert_eq!(verify(&path_arg).unwrap().trim_end(), Messages::new(Language::English).checksum_ok(&path));
+
+        // A lost line is caught, as is a file without a checksum line
+        let truncated: Vec<u8> = [&body[..body.len() - 38], &content[body.len()..]].concat();
+        std::fs::write(&path, truncated).unwrap();
+        assert_eq!(verify(&path_arg).unwrap_err().exit_code(), 4);
+        std::fs::write(&path, body).unwrap();
+        assert_eq!(verify(&path_arg).unwrap_err().exit_code(), 4);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(verify(&path_arg).unwrap_err().exit_code(), 74);
+
+        // On stdout the checksum is the last line
+        let output = run_to_string(&["zuuid", "-n", "2", "--checksum"]).unwrap();
+        assert!(output.lines().last().unwrap().starts_with(CHECKSUM_PREFIX));
+        assert!(Cli::try_parse_from(["zuuid", "--checksum", "--xml"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_combined() {
-        let cli = Cli::try_parse_from(["zuuid", "-u", "-s"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_decode_require_version() {
+        let v7 = ["018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f7"];
+        let v4 = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+        let args = DecodeArgs { require_version: Some(7), ..decode_args(&v7, true) };
+        assert_eq!(decode_lines(&args, false, &ENGLISH).unwrap(), v7);
+        let stats = uuid_stats(std::io::Cursor::new(v7.join("\n")), Some(7), &ENGLISH).unwrap();
+        assert_eq!(stats.total, 2);
+
+        let mixed = [v7[0], v4, v7[1]];
+        let args = DecodeArgs { require_version: Some(7), ..decode_args(&mixed, false) };
+        let err = decode_lines(&args, false, &ENGLISH).unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.message(), Messages::new(Language::English).version_mismatch(v4, 4, 7));
+        assert!(err.message().contains(v4));
+        let err = uuid_stats(std::io::Cursor::new(mixed.join("\n")), Some(7), &ENGLISH).unwrap_err();
+        assert_eq!(err.message(), Messages::new(Language::English).version_mismatch(v4, 4, 7));
+
+        assert_eq!(parse_version_number("v7").unwrap(), 7);
+        assert!(parse_version_number("0").is_err());
+        assert!(parse_version_number("9").is_err());
     }
 
     #[test]
-    fn test_cli_parse_combined_long() {
-        let cli = Cli::try_parse_from(["zuuid", "--upper", "--simple"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_markdown_table() {
+        let cli = Cli::try_parse_from(["zuuid", "--seed", "42", "-n", "2", "--markdown"]).unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "| # | UUID |\n",
+                "| ---: | --- |\n",
+                "| 1 | `a2242722-6377-4c86-bd51-ad3f130af08a` |\n",
+                "| 2 | `d13451de-7160-4fa2-b230-76fd782de967` |\n",
+            )
+        );
+
+        // A single UUID is still a whole table
+        let cli = Cli::try_parse_from(["zuuid", "--seed", "42", "--markdown", "-s"]).unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+
+        let id = Uuid::parse_str("018cc251-f400-7000-8000-000000000000").unwrap();
+        assert_eq!(markdown_header(true), ["| # | UUID | Timestamp |", "| ---: | --- | --- |"]);
+        assert_eq!(
+            markdown_row(3, &id.to_string(), id, true),
+            "| 3 | `018cc251-f400-7000-8000-000000000000` | 2024-01-01T00:00:00.000Z |"
+        );
     }
 
     #[test]
-    fn test_cli_parse_no_args() {
-        let cli = Cli::try_parse_from(["zuuid"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(!cli.uppercase);
-        assert!(!cli.simple);
+    fn test_output_append_accumulates_batches() {
+        let path = temp_path("append.txt");
+        let path_arg = path.display().to_string();
+        run_to_string(&["zuuid", "-n", "2", "--seed", "1", "-o", &path_arg, "--append"]).unwrap();
+        let first = std::fs::read_to_string(&path).unwrap();
+        run_to_string(&["zuuid", "-n", "3", "--seed", "2", "-o", &path_arg, "--append"]).unwrap();
+        let both = std::fs::read_to_string(&path).unwrap();
+        assert!(both.starts_with(&first));
+        assert_eq!(both.lines().filter(|line| Uuid::parse_str(line).is_ok()).count(), 5);
+
+        // Without --append the file is replaced
+        run_to_string(&["zuuid", "-n", "1", "-o", &path_arg]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        std::fs::remove_file(&path).unwrap();
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_cli_parse_version_4() {
-        let cli = Cli::try_parse_from(["zuuid", "-V", "4"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
+    fn test_output_to_fd() {
+        use std::io::Read;
+        use std::os::fd::AsRawFd;
+
+        let (mut reader, writer) = std::io::pipe().unwrap();
+        let fd = writer.as_raw_fd().to_string();
+        let cli = Cli::try_parse_from(["zuuid", "-n", "3", "--fd", &fd]).unwrap();
+        let mut stdout = Vec::new();
+        run(&cli, false, false, &mut stdout, &ENGLISH).unwrap();
+        drop(writer);
+
+        assert!(stdout.is_empty());
+        let mut piped = String::new();
+        reader.read_to_string(&mut piped).unwrap();
+        assert_eq!(piped.lines().filter(|line| Uuid::parse_str(line).is_ok()).count(), 3);
+
+        // A descriptor that is not open is an I/O error, not a crash
+        let cli = Cli::try_parse_from(["zuuid", "--fd", "987654"]).unwrap();
+        assert_eq!(run(&cli, false, false, &mut Vec::new(), &ENGLISH).unwrap_err().exit_code(), 74);
+        assert!(Cli::try_parse_from(["zuuid", "--fd", "-1"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_version_7() {
-        let cli = Cli::try_parse_from(["zuuid", "-V", "7"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
+    fn test_xml_output_counts() {
+        assert_eq!(run_to_string(&["zuuid", "--xml", "-n", "0", "--allow-empty"]).unwrap(), "<uuids>\n</uuids>\n");
+        assert_eq!(
+            run_to_string(&["zuuid", "--xml", "--seed", "42", "--declaration"]).unwrap(),
+            "<?xml version=\"1.0\"?>\n<uuids>\n  <uuid version=\"4\">a2242722-6377-4c86-bd51-ad3f130af08a</uuid>\n</uuids>\n"
+        );
+
+        let output = run_to_string(&["zuuid", "--xml", "-V", "7", "-n", "50", "--root", "ids", "--element", "id"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 52);
+        assert_eq!(lines[0], "<ids>");
+        assert_eq!(lines[51], "</ids>");
+        for line in &lines[1..51] {
+            let text = line.strip_prefix("  <id version=\"7\">").unwrap().strip_suffix("</id>").unwrap();
+            assert!(Uuid::parse_str(text).is_ok());
+        }
     }
 
     #[test]
-    fn test_cli_parse_version_lowercase_v_4() {
-        let cli = Cli::try_parse_from(["zuuid", "-v", "4"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
+    fn test_xml_escaping_and_names() {
+        assert_eq!(xml_escape(r#"a<b>&"c'"#), "a&lt;b&gt;&amp;&quot;c&apos;");
+        assert_eq!(xml_element("uuid", 4, "x&y"), "  <uuid version=\"4\">x&amp;y</uuid>");
+        assert!(parse_xml_name("ids.v2").is_ok());
+        assert!(parse_xml_name("_id").is_ok());
+        assert!(parse_xml_name("2ids").is_err());
+        assert!(parse_xml_name("a b").is_err());
+        assert!(parse_xml_name("XmlThing").is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--root", "ids"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_version_lowercase_v_7() {
-        let cli = Cli::try_parse_from(["zuuid", "-v", "7"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
+    fn test_parse_count_expressions() {
+        assert_eq!(parse_count("10*24").unwrap(), 240);
+        assert_eq!(parse_count("5+5").unwrap(), 10);
+        assert_eq!(parse_count(" 2^10 * 3 ").unwrap(), 3072);
+        // Left to right, no precedence
+        assert_eq!(parse_count("2+3*4").unwrap(), 20);
+        assert_eq!(Cli::try_parse_from(["zuuid", "-n", "10*24"]).unwrap().count, 240);
+
+        let overflow = format!("{}*2", usize::MAX);
+        assert_eq!(parse_count(&overflow).unwrap_err(), Messages::new(Language::English).count_too_large(&overflow));
+        assert!(parse_count(&format!("{}+1", usize::MAX)).is_err());
+        for invalid in ["5+", "*5", "5**5", "5-1", "auto*2"] {
+            assert_eq!(parse_count(invalid).unwrap_err(), Messages::new(Language::English).invalid_count(invalid));
+        }
     }
 
     #[test]
-    fn test_cli_parse_version_long() {
-        let cli = Cli::try_parse_from(["zuuid", "--uuid-version", "7"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
+    fn test_parse_count_auto() {
+        assert_eq!(parse_count_with("auto", || 6).unwrap(), 6);
+        assert_eq!(parse_count_with("AUTO", || 1).unwrap(), 1);
+        assert_eq!(parse_count_with("12", || unreachable!()).unwrap(), 12);
+        assert!(parse_count_with("automatic", || 6).is_err());
+        assert_eq!(parse_count("auto").unwrap(), available_cores());
+        assert!(available_cores() >= 1);
     }
 
     #[test]
-    fn test_cli_parse_version_with_other_options() {
-        let cli = Cli::try_parse_from(["zuuid", "-V", "7", "-U", "-s"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_pg_copy_text() {
+        let output = run_to_string(&["zuuid", "--pg-copy", "-n", "3", "--with-terminator"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[..3].iter().all(|line| Uuid::parse_str(line).is_ok()));
+        assert_eq!(lines[3], "\\.");
+        assert_eq!(run_to_string(&["zuuid", "--pg-copy", "-n", "3"]).unwrap().lines().count(), 3);
+        assert!(Cli::try_parse_from(["zuuid", "--with-terminator"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_version_lowercase_v_with_other_options() {
-        let cli = Cli::try_parse_from(["zuuid", "-v", "7", "-U", "-s"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_pg_copy_binary() {
+        let cli = Cli::try_parse_from([
+            "zuuid", "-V", "5", "--namespace", "dns", "--name", "a.example", "--name", "b.example", "--pg-copy-binary",
+        ])
+        .unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+
+        let a = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"a.example");
+        let b = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"b.example");
+        let mut expected = vec![
+            b'P', b'G', b'C', b'O', b'P', b'Y', 0x0a, 0xff, 0x0d, 0x0a, 0x00, // signature
+            0x00, 0x00, 0x00, 0x00, // flags
+            0x00, 0x00, 0x00, 0x00, // header extension length
+        ];
+        for id in [a, b] {
+            expected.extend([0x00, 0x01, 0x00, 0x00, 0x00, 0x10]); // one field of 16 bytes
+            expected.extend(id.as_bytes());
+        }
+        expected.extend([0xff, 0xff]);
+        assert_eq!(out, expected);
+        assert_eq!(out.len(), 19 + 2 * 22 + 2);
+
+        let cli = Cli::try_parse_from(["zuuid", "-n", "0", "--allow-empty", "--pg-copy-binary"]).unwrap();
+        let mut out = Vec::new();
+        run(&cli, false, false, &mut out, &ENGLISH).unwrap();
+        assert_eq!(out, [PG_COPY_HEADER, PG_COPY_TRAILER].concat());
     }
 
     #[test]
-    fn test_uuid_version_from_str() {
-        assert_eq!("4".parse::<UuidVersion>().unwrap(), UuidVersion::V4);
-        assert_eq!("7".parse::<UuidVersion>().unwrap(), UuidVersion::V7);
-        assert_eq!("v4".parse::<UuidVersion>().unwrap(), UuidVersion::V4);
-        assert_eq!("v7".parse::<UuidVersion>().unwrap(), UuidVersion::V7);
-        assert_eq!("V4".parse::<UuidVersion>().unwrap(), UuidVersion::V4);
-        assert_eq!("V7".parse::<UuidVersion>().unwrap(), UuidVersion::V7);
-        assert!("5".parse::<UuidVersion>().is_err());
-        assert!("invalid".parse::<UuidVersion>().is_err());
+    fn test_with_hash() {
+        use sha2::{Digest, Sha256};
+        let output = run_to_string(&["zuuid", "-n", "3", "--with-hash"]).unwrap();
+        for line in output.lines() {
+            let (id, hash) = line.split_once(' ').unwrap();
+            let id = Uuid::parse_str(id).unwrap();
+            let expected: String = Sha256::digest(id.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(hash, expected);
+        }
+
+        // The hash covers the bytes, not the text, so the output format does not change it
+        assert_eq!(
+            uuid_sha256(&Uuid::nil()),
+            "374708fff7719dd5979ec875d56cd2286f6d3cf7ec317a3b25632aab28ec37bb"
+        );
+        let simple = run_to_string(&["zuuid", "--seed", "7", "-s", "-u", "--with-hash"]).unwrap();
+        let full = run_to_string(&["zuuid", "--seed", "7", "--with-hash"]).unwrap();
+        assert_eq!(simple.split_once(' ').unwrap().1, full.split_once(' ').unwrap().1);
     }
 
     #[test]
-    fn test_cli_parse_combined_flags_us() {
-        let cli = Cli::try_parse_from(["zuuid", "-us"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_no_final_newline() {
+        let output = run_to_string(&["zuuid", "-n", "1", "--no-final-newline"]).unwrap();
+        assert_eq!(output.len(), 36);
+        assert!(Uuid::parse_str(&output).is_ok());
+
+        let output = run_to_string(&["zuuid", "-n", "2", "--no-final-newline", "--crlf"]).unwrap();
+        let lines: Vec<&str> = output.split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| Uuid::parse_str(line).is_ok()));
+
+        // Line endings inside one write are kept, only the last one is held back
+        let mut out = Vec::new();
+        let mut writer = FinalNewlineWriter::new(&mut out, true);
+        writer.write_all(b"a\nb\n").unwrap();
+        writer.write_all(b"c\n").unwrap();
+        drop(writer);
+        assert_eq!(out, b"a\nb\nc");
+
+        assert!(run_to_string(&["zuuid", "-n", "2"]).unwrap().ends_with('\n'));
+        assert!(Cli::try_parse_from(["zuuid", "--no-final-newline", "--checksum"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_combined_flags_u_upper_s() {
-        let cli = Cli::try_parse_from(["zuuid", "-uS"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_comment_annotations() {
+        let v7 = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        assert_eq!(record_comment("#", &v7), "# v7 2024-05-02T16:40:51.244Z");
+        assert_eq!(record_comment("--", &Uuid::parse_str("00000000-0000-4000-8000-000000000000").unwrap()), "-- v4");
+
+        let output = run_to_string(&["zuuid", "-n", "2", "--comment"]).unwrap();
+        for line in output.lines() {
+            let (id, comment) = line.split_once(' ').unwrap();
+            assert_eq!(Uuid::parse_str(id).unwrap().get_version_num(), 4);
+            assert_eq!(comment, "# v4");
+        }
+        let output = run_to_string(&["zuuid", "-V", "7", "--comment", "--comment-prefix", "//"]).unwrap();
+        let (id, comment) = output.trim_end().split_once(' ').unwrap();
+        assert_eq!(comment, record_comment("//", &Uuid::parse_str(id).unwrap()));
+        assert!(comment.starts_with("// v7 20") && comment.ends_with('Z'));
+
+        for structured in ["--xml", "--markdown", "--pg-copy", "--env-file", "--all-versions", "--c-array"] {
+            assert!(Cli::try_parse_from(["zuuid", "--comment", structured]).is_err(), "{}", structured);
+        }
+        assert!(Cli::try_parse_from(["zuuid", "--comment-prefix", ";"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_combined_flags_upper_u_s() {
-        let cli = Cli::try_parse_from(["zuuid", "-Us"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_braced_and_urn_uppercase_only_the_hex() {
+        let id = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        let options = |style| FormatOptions { style, case: Case::Upper };
+        assert_eq!(format_uuid(&id, &options(Style::Braced)), "{018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}");
+        assert_eq!(format_uuid(&id, &options(Style::Urn)), "urn:uuid:018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6");
+        assert_eq!(
+            format_uuid(&id, &FormatOptions { style: Style::Urn, case: Case::Lower }),
+            "urn:uuid:018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"
+        );
+
+        let output = run_to_string(&["zuuid", "--braced", "-U"]).unwrap();
+        let braced = output.trim_end();
+        assert_eq!((braced.len(), braced.chars().next(), braced.chars().last()), (38, Some('{'), Some('}')));
+        assert_eq!(braced, braced.to_uppercase());
+        let output = run_to_string(&["zuuid", "--urn", "-U", "-n", "2"]).unwrap();
+        for line in output.lines() {
+            let hex = line.strip_prefix("urn:uuid:").unwrap();
+            assert_eq!(hex, hex.to_uppercase());
+            assert!(Uuid::parse_str(line).is_ok());
+        }
+
+        let cli = cli_with_config(&["zuuid"], "format = \"urn\"").unwrap();
+        assert_eq!(FormatOptions::from_cli(&cli, false).style, Style::Urn);
+        assert!(Cli::try_parse_from(["zuuid", "--braced", "-s"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--urn", "--braced"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_combined_flags_upper_u_upper_s() {
-        let cli = Cli::try_parse_from(["zuuid", "-US"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V4);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_output_styles_conflict_at_parse_time() {
+        for args in [
+            &["zuuid", "--urn", "--braced"][..],
+            &["zuuid", "--urn", "--short", "8"],
+            &["zuuid", "--braced", "--c-array"],
+            &["zuuid", "--short", "8", "--c-array"],
+            &["zuuid", "--urn", "--encoding", "uuid25"],
+            &["zuuid", "--c-array", "--encoding", "efi"],
+        ] {
+            let err = Cli::try_parse_from(args).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::ArgumentConflict, "{:?}", args);
+        }
+
+        // -f and -s stay a soft conflict, settled by order with a warning
+        assert!(Cli::try_parse_from(["zuuid", "-fs"]).is_ok());
+        assert!(Cli::try_parse_from(["zuuid", "-s", "--short", "8", "-U"]).is_ok());
     }
 
     #[test]
-    fn test_cli_parse_combined_with_version() {
-        let cli = Cli::try_parse_from(["zuuid", "-V7", "-us"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_kafka_murmur2_vectors() {
+        // From Kafka's UtilsTest.testMurmur2, which states them as signed 32-bit values
+        let cases: [(&[u8], i32); 6] = [
+            (b"21", -973932308),
+            (b"foobar", -790332482),
+            (b"a-little-bit-long-string", -985981536),
+            (b"a-little-bit-longer-string", -1486304829),
+            (b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8", -58897971),
+            (b"abc", 479470107),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(kafka_murmur2(key) as i32, expected, "{}", String::from_utf8_lossy(key));
+        }
+        assert_eq!(kafka_partition("foobar", 10), (-790332482i32 & 0x7fff_ffff) as u32 % 10);
     }
 
     #[test]
-    fn test_cli_parse_combined_all_flags() {
-        let cli = Cli::try_parse_from(["zuuid", "-V7", "-U", "-s"]).unwrap();
-        assert_eq!(cli.version, UuidVersion::V7);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
+    fn test_with_partition_and_target() {
+        let output = run_to_string(&["zuuid", "-n", "4000", "--with-partition", "8"]).unwrap();
+        let mut counts = [0; 8];
+        for line in output.lines() {
+            let (id, partition) = line.split_once('\t').unwrap();
+            let partition: u32 = partition.parse().unwrap();
+            assert_eq!(kafka_partition(id, 8), partition);
+            counts[partition as usize] += 1;
+        }
+        // 500 expected per partition; murmur2 spreads random keys evenly
+        assert!(counts.iter().all(|&count| (380..=620).contains(&count)), "{:?}", counts);
+
+        let output = run_to_string(&["zuuid", "-n", "20", "-Us", "--with-partition", "12", "--target-partition", "5"]).unwrap();
+        assert_eq!(output.lines().count(), 20);
+        assert!(output.lines().all(|line| line.ends_with("\t5") && kafka_partition(&line[..32], 12) == 5));
+
+        assert!(matches!(run_to_string(&["zuuid", "--with-partition", "4", "--target-partition", "4"]), Err(AppError::Usage(_))));
+        assert!(Cli::try_parse_from(["zuuid", "--target-partition", "1"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "--with-partition", "0"]).is_err());
+        // The search gives up rather than spinning forever
+        let other = 1 - kafka_partition(&Uuid::nil().to_string(), 2);
+        assert!(find_partition(other, 2, &FormatOptions::default(), || Ok(Uuid::nil()), &ENGLISH).is_err());
     }
 
     #[test]
-    fn test_generate_full_format() {
-        let uuid = generate_uuid(UuidVersion::V4, false, false, true, true);
-        // Full format: lowercase with hyphens (36 chars)
-        assert!(uuid.len() == 36);
-        assert!(uuid.contains('-'));
-        assert!(uuid.chars().filter(|&c| c == '-').count() == 4);
+    fn test_show_variant() {
+        let output = run_to_string(&["zuuid", "-n", "3", "--show-variant", "-U"]).unwrap();
+        for line in output.lines() {
+            let (id, variant) = line.split_once(' ').unwrap();
+            assert_eq!(Uuid::parse_str(id).unwrap().get_version_num(), 4);
+            assert_eq!(variant, "RFC4122");
+        }
+
+        // Read from the bits, not assumed from the version
+        let variant = |hex: &str| variant_label(&Uuid::parse_str(hex).unwrap());
+        assert_eq!(variant("00000000-0000-4000-0000-000000000000"), "NCS");
+        assert_eq!(variant("00000000-0000-4000-8000-000000000000"), "RFC4122");
+        assert_eq!(variant("00000000-0000-4000-c000-000000000000"), "Microsoft");
+        assert_eq!(variant("00000000-0000-4000-e000-000000000000"), "Future");
+
+        let line = run_to_string(&["zuuid", "--seed", "7", "--show-variant", "--with-hash"]).unwrap();
+        assert_eq!(line.split(' ').nth(1), Some("RFC4122"));
+        assert!(Cli::try_parse_from(["zuuid", "--show-variant", "--c-array"]).is_err());
     }
 
     #[test]
-    fn test_generate_full_uppercase_format() {
-        let uuid = generate_uuid(UuidVersion::V4, true, false, true, true);
-        // Full uppercase format
-        assert!(uuid.len() == 36);
-        assert!(uuid.contains('-'));
-        assert!(!uuid.chars().any(|c| c.is_ascii_lowercase()));
+    fn test_rfc4648_round_trip() {
+        let cases = [
+            (Uuid::nil(), "AAAAAAAAAAAAAAAAAAAAAAAAAA", "AAAAAAAAAAAAAAAAAAAAAA", "AAAAAAAAAAAAAAAAAAAAAA"),
+            (Uuid::max(), "77777777777777777777777774", "/////////////////////w", "_____________________w"),
+            (
+                Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap(),
+                "AGHTULQ3FR6T5D2AUGZMHVHF6Y",
+                "AY86LhssfT6PQKGyw9Tl9g",
+                "AY86LhssfT6PQKGyw9Tl9g",
+            ),
+            (Uuid::NAMESPACE_DNS, "NOT3QEE5VUI5DAFUADAE7VBQZA", "a6e4EJ2tEdGAtADAT9QwyA", "a6e4EJ2tEdGAtADAT9QwyA"),
+        ];
+        for (id, base32, base64, base64url) in cases {
+            for (encoding, encoded) in [(Encoding::Base32, base32), (Encoding::Base64, base64), (Encoding::Base64Url, base64url)] {
+                let (alphabet, bits) = encoding.rfc4648_alphabet().unwrap();
+                assert_eq!(encode_rfc4648(&id, alphabet, bits), encoded);
+                assert_eq!(decode_rfc4648(encoded, alphabet, bits), Some(id));
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(4648);
+        for _ in 0..1000 {
+            let id = Uuid::from_u128(rng.random());
+            for (alphabet, bits, len) in [(BASE32_ALPHABET, 5, 26), (BASE64_ALPHABET, 6, 22), (BASE64URL_ALPHABET, 6, 22)] {
+                let encoded = encode_rfc4648(&id, alphabet, bits);
+                assert_eq!(encoded.len(), len);
+                assert_eq!(decode_rfc4648(&encoded, alphabet, bits), Some(id));
+            }
+        }
+
+        // Base32 ignores case, base64 does not; nonzero fill bits, padding and wrong lengths are rejected
+        assert_eq!(decode_rfc4648("agHTULQ3FR6T5D2AUGZMHVHF6Y", BASE32_ALPHABET, 5), Some(cases[2].0));
+        assert_ne!(decode_rfc4648("ay86LhssfT6PQKGyw9Tl9g", BASE64_ALPHABET, 6), Some(cases[2].0));
+        assert_eq!(decode_rfc4648("77777777777777777777777777", BASE32_ALPHABET, 5), None);
+        assert_eq!(decode_rfc4648("/////////////////////w==", BASE64_ALPHABET, 6), None);
+        assert_eq!(decode_rfc4648("_____________________w", BASE64_ALPHABET, 6), None);
+
+        let output = run_to_string(&["zuuid", "--encoding", "base64url", "-n", "3"]).unwrap();
+        assert!(output.lines().all(|line| decode_rfc4648(line, BASE64URL_ALPHABET, 6).is_some()));
+        let output = run_to_string(&["zuuid", "convert", "--to", "base32", "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"]).unwrap();
+        assert_eq!(output, "AGHTULQ3FR6T5D2AUGZMHVHF6Y\n");
+        let output = run_to_string(&["zuuid", "convert", "--from", "base64", "AY86LhssfT6PQKGyw9Tl9g"]).unwrap();
+        assert_eq!(output, "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6\n");
     }
 
     #[test]
-    fn test_conflict_simple_wins() {
-        let uuid = generate_uuid(UuidVersion::V4, false, true, true, false);
-        // When prefer_full is false, simple wins
-        assert!(uuid.len() == 32);
-        assert!(!uuid.contains('-'));
+    fn test_wrap_concatenated_base64() {
+        let lines = run_to_string(&["zuuid", "--encoding", "base64", "-n", "20", "--seed", "5"]).unwrap();
+        let wrapped = run_to_string(&["zuuid", "--encoding", "base64", "-n", "20", "--seed", "5", "--wrap", "76"]).unwrap();
+        assert!(wrapped.lines().all(|line| line.len() <= 76));
+        assert_eq!(wrapped.lines().count(), 6);
+        assert_eq!(wrapped.lines().collect::<String>(), lines.lines().collect::<String>());
+
+        let unwrapped = run_to_string(&["zuuid", "--encoding", "base32", "-n", "3", "--wrap", "0"]).unwrap();
+        assert_eq!(unwrapped.lines().map(str::len).collect::<Vec<_>>(), [78]);
+        assert_eq!(wrap_concatenated(&["abc", "def"], 4), ["abcd", "ef"]);
+        assert!(wrap_concatenated(&[], 4).is_empty());
+
+        let err = run_to_string(&["zuuid", "--wrap", "76"]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert_eq!(err.message(), Messages::new(Language::English).wrap_not_supported(Encoding::Hex));
+        assert!(Cli::try_parse_from(["zuuid", "--encoding", "base64", "--wrap", "76", "--json"]).is_err());
     }
 
     #[test]
-    fn test_conflict_full_wins() {
-        let uuid = generate_uuid(UuidVersion::V4, false, true, true, true);
-        // When prefer_full is true, full wins
-        assert!(uuid.len() == 36);
-        assert!(uuid.contains('-'));
+    fn test_uuid25_round_trip() {
+        let cases = [
+            (Uuid::nil(), "0000000000000000000000000"),
+            (Uuid::max(), "f5lxx1zz5pnorynqglhzmsp33"),
+            (Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap(), "03bnioid40h3dv2if0wwo0fee"),
+            (Uuid::NAMESPACE_DNS, "6dfzh5ik5uxynzmlf5cshamdk"),
+        ];
+        for (id, encoded) in cases {
+            assert_eq!(encode_uuid25(&id, Case::Lower), encoded);
+            assert_eq!(decode_uuid25(encoded), Some(id));
+            assert_eq!(decode_uuid25(&encoded.to_uppercase()), Some(id));
+        }
+
+        let mut rng = StdRng::seed_from_u64(25);
+        for _ in 0..1000 {
+            let id = Uuid::from_u128(rng.random());
+            let encoded = encode_uuid25(&id, Case::Upper);
+            assert_eq!(encoded.len(), 25);
+            assert!(encoded.bytes().all(|b| b.is_ascii_alphanumeric()));
+            assert_eq!(decode_uuid25(&encoded), Some(id));
+        }
+
+        // One past the largest UUID, wrong length, or not base 36
+        assert_eq!(decode_uuid25("f5lxx1zz5pnorynqglhzmsp34"), None);
+        assert_eq!(decode_uuid25("zzzzzzzzzzzzzzzzzzzzzzzzz"), None);
+        assert_eq!(decode_uuid25("000000000000000000000000"), None);
+        assert_eq!(decode_uuid25("000000000000000000000000-"), None);
+
+        let output = run_to_string(&["zuuid", "--encoding", "uuid25", "-n", "3", "-U"]).unwrap();
+        assert!(output.lines().all(|line| line.len() == 25 && decode_uuid25(line).is_some()));
+        let output = run_to_string(&["zuuid", "convert", "--to", "uuid25", "{018F3A2E-1B2C-7D3E-8F40-A1B2C3D4E5F6}"]).unwrap();
+        assert_eq!(output, "03bnioid40h3dv2if0wwo0fee\n");
+        let output = run_to_string(&["zuuid", "convert", "--from", "uuid25", "0000000000000000000000000"]).unwrap();
+        assert_eq!(output, "00000000-0000-0000-0000-000000000000\n");
+        let err = run_to_string(&["zuuid", "convert", "--from", "uuid25", "nope"]).unwrap_err();
+        assert_eq!(err.message(), "Invalid value #1 for UUID: \"nope\": not a valid uuid25 UUID");
     }
 
     #[test]
-    fn test_cli_parse_full_short() {
-        let cli = Cli::try_parse_from(["zuuid", "-f"]).unwrap();
-        assert!(cli.full);
-        assert!(!cli.simple);
+    fn test_efi_guid_round_trip() {
+        // EFI_GLOBAL_VARIABLE from the UEFI specification
+        const EFI: &str = "{ 0x8BE4DF61, 0x93CA, 0x11D2, { 0xAA, 0x0D, 0x00, 0xE0, 0x98, 0x03, 0x2B, 0x8C }}";
+        let id = Uuid::parse_str("8be4df61-93ca-11d2-aa0d-00e098032b8c").unwrap();
+        assert_eq!(efi_guid(&id), EFI);
+        assert_eq!(parse_efi_guid(EFI), Some(id));
+        assert_eq!(parse_efi_guid("{0x8be4df61,0x93ca,0x11d2,\n  {0xaa,0x0d,0x00,0xe0,0x98,0x03,0x2b,0x8c}}"), Some(id));
+        assert_eq!(efi_guid(&Uuid::nil()).matches("0x").count(), 11);
+        assert_eq!(parse_efi_guid(&efi_guid(&Uuid::max())), Some(Uuid::max()));
+
+        assert_eq!(parse_efi_guid("{ 0x8BE4DF61, 0x93CA, 0x11D2, { 0xAA, 0x0D, 0x00, 0xE0, 0x98, 0x03, 0x2B }}"), None);
+        assert_eq!(parse_efi_guid("{ 0x8BE4DF611, 0x93CA, 0x11D2, { 0xAA, 0x0D, 0x00, 0xE0, 0x98, 0x03, 0x2B, 0x8C }}"), None);
+        assert_eq!(parse_efi_guid("8be4df61-93ca-11d2-aa0d-00e098032b8c"), None);
+
+        let output = run_to_string(&["zuuid", "convert", "--from", "efi", EFI]).unwrap();
+        assert_eq!(output, "8be4df61-93ca-11d2-aa0d-00e098032b8c\n");
+        let output = run_to_string(&["zuuid", "convert", "--to", "efi", "8be4df61-93ca-11d2-aa0d-00e098032b8c"]).unwrap();
+        assert_eq!(output, format!("{}\n", EFI));
+        let output = run_to_string(&["zuuid", "--encoding", "efi", "-V", "5", "--namespace", "dns", "--name", "x"]).unwrap();
+        assert_eq!(output, format!("{}\n", efi_guid(&Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"x"))));
     }
 
     #[test]
-    fn test_cli_parse_full_short_upper() {
-        let cli = Cli::try_parse_from(["zuuid", "-F"]).unwrap();
-        assert!(cli.full);
-        assert!(!cli.simple);
+    fn test_oid_round_trip() {
+        // The example from ITU-T X.667
+        const OID: &str = "2.25.329800735698586629295641978511506172918";
+        let id = Uuid::parse_str("f81d4fae-7dec-11d0-a765-00a0c91e6bf6").unwrap();
+        assert_eq!(uuid_oid(&id), OID);
+        assert_eq!(parse_uuid_oid(OID), Some(id));
+        assert_eq!(uuid_oid(&Uuid::nil()), "2.25.0");
+        assert_eq!(parse_uuid_oid("2.25.0"), Some(Uuid::nil()));
+        assert_eq!(parse_uuid_oid(&uuid_oid(&Uuid::max())), Some(Uuid::max()));
+
+        // One past the largest UUID, a leading zero, another arc, or no number at all
+        for bad in ["2.25.340282366920938463463374607431768211456", "2.25.01", "1.25.1", "2.25.", "2.25.+1", "2.25.1.2", "f81d4fae"] {
+            assert_eq!(parse_uuid_oid(bad), None, "{}", bad);
+        }
+
+        let output = run_to_string(&["zuuid", "convert", "--from", "oid", OID]).unwrap();
+        assert_eq!(output, "f81d4fae-7dec-11d0-a765-00a0c91e6bf6\n");
+        let output = run_to_string(&["zuuid", "convert", "--to", "oid", "F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6"]).unwrap();
+        assert_eq!(output, format!("{}\n", OID));
+        let err = run_to_string(&["zuuid", "convert", "--from", "oid", "2.25.01"]).unwrap_err();
+        assert_eq!(err.message(), "Invalid value #1 for UUID: \"2.25.01\": not a valid oid UUID");
+        let output = run_to_string(&["zuuid", "--encoding", "oid", "-n", "3"]).unwrap();
+        assert!(output.lines().all(|line| parse_uuid_oid(line).is_some_and(|id| id.get_version_num() == 4)));
     }
 
     #[test]
-    fn test_cli_parse_full_long() {
-        let cli = Cli::try_parse_from(["zuuid", "--full"]).unwrap();
-        assert!(cli.full);
-        assert!(!cli.simple);
+    fn test_convert_raw_records() {
+        let convert_bytes = |extra: &[&str], input: &[u8]| {
+            let mut args = vec!["zuuid", "convert"];
+            args.extend(extra);
+            let Some(Command::Convert(args)) = Cli::try_parse_from(&args).unwrap().command else { panic!("not convert") };
+            let mut out = Vec::new();
+            let result = convert(&args, input, "\n", &mut out, &ENGLISH);
+            (result, out)
+        };
+        let ids: Vec<Uuid> = (1..=4u128).map(|n| Uuid::from_u128(n << 64 | n)).collect();
+        let dump: Vec<u8> = ids.iter().flat_map(|id| *id.as_bytes()).collect();
+        let text = |ids: &[Uuid]| ids.iter().map(|id| format!("{}\n", id)).collect::<String>();
+
+        let (result, out) = convert_bytes(&["--from", "raw", "--binary-in"], &dump);
+        result.unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text(&ids));
+        assert_eq!(text(&ids).lines().next(), Some("00000000-0000-0001-0000-000000000001"));
+
+        let (result, out) = convert_bytes(&["--from", "raw", "--binary-in", "--skip", "1", "--take", "2"], &dump);
+        result.unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text(&ids[1..3]));
+
+        // A partial record fails after the whole ones are written, unless --lenient drops it
+        let mut truncated = dump.clone();
+        truncated.extend([0xab; 5]);
+        let (result, out) = convert_bytes(&["--from", "raw", "--binary-in"], &truncated);
+        let err = result.unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(err.message(), "Trailing partial record: 5 of 16 bytes at offset 64");
+        assert_eq!(String::from_utf8(out).unwrap(), text(&ids));
+        let (result, out) = convert_bytes(&["--from", "raw", "--binary-in", "--lenient"], &truncated);
+        result.unwrap();
+        assert_eq!(out.len(), 4 * 37);
+
+        // And back: text to raw gives the original bytes
+        let hex: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let mut args = vec!["--to", "raw", "--binary-out"];
+        args.extend(hex.iter().map(String::as_str));
+        let (result, out) = convert_bytes(&args, &[]);
+        result.unwrap();
+        assert_eq!(out, dump);
+
+        let (result, _) = convert_bytes(&["--from", "raw", "--binary-in", "--to", "raw"], &dump);
+        assert_eq!(result.unwrap_err().exit_code(), 2);
+        assert!(run_to_string(&["zuuid", "--encoding", "raw"]).is_err());
+        assert!(Cli::try_parse_from(["zuuid", "convert", "--binary-in", "x"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_full_with_uppercase() {
-        let cli = Cli::try_parse_from(["zuuid", "-f", "-U"]).unwrap();
-        assert!(cli.full);
-        assert!(cli.uppercase);
+    fn test_check_digit_vectors() {
+        let id = |hex: &str| Uuid::parse_str(hex).unwrap();
+        let vectors = [
+            ("00000000-0000-0000-0000-000000000000", '0'),
+            ("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", 'P'),
+            ("6ba7b810-9dad-11d1-80b4-00c04fd430c8", 'D'),
+            ("ffffffff-ffff-ffff-ffff-ffffffffffff", '*'),
+        ];
+        for (hex, symbol) in vectors {
+            assert_eq!(check_symbol(&id(hex)), symbol, "{}", hex);
+        }
+
+        let v7 = id("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6");
+        assert_eq!(with_check_digit(&v7, &FormatOptions::default()), "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6-p");
+        assert_eq!(with_check_digit(&v7, &UPPER_SIMPLE), "018F3A2E1B2C7D3E8F40A1B2C3D4E5F6*P");
+        assert_eq!(strip_check_digit("018F3A2E1B2C7D3E8F40A1B2C3D4E5F6*p", &ENGLISH), Ok(v7));
+
+        // Swapping the last two hex digits (f6 -> 6f) is caught
+        assert_eq!(check_symbol(&id("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e56f")), '=');
+        let err = strip_check_digit("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e56f-P", &ENGLISH).unwrap_err();
+        assert_eq!(err, "Check digit does not match: 018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e56f-P (expected =)");
+        assert!(strip_check_digit("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", &ENGLISH).is_err());
+
+        // Every generated line verifies through decode
+        let output = run_to_string(&["zuuid", "-n", "20", "--check-digit"]).unwrap();
+        let mut args = vec!["zuuid", "decode", "--expect-check-digit", "--canonical-only"];
+        args.extend(output.lines());
+        let canonical = run_to_string(&args).unwrap();
+        let expected: Vec<&str> = output.lines().map(|line| &line[..36]).collect();
+        assert_eq!(canonical.lines().collect::<Vec<_>>(), expected);
+        assert!(matches!(
+            run_to_string(&["zuuid", "decode", "--expect-check-digit", "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6-Q"]),
+            Err(AppError::Invalid(_))
+        ));
     }
 
     #[test]
-    fn test_cli_parse_combined_full_flags() {
-        let cli = Cli::try_parse_from(["zuuid", "-fU"]).unwrap();
-        assert!(cli.full);
-        assert!(cli.uppercase);
+    fn test_snippets_for_nil() {
+        let nil = "00000000-0000-0000-0000-000000000000";
+        let cases = [
+            ("python", format!("uuid.UUID(\"{}\")", nil)),
+            ("go", format!("uuid.MustParse(\"{}\")", nil)),
+            ("java", format!("UUID.fromString(\"{}\")", nil)),
+            ("csharp", format!("new Guid(\"{}\")", nil)),
+            ("kotlin", format!("UUID.fromString(\"{}\")", nil)),
+            ("swift", format!("UUID(uuidString: \"{}\")!", nil)),
+            ("typescript", format!("\"{}\" as UUID", nil)),
+        ];
+        assert_eq!(cases.len(), SNIPPETS.len());
+        for (language, expected) in cases {
+            assert_eq!(snippet(&parse_snippet_language(language).unwrap(), nil), expected);
+        }
+        assert_eq!(parse_snippet_language("Python").unwrap(), "python");
+        assert!(parse_snippet_language("cobol").is_err());
     }
 
     #[test]
-    fn test_cli_parse_full_uppercase_u_with_full() {
-        let cli = Cli::try_parse_from(["zuuid", "-Uf"]).unwrap();
-        assert!(cli.full);
-        assert!(cli.uppercase);
+    fn test_snippet_output_per_line() {
+        let output = run_to_string(&["zuuid", "-n", "3", "--snippet", "go"]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            let id = line.strip_prefix("uuid.MustParse(\"").unwrap().strip_suffix("\")").unwrap();
+            assert_eq!(id.len(), 36);
+        }
+        assert!(Cli::try_parse_from(["zuuid", "--snippet", "go", "-s"]).is_err());
     }
 
     #[test]
-    fn test_cli_parse_default_count() {
-        let cli = Cli::try_parse_from(["zuuid"]).unwrap();
-        assert_eq!(cli.count, 1);
+    fn test_exit_codes_by_error_kind() {
+        // parse_args stops before load_config, so a developer's own config file cannot change the result
+        let parse = |args: &[&str]| parse_args(&to_args(args), &ENGLISH).err().unwrap();
+
+        // clap's own errors are returned to main instead of exiting inside the parser
+        assert_eq!(parse(&["zuuid", "--no-such-flag"]).exit_code(), 2);
+        assert_eq!(parse(&["zuuid", "-s", "--per-line", "-n", "2"]).exit_code(), 2);
+        assert_eq!(parse(&["zuuid", "-V", "9"]).exit_code(), 4);
+        assert_eq!(parse(&["zuuid", "-V", "9"]).code(), "invalid");
+        assert_eq!(parse(&["zuuid", "--help"]).exit_code(), 0);
+        assert_eq!(parse(&["zuuid", "--no-such-flag"]).code(), "usage");
+
+        assert_eq!(AppError::Io(String::new()).exit_code(), 74);
+        assert_eq!(AppError::Failure(String::new()).exit_code(), 1);
+        assert_eq!(AppError::Closed.exit_code(), 0);
+
+        // --porcelain's help promises these codes to scripts, so it must list the ones actually used
+        let help = Cli::command().render_long_help().to_string();
+        let help = help.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(help.contains("exit codes 0 (success), 1 (generation failure), 2 (usage), 74 (I/O), 4 (invalid value)"), "{}", help);
     }
 
+    const INSPECT_INPUT: &str = "018F3A2E1B2C7D3E8F40A1B2C3D4E5F6\n\nnot-a-uuid\n67e55044-10b1-426f-9247-bb680e5fe0c8\n";
+
     #[test]
-    fn test_cli_parse_count_short() {
-        let cli = Cli::try_parse_from(["zuuid", "-n", "5"]).unwrap();
-        assert_eq!(cli.count, 5);
+    fn test_inspect_rows_mixed_input() {
+        let rows = inspect_rows(std::io::Cursor::new(INSPECT_INPUT), &ENGLISH).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].canonical.as_deref(), Some("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6"));
+        assert_eq!(rows[0].version, Some("7"));
+        assert_eq!(rows[0].timestamp.as_deref(), Some("2024-05-02T16:40:51.244Z"));
+        assert!(!rows[1].valid);
+        assert_eq!(rows[1].input, "not-a-uuid");
+        assert_eq!(rows[2].timestamp, None);
     }
 
     #[test]
-    fn test_cli_parse_count_long() {
-        let cli = Cli::try_parse_from(["zuuid", "--count", "10"]).unwrap();
-        assert_eq!(cli.count, 10);
+    fn test_inspect_pre_epoch_v1() {
+        // A v1/v6 time before 1970 must not wrap around into the far future
+        let rows = inspect_rows(std::io::Cursor::new("00000000-0000-1000-8000-000000000000\n"), &ENGLISH).unwrap();
+        assert_eq!(rows[0].timestamp.as_deref(), Some("1582-10-15T00:00:00.000Z"));
+        let positional = run_to_string(&["zuuid", "00000000-0000-1000-8000-000000000000"]).unwrap();
+        assert_eq!(positional, "1\trfc9562\t1582-10-15T00:00:00.000Z\t00000000-0000-1000-8000-000000000000\n");
+        assert_eq!(format_rfc3339_millis(-1), "1969-12-31T23:59:59.999Z");
     }
 
     #[test]
-    fn test_cli_parse_count_with_other_options() {
-        let cli = Cli::try_parse_from(["zuuid", "-n", "3", "-U", "-s", "-V", "7"]).unwrap();
-        assert_eq!(cli.count, 3);
-        assert!(cli.uppercase);
-        assert!(cli.simple);
-        assert_eq!(cli.version, UuidVersion::V7);
+    fn test_inspect_lines_tty_and_pipe() {
+        let msgs = Messages::new(Language::English);
+        let rows = inspect_rows(std::io::Cursor::new(INSPECT_INPUT), &ENGLISH).unwrap();
+        let invalid = msgs.invalid_label();
+
+        let tsv = inspect_lines(&rows, false, false, None, &ENGLISH).unwrap();
+        assert_eq!(
+            tsv,
+            [
+                "7\trfc9562\t2024-05-02T16:40:51.244Z\t018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6".to_string(),
+                format!("{}\t-\t-\tnot-a-uuid", invalid),
+                "4\trfc9562\t-\t67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+            ]
+        );
+
+        let table = inspect_lines(&rows, true, false, None, &ENGLISH).unwrap();
+        assert_eq!(table.len(), 4);
+        // The UUID column starts at the same offset on every row
+        let last_column = |line: &str| line[..line.rfind(' ').unwrap() + 1].chars().count();
+        for line in &table[1..] {
+            assert_eq!(last_column(line), last_column(&table[0]));
+        }
+
+        let colored = inspect_lines(&rows, true, true, None, &ENGLISH).unwrap();
+        assert!(colored[2].starts_with("\x1b[31m") && colored[2].ends_with("\x1b[0m"));
+        assert!(!colored[1].contains('\x1b'));
+    }
+
+    #[test]
+    fn test_inspect_json_schema() {
+        let rows = inspect_rows(std::io::Cursor::new(INSPECT_INPUT), &ENGLISH).unwrap();
+        let json = inspect_lines(&rows, true, true, Some(JsonLayout::Compact), &ENGLISH).unwrap();
+        assert_eq!(json.len(), 1);
+        let value: serde_json::Value = serde_json::from_str(&json[0]).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {
+                    "input": "018F3A2E1B2C7D3E8F40A1B2C3D4E5F6",
+                    "valid": true,
+                    "canonical": "018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6",
+                    "version": "7",
+                    "variant": "rfc9562",
+                    "timestamp": "2024-05-02T16:40:51.244Z"
+                },
+                { "input": "not-a-uuid", "valid": false },
+                {
+                    "input": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                    "valid": true,
+                    "canonical": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                    "version": "4",
+                    "variant": "rfc9562"
+                }
+            ])
+        );
+
+        assert!(Cli::try_parse_from(["zuuid", "inspect", "--stdin"]).is_ok());
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--stdin", "--canonical-only"]).is_err());
+    }
+
+    #[test]
+    fn test_json_pretty_round_trips() {
+        let rows = inspect_rows(std::io::Cursor::new(INSPECT_INPUT), &ENGLISH).unwrap();
+        let compact = inspect_lines(&rows, true, true, Some(JsonLayout::Compact), &ENGLISH).unwrap();
+        let pretty = inspect_lines(&rows, true, true, Some(JsonLayout::Pretty), &ENGLISH).unwrap();
+        assert_eq!(pretty.len(), rows.len() + 2);
+        assert!(pretty[1].starts_with("  {\"input\":\"018F3A2E1B2C7D3E8F40A1B2C3D4E5F6\""));
+        let text = pretty.join("\n");
+        assert!(text.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value, serde_json::from_str::<serde_json::Value>(&compact[0]).unwrap());
+
+        assert_eq!(json_array_lines::<InspectRow>(&[], JsonLayout::Pretty).unwrap(), ["[]"]);
+        let stats = uuid_stats(std::io::Cursor::new(INSPECT_INPUT), None, &ENGLISH).unwrap();
+        let pretty_stats = stats_lines(&stats, Some(JsonLayout::Pretty), &ENGLISH).unwrap().join("\n");
+        assert!(pretty_stats.contains("\n  \"total\": 3"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&pretty_stats).unwrap()["invalid"], 1);
+
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--stdin", "--json-pretty"]).is_ok());
+        assert!(Cli::try_parse_from(["zuuid", "bench", "--json-pretty"]).is_ok());
+        assert!(Cli::try_parse_from(["zuuid", "decode", "--dump", "--json-pretty", "nil"]).is_err());
+    }
+
+    #[test]
+    fn test_hex_dump_plain_snapshot() {
+        let v7 = Uuid::parse_str("018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6").unwrap();
+        let dump = hex_dump(&v7, false, &ENGLISH);
+        assert_eq!(dump[..2], ["00  <01 8f 3a 2e 1b 2c> [7]d 3e", "08  {8}f 40 a1 b2 c3 d4 e5 f6"]);
+        assert_eq!(dump[2..], Messages::new(Language::English).dump_legend(&v7, "10", false));
+        assert_eq!(
+            Messages::new(Language::English).dump_legend(&v7, "10", false),
+            ["[x] version nibble: 7", "{x} variant bits: 10 (RFC 9562)", "<..> timestamp: 48-bit Unix milliseconds"]
+        );
+
+        // No timestamp marker outside v7; Microsoft GUIDs have three variant bits
+        let v4 = Uuid::parse_str("67e55044-10b1-426f-c247-bb680e5fe0c8").unwrap();
+        let dump = hex_dump(&v4, false, &ENGLISH);
+        assert_eq!(dump[..2], ["00  67 e5 50 44 10 b1 [4]2 6f", "08  {c}2 47 bb 68 0e 5f e0 c8"]);
+        assert_eq!(dump.len(), 4);
+        assert!(dump[3].contains("110"));
+
+        let colored = hex_dump(&v7, true, &ENGLISH);
+        assert!(colored[0].contains("\x1b[35m7\x1b[0m") && !colored[0].contains("[7]") && !colored[0].contains('<'));
+
+        let both = ["018f3a2e-1b2c-7d3e-8f40-a1b2c3d4e5f6", "67e55044-10b1-426f-9247-bb680e5fe0c8"];
+        let args = DecodeArgs { dump: true, ..decode_args(&both, false) };
+        let lines = decode_lines(&args, false, &ENGLISH).unwrap();
+        assert_eq!(lines[5], "");
+        assert_eq!(lines[6], "00  67 e5 50 44 10 b1 [4]2 6f");
+    }
+
+    #[test]
+    fn test_shuffle_is_reproducible_with_seed() {
+        let shuffled = run_to_string(&["zuuid", "-n", "50", "--seed", "9", "--shuffle"]).unwrap();
+        let again = run_to_string(&["zuuid", "-n", "50", "--seed", "9", "--shuffle"]).unwrap();
+        assert_eq!(shuffled, again);
+
+        // Every UUID of the batch is kept; only the order changes
+        let unshuffled = run_to_string(&["zuuid", "-n", "50", "--seed", "9"]).unwrap();
+        assert_ne!(shuffled, unshuffled);
+        let mut lines: Vec<&str> = shuffled.lines().collect();
+        let mut expected: Vec<&str> = unshuffled.lines().collect();
+        lines.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(lines, expected);
+
+        let names = ["zuuid", "-V", "5", "--namespace", "dns", "--name", "a", "--name", "b", "--name", "c", "--seed", "1"];
+        let shuffled = run_to_string(&[&names[..], &["--shuffle"]].concat()).unwrap();
+        let mut lines: Vec<&str> = shuffled.lines().collect();
+        lines.sort_unstable();
+        assert_eq!(lines.join("\n") + "\n", run_to_string(&[&names[..], &["--sort"]].concat()).unwrap());
+        assert!(Cli::try_parse_from(["zuuid", "--shuffle", "--sort"]).is_err());
+    }
+
+    #[test]
+    fn test_uuid_version_value_enum() {
+        for args in [&["zuuid", "-V7"][..], &["zuuid", "-v", "V7"], &["zuuid", "--uuid-version=v7"]] {
+            assert_eq!(Cli::try_parse_from(args).unwrap().version, UuidVersion::V7);
+        }
+
+        let err = Cli::try_parse_from(["zuuid", "-V", "6"]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+        assert!(err.to_string().contains("[possible values: 2, 3, 4, 5, 7]"));
+
+        let help = Cli::command().render_long_help().to_string();
+        assert!(help.contains("Version 7: Time-ordered UUID"));
+
+        // The Chinese message rides along as a tip; English output stays clap's own
+        let english = localize_clap_error(Cli::try_parse_from(["zuuid", "-V", "6"]).err().unwrap(), Language::English);
+        assert!(english.get(ContextKind::Suggested).is_none());
+        let chinese = localize_clap_error(err, Language::Chinese);
+        assert!(chinese.to_string().contains("tip: 无效的 UUID 版本：6"));
     }
 }